@@ -1,5 +1,5 @@
 use criterion::{Criterion, black_box, criterion_group, criterion_main};
-use dxgi_capture_rs::DXGIManager;
+use dxgi_capture_rs::{DXGIManager, MetadataFlags};
 
 fn bench_capture_frame(c: &mut Criterion) {
     let mut manager = match DXGIManager::new(1000) {
@@ -15,6 +15,33 @@ fn bench_capture_frame(c: &mut Criterion) {
     });
 }
 
+fn bench_capture_frame_lazy_release(c: &mut Criterion) {
+    let mut manager = match DXGIManager::builder().timeout_ms(1000).build() {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+
+    let mut group = c.benchmark_group("capture_frame_lazy_release");
+
+    group.bench_function("release_after_copy", |b| {
+        manager.set_lazy_release(false);
+        b.iter(|| {
+            let result = manager.capture_frame();
+            black_box(result)
+        })
+    });
+
+    group.bench_function("release_before_acquire", |b| {
+        manager.set_lazy_release(true);
+        b.iter(|| {
+            let result = manager.capture_frame();
+            black_box(result)
+        })
+    });
+
+    group.finish();
+}
+
 fn bench_capture_frame_components(c: &mut Criterion) {
     let mut manager = match DXGIManager::new(1000) {
         Ok(m) => m,
@@ -29,6 +56,20 @@ fn bench_capture_frame_components(c: &mut Criterion) {
     });
 }
 
+fn bench_capture_frame_scaled(c: &mut Criterion) {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+
+    c.bench_function("capture_frame_scaled", |b| {
+        b.iter(|| {
+            let result = manager.capture_frame_scaled(0.25);
+            black_box(result)
+        })
+    });
+}
+
 fn bench_geometry(c: &mut Criterion) {
     let manager = match DXGIManager::new(1000) {
         Ok(m) => m,
@@ -88,10 +129,7 @@ fn bench_capture_source_setting(c: &mut Criterion) {
     };
 
     c.bench_function("capture_source_setting", |b| {
-        b.iter(|| {
-            manager.set_capture_source_index(0);
-            black_box(())
-        })
+        b.iter(|| black_box(manager.set_capture_source_index(0)))
     });
 }
 
@@ -217,14 +255,23 @@ fn bench_metadata_vs_regular_capture(c: &mut Criterion) {
         })
     });
 
+    group.bench_function("pointer_only_metadata_capture", |b| {
+        b.iter(|| {
+            let result = manager.capture_frame_with_metadata_opts(MetadataFlags::POINTER);
+            black_box(result)
+        })
+    });
+
     group.finish();
 }
 
 criterion_group!(
     benches,
     bench_capture_frame,
+    bench_capture_frame_lazy_release,
     bench_capture_frame_components,
     bench_capture_frame_fast,
+    bench_capture_frame_scaled,
     bench_geometry,
     bench_manager_creation,
     bench_timeout_operations,
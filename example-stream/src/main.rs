@@ -1,5 +1,3 @@
-mod simd_utils;
-
 use dxgi_capture_rs::{CaptureError, DXGIManager};
 use eframe::egui;
 use egui::{ColorImage, TextureHandle};
@@ -147,11 +145,8 @@ fn capture_thread_main(sender: mpsc::Sender<RenderableFrame>, ctx: egui::Context
     let mut last_reported_fps = 0.0;
 
     loop {
-        match manager.capture_frame_components() {
-            Ok((components, (width, height))) => {
-                let mut rgba_pixels = components;
-                simd_utils::bgra_to_rgba(&mut rgba_pixels);
-
+        match manager.capture_frame_rgba() {
+            Ok((rgba_pixels, (width, height))) => {
                 let image = ColorImage::from_rgba_unmultiplied([width, height], &rgba_pixels);
 
                 frame_count += 1;
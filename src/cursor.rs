@@ -0,0 +1,105 @@
+//! Compositing of the mouse pointer shape into captured BGRA frames, following
+//! the blending rules of the three DXGI pointer shape types.
+
+use crate::PixelOrder;
+use windows::Win32::Graphics::Dxgi::{
+    DXGI_OUTDUPL_POINTER_SHAPE_INFO, DXGI_OUTDUPL_POINTER_SHAPE_TYPE_COLOR,
+    DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MASKED_COLOR, DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MONOCHROME,
+};
+
+/// A pointer shape as returned by `GetFramePointerShape`.
+pub(crate) struct PointerShape {
+    pub(crate) info: DXGI_OUTDUPL_POINTER_SHAPE_INFO,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Draws `shape` with its top-left corner at `position` into `frame`, a tightly
+/// packed `width` x `height` image in `order`. If `flip` is set, the rows of
+/// `frame` run bottom to top and the shape is drawn flipped to match. Parts
+/// outside the frame are clipped.
+pub(crate) fn composite(
+    frame: &mut [u8],
+    width: usize,
+    height: usize,
+    order: PixelOrder,
+    flip: bool,
+    shape: &PointerShape,
+    position: (i32, i32),
+) {
+    let info = &shape.info;
+    let shape_type = info.Type as i32;
+    let pitch = info.Pitch as usize;
+    let bytes_per_row = if shape_type == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MONOCHROME.0 {
+        (info.Width as usize).div_ceil(8)
+    } else {
+        info.Width as usize * 4
+    };
+    if pitch < bytes_per_row || shape.data.len() < pitch * info.Height as usize {
+        return;
+    }
+    // Monochrome shapes stack an AND mask on top of an XOR mask
+    let shape_height = if shape_type == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MONOCHROME.0 {
+        info.Height as usize / 2
+    } else {
+        info.Height as usize
+    };
+
+    for shape_y in 0..shape_height {
+        let frame_y = position.1 + shape_y as i32;
+        if frame_y < 0 || frame_y >= height as i32 {
+            continue;
+        }
+        for shape_x in 0..info.Width as usize {
+            let frame_x = position.0 + shape_x as i32;
+            if frame_x < 0 || frame_x >= width as i32 {
+                continue;
+            }
+            let row = if flip {
+                height - 1 - frame_y as usize
+            } else {
+                frame_y as usize
+            };
+            let index = (row * width + frame_x as usize) * 4;
+            let dest = &mut frame[index..index + 3];
+
+            if shape_type == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MONOCHROME.0 {
+                let byte = shape_x / 8;
+                let bit = 0x80 >> (shape_x % 8);
+                let and = shape.data[shape_y * pitch + byte] & bit != 0;
+                let xor = shape.data[(shape_y + shape_height) * pitch + byte] & bit != 0;
+                for channel in dest.iter_mut() {
+                    if !and {
+                        *channel = 0;
+                    }
+                    if xor {
+                        *channel ^= 0xff;
+                    }
+                }
+                continue;
+            }
+
+            let source = &shape.data[shape_y * pitch + shape_x * 4..][..4];
+            // Pointer shapes are BGRA
+            let colors = match order {
+                PixelOrder::Bgra => [source[0], source[1], source[2]],
+                PixelOrder::Rgba => [source[2], source[1], source[0]],
+            };
+            if shape_type == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_COLOR.0 {
+                let alpha = source[3] as u32;
+                for (channel, &color) in dest.iter_mut().zip(&colors) {
+                    *channel = ((color as u32 * alpha + *channel as u32 * (255 - alpha) + 127)
+                        / 255) as u8;
+                }
+            } else if shape_type == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MASKED_COLOR.0 {
+                // A zero mask replaces the pixel, otherwise the color is XORed in
+                for (channel, &color) in dest.iter_mut().zip(&colors) {
+                    if source[3] == 0 {
+                        *channel = color;
+                    } else {
+                        *channel ^= color;
+                    }
+                }
+            }
+        }
+    }
+}
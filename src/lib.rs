@@ -7,7 +7,7 @@
 //!
 //! - **High Performance**: Direct access to DXGI Desktop Duplication API
 //! - **Multiple Monitor Support**: Capture from any available display
-//! - **Flexible Output**: Get pixel data as [`BGRA8`] or raw component bytes
+//! - **Flexible Output**: Get pixel data as [`BGRA8`], raw component bytes, or RGBA bytes
 //! - **Frame Metadata**: Access dirty rectangles, moved rectangles, and timing information
 //! - **Comprehensive Error Handling**: Robust error types for production use
 //! - **Windows Optimized**: Specifically designed for Windows platforms
@@ -47,10 +47,10 @@
 //! # fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! let mut manager = DXGIManager::new(1000)?;
 //!
-//! manager.set_capture_source_index(0); // Primary monitor
+//! manager.set_capture_source_index(0)?; // Primary monitor
 //! let (pixels, dimensions) = manager.capture_frame()?;
 //!
-//! manager.set_capture_source_index(1); // Secondary monitor
+//! manager.set_capture_source_index(1)?; // Secondary monitor
 //! let (pixels, dimensions) = manager.capture_frame()?;
 //! # Ok(())
 //! # }
@@ -76,21 +76,21 @@
 //!             // Process moved rectangles first (as per Microsoft recommendation)
 //!             for move_rect in &metadata.move_rects {
 //!                 let (src_x, src_y) = move_rect.source_point;
-//!                 let (dst_left, dst_top, dst_right, dst_bottom) = move_rect.destination_rect;
+//!                 let dst = move_rect.destination_rect;
 //!                 
 //!                 // Copy pixels from source to destination
 //!                 // This is much more efficient than re-encoding the entire area
-//!                 copy_rectangle(&pixels, src_x, src_y, dst_left, dst_top,
-//!                               dst_right - dst_left, dst_bottom - dst_top);
+//!                 copy_rectangle(&pixels, src_x, src_y, dst.left, dst.top,
+//!                               dst.width(), dst.height());
 //!             }
 //!             
 //!             // Then process dirty rectangles
-//!             for &(left, top, right, bottom) in &metadata.dirty_rects {
-//!                 let width = (right - left) as usize;
-//!                 let height = (bottom - top) as usize;
+//!             for rect in &metadata.dirty_rects {
+//!                 let width = rect.width() as usize;
+//!                 let height = rect.height() as usize;
 //!                 
 //!                 // Only encode/transmit the changed region
-//!                 encode_region(&pixels, left as usize, top as usize, width, height);
+//!                 encode_region(&pixels, rect.left as usize, rect.top as usize, width, height);
 //!             }
 //!         }
 //!         
@@ -123,6 +123,9 @@
 //!     Err(CaptureError::Timeout) => { /* No new frame - normal */ }
 //!     Err(CaptureError::AccessDenied) => { /* Protected content */ }
 //!     Err(CaptureError::AccessLost) => { /* Display mode changed */ }
+//!     Err(CaptureError::DeviceLost) => { /* Resumed from sleep - capture again */ }
+//!     Err(CaptureError::DesktopSwitch) => { /* UAC prompt or lock screen - pause */ }
+//!     Err(CaptureError::SessionDisconnected) => { /* Session disconnected - pause */ }
 //!     Err(e) => eprintln!("Capture failed: {:?}", e),
 //! }
 //! # Ok(())
@@ -133,6 +136,7 @@
 //!
 //! - Use appropriate timeout values based on your frame rate requirements
 //! - Consider using [`DXGIManager::capture_frame_components`] for raw byte data
+//! - Enable the `simd` feature to vectorize [`DXGIManager::capture_frame_rgba`]
 //! - Memory usage scales with screen resolution
 //! - The library automatically handles screen rotation
 //! - Use metadata to optimize streaming by only processing changed regions
@@ -147,33 +151,95 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![cfg_attr(docsrs, doc(cfg(windows)))]
 
+mod cursor;
+#[cfg(feature = "nv12")]
+mod nv12;
+#[cfg(feature = "record")]
+mod record;
+mod simd;
+#[cfg(feature = "async")]
+mod timer;
+mod widen;
+
 use std::fmt;
-use std::{mem, slice};
+#[cfg(feature = "record")]
+use std::fs::File;
+use std::io;
+#[cfg(feature = "record")]
+use std::io::BufWriter;
+use std::marker::PhantomData;
+use std::ops::{BitOr, BitOrAssign, ControlFlow, Deref, Range};
+#[cfg(feature = "record")]
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+#[cfg(feature = "async")]
+use std::task::Poll;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use std::{iter, mem, slice};
+#[cfg(feature = "raw-dxgi")]
+use windows::Win32::Graphics::{
+    Direct3D11::{D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX, D3D11_RESOURCE_MISC_SHARED_NTHANDLE},
+    Dxgi::{
+        DXGI_SHARED_RESOURCE_READ, DXGI_SHARED_RESOURCE_WRITE, IDXGIKeyedMutex, IDXGIResource1,
+    },
+};
 use windows::{
     Win32::{
-        Foundation::{HMODULE, RECT},
+        Foundation::{E_INVALIDARG, HANDLE, HMODULE, HWND, LUID, POINT, RECT},
         Graphics::{
             Direct3D::{D3D_DRIVER_TYPE_UNKNOWN, D3D_FEATURE_LEVEL_9_1},
             Direct3D11::{
-                D3D11_CPU_ACCESS_READ, D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_SDK_VERSION,
-                D3D11_TEXTURE2D_DESC, D3D11_USAGE_STAGING, D3D11CreateDevice, ID3D11Device,
-                ID3D11DeviceContext, ID3D11Texture2D,
+                D3D11_BIND_RENDER_TARGET, D3D11_BIND_SHADER_RESOURCE, D3D11_BOX,
+                D3D11_CPU_ACCESS_READ, D3D11_CPU_ACCESS_WRITE, D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                D3D11_RESOURCE_MISC_GENERATE_MIPS, D3D11_SDK_VERSION, D3D11_TEXTURE2D_DESC,
+                D3D11_USAGE_DEFAULT, D3D11_USAGE_STAGING, D3D11CreateDevice, ID3D11Device,
+                ID3D11DeviceContext, ID3D11ShaderResourceView, ID3D11Texture2D,
             },
             Dxgi::{
                 Common::{
+                    DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709,
+                    DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709,
+                    DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020, DXGI_COLOR_SPACE_TYPE, DXGI_FORMAT,
+                    DXGI_FORMAT_B8G8R8A8_TYPELESS, DXGI_FORMAT_B8G8R8A8_UNORM,
+                    DXGI_FORMAT_B8G8R8A8_UNORM_SRGB, DXGI_FORMAT_R8G8B8A8_TYPELESS,
+                    DXGI_FORMAT_R8G8B8A8_UNORM, DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+                    DXGI_FORMAT_R10G10B10A2_TYPELESS, DXGI_FORMAT_R10G10B10A2_UNORM,
+                    DXGI_FORMAT_R16G16B16A16_FLOAT, DXGI_MODE_ROTATION,
                     DXGI_MODE_ROTATION_IDENTITY, DXGI_MODE_ROTATION_ROTATE90,
                     DXGI_MODE_ROTATION_ROTATE180, DXGI_MODE_ROTATION_ROTATE270,
                     DXGI_MODE_ROTATION_UNSPECIFIED,
                 },
                 CreateDXGIFactory1, DXGI_ERROR_ACCESS_DENIED, DXGI_ERROR_ACCESS_LOST,
-                DXGI_ERROR_NOT_FOUND, DXGI_ERROR_WAIT_TIMEOUT, DXGI_MAP_READ, DXGI_MAPPED_RECT,
-                DXGI_OUTDUPL_FRAME_INFO, DXGI_OUTDUPL_MOVE_RECT, DXGI_OUTPUT_DESC, IDXGIAdapter,
-                IDXGIAdapter1, IDXGIFactory1, IDXGIOutput, IDXGIOutput1, IDXGIOutputDuplication,
-                IDXGIResource, IDXGISurface1,
+                DXGI_ERROR_DEVICE_REMOVED, DXGI_ERROR_DEVICE_RESET, DXGI_ERROR_MORE_DATA,
+                DXGI_ERROR_NOT_FOUND, DXGI_ERROR_SESSION_DISCONNECTED, DXGI_ERROR_UNSUPPORTED,
+                DXGI_ERROR_WAIT_TIMEOUT, DXGI_MAP_READ, DXGI_MAP_WRITE, DXGI_MAPPED_RECT,
+                DXGI_OUTDUPL_DESC, DXGI_OUTDUPL_FRAME_INFO, DXGI_OUTDUPL_MOVE_RECT,
+                DXGI_OUTDUPL_POINTER_SHAPE_INFO, DXGI_OUTDUPL_POINTER_SHAPE_TYPE_COLOR,
+                DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MASKED_COLOR,
+                DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MONOCHROME, DXGI_OUTPUT_DESC, IDXGIAdapter,
+                IDXGIAdapter1, IDXGIDevice, IDXGIFactory1, IDXGIOutput, IDXGIOutput1, IDXGIOutput5,
+                IDXGIOutput6, IDXGIOutputDuplication, IDXGIResource, IDXGISurface1,
+            },
+            Gdi::{
+                ClientToScreen, DEVMODEW, DMDO_90, DMDO_180, DMDO_270, ENUM_CURRENT_SETTINGS,
+                EnumDisplaySettingsW, HMONITOR, MONITOR_DEFAULTTONULL, MonitorFromWindow,
             },
         },
+        System::{
+            Performance::{QueryPerformanceCounter, QueryPerformanceFrequency},
+            StationsAndDesktops::{
+                CloseDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_READOBJECTS,
+                GetUserObjectInformationW, OpenInputDesktop, UOI_NAME,
+            },
+        },
+        UI::{
+            HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
+            WindowsAndMessaging::{GetClientRect, GetSystemMetrics, SM_REMOTESESSION},
+        },
     },
-    core::{Interface, Result as WindowsResult},
+    core::{Interface, PCWSTR, Result as WindowsResult},
 };
 
 /// A pixel color in BGRA8 format.
@@ -181,6 +247,7 @@ use windows::{
 /// Each channel can hold values from 0 to 255. The channels are ordered as BGRA
 /// to match the Windows DXGI format.
 #[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Eq, Ord)]
+#[repr(C)]
 pub struct BGRA8 {
     /// Blue channel (0-255)
     pub b: u8,
@@ -192,6 +259,102 @@ pub struct BGRA8 {
     pub a: u8,
 }
 
+/// An axis-aligned rectangle in pixels.
+///
+/// `right` and `bottom` are exclusive, so a rectangle covers the pixels with
+/// `left <= x < right` and `top <= y < bottom`. Rectangles convert to and from
+/// `(left, top, right, bottom)` tuples.
+///
+/// # Examples
+///
+/// ```rust
+/// use dxgi_capture_rs::Rect;
+///
+/// let a = Rect::new(0, 0, 100, 50);
+/// let b: Rect = (50, 25, 150, 75).into();
+///
+/// assert_eq!(a.width(), 100);
+/// assert!(a.contains(99, 49));
+/// assert!(!a.contains(100, 49));
+/// assert_eq!(a.intersect(&b), Some(Rect::new(50, 25, 100, 50)));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    /// Left edge, inclusive
+    pub left: i32,
+    /// Top edge, inclusive
+    pub top: i32,
+    /// Right edge, exclusive
+    pub right: i32,
+    /// Bottom edge, exclusive
+    pub bottom: i32,
+}
+
+impl Rect {
+    /// Creates a rectangle from its edges
+    pub const fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// Returns the width, or 0 if `right` is not after `left`
+    pub fn width(&self) -> i32 {
+        (self.right - self.left).max(0)
+    }
+
+    /// Returns the height, or 0 if `bottom` is not below `top`
+    pub fn height(&self) -> i32 {
+        (self.bottom - self.top).max(0)
+    }
+
+    /// Returns true if the rectangle covers no pixels
+    pub fn is_empty(&self) -> bool {
+        self.width() == 0 || self.height() == 0
+    }
+
+    /// Returns true if the pixel at `(x, y)` lies within the rectangle
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (self.left..self.right).contains(&x) && (self.top..self.bottom).contains(&y)
+    }
+
+    /// Returns the overlap of both rectangles, or `None` if it is empty
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect::new(
+            self.left.max(other.left),
+            self.top.max(other.top),
+            self.right.min(other.right),
+            self.bottom.min(other.bottom),
+        );
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    /// Returns the smallest rectangle containing both rectangles
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect::new(
+            self.left.min(other.left),
+            self.top.min(other.top),
+            self.right.max(other.right),
+            self.bottom.max(other.bottom),
+        )
+    }
+}
+
+impl From<(i32, i32, i32, i32)> for Rect {
+    fn from((left, top, right, bottom): (i32, i32, i32, i32)) -> Self {
+        Rect::new(left, top, right, bottom)
+    }
+}
+
+impl From<Rect> for (i32, i32, i32, i32) {
+    fn from(rect: Rect) -> Self {
+        (rect.left, rect.top, rect.right, rect.bottom)
+    }
+}
+
 /// Represents a rectangle that has been moved from one location to another.
 ///
 /// This structure describes a region that was moved from a source location to
@@ -202,7 +365,370 @@ pub struct MoveRect {
     /// The source point where the content was moved from (top-left corner)
     pub source_point: (i32, i32),
     /// The destination rectangle where the content was moved to
-    pub destination_rect: (i32, i32, i32, i32), // (left, top, right, bottom)
+    pub destination_rect: Rect,
+}
+
+/// The rotation of a display relative to its native orientation.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    /// The display is not rotated
+    #[default]
+    Identity,
+    /// The display is rotated 90 degrees
+    Rotate90,
+    /// The display is rotated 180 degrees
+    Rotate180,
+    /// The display is rotated 270 degrees
+    Rotate270,
+    /// The driver did not report a rotation. Captures resolve it from the
+    /// current display settings, so this only appears in
+    /// [`DisplayMode::reported_rotation`]; elsewhere it is treated as
+    /// [`Rotation::Identity`]
+    Unspecified,
+}
+
+impl Rotation {
+    fn from_dxgi(rotation: DXGI_MODE_ROTATION) -> Self {
+        match rotation {
+            DXGI_MODE_ROTATION_IDENTITY => Rotation::Identity,
+            DXGI_MODE_ROTATION_ROTATE90 => Rotation::Rotate90,
+            DXGI_MODE_ROTATION_ROTATE180 => Rotation::Rotate180,
+            DXGI_MODE_ROTATION_ROTATE270 => Rotation::Rotate270,
+            _ => Rotation::Unspecified,
+        }
+    }
+}
+
+/// The color space a display expects its desktop image in.
+///
+/// Returned by [`DXGIManager::color_space`]. Together with the pixel format
+/// from [`DXGIManager::current_format`] it tells how captured values map to
+/// colors, e.g. whether they need tone-mapping before being shown as SDR.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Standard dynamic range sRGB: BT.709 primaries with a gamma of 2.2
+    Srgb,
+    /// Linear scRGB: BT.709 primaries with a gamma of 1.0, typically paired
+    /// with a 16-bit floating point format
+    ScRgb,
+    /// HDR10: BT.2020 primaries with the SMPTE ST 2084 (PQ) transfer function
+    Hdr10,
+    /// Any other DXGI color space
+    Other(DXGI_COLOR_SPACE_TYPE),
+}
+
+impl ColorSpace {
+    fn from_dxgi(color_space: DXGI_COLOR_SPACE_TYPE) -> Self {
+        match color_space {
+            DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709 => ColorSpace::Srgb,
+            DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709 => ColorSpace::ScRgb,
+            DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020 => ColorSpace::Hdr10,
+            other => ColorSpace::Other(other),
+        }
+    }
+
+    /// Returns true for the high dynamic range color spaces, scRGB and HDR10
+    pub fn is_hdr(&self) -> bool {
+        matches!(self, ColorSpace::ScRgb | ColorSpace::Hdr10)
+    }
+}
+
+/// The luminance range of an HDR display, as returned by
+/// [`DXGIManager::hdr_metadata`].
+///
+/// All values are in nits (candela per square meter) as reported by the
+/// display's EDID, so they describe the panel rather than the current content.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HdrMetadata {
+    /// Minimum luminance the display can show
+    pub min_luminance: f32,
+    /// Maximum luminance the display can show in a small area of the screen
+    pub max_luminance: f32,
+    /// Maximum luminance the display can sustain across the whole screen
+    pub max_full_frame_luminance: f32,
+}
+
+/// The byte order of pixels returned by the component capture methods.
+///
+/// Set with [`DXGIManagerBuilder::output_order`] or
+/// [`DXGIManager::set_output_order`]. Methods returning [`BGRA8`] pixels are
+/// not affected, as the fields of [`BGRA8`] always name the channels they hold.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PixelOrder {
+    /// Blue, green, red, alpha, the native order of Desktop Duplication
+    #[default]
+    Bgra,
+    /// Red, green, blue, alpha, as expected by most image and GUI libraries
+    Rgba,
+}
+
+/// The severity of a diagnostic passed to a logger set with
+/// [`DXGIManager::set_logger`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// A step failed and the operation returned an error
+    Error,
+    /// A step failed, but the operation continued, e.g. by skipping an adapter
+    Warn,
+    /// A step succeeded or was skipped as expected
+    Debug,
+}
+
+type LogFn = dyn Fn(LogLevel, &str) + Send + Sync;
+
+/// A diagnostics callback, shared so the builder stays cloneable.
+#[derive(Clone)]
+struct Logger(Arc<LogFn>);
+
+impl fmt::Debug for Logger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Logger")
+    }
+}
+
+/// The current display mode of a capture source.
+///
+/// This describes the mode of the duplicated desktop image, including its
+/// refresh rate, which is useful for pacing encoders to the display.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DisplayMode {
+    /// Width of the desktop image in pixels
+    pub width: u32,
+    /// Height of the desktop image in pixels
+    pub height: u32,
+    /// Numerator of the refresh rate in hertz
+    pub refresh_numerator: u32,
+    /// Denominator of the refresh rate in hertz (0 if unknown)
+    pub refresh_denominator: u32,
+    /// Rotation of the display. An unspecified rotation is resolved from the
+    /// current display settings, so this is always one of the four concrete
+    /// rotations
+    pub rotation: Rotation,
+    /// Rotation as reported by DXGI, which is [`Rotation::Unspecified`] on
+    /// drivers that leave it unset. Useful for debugging sideways captures.
+    pub reported_rotation: Rotation,
+}
+
+impl DisplayMode {
+    /// Returns the refresh rate in hertz, or `0.0` if it is unknown
+    pub fn refresh_rate_hz(&self) -> f64 {
+        if self.refresh_denominator == 0 {
+            0.0
+        } else {
+            self.refresh_numerator as f64 / self.refresh_denominator as f64
+        }
+    }
+}
+
+/// A desktop-attached output, as listed by [`DXGIManager::outputs`].
+///
+/// In clone mode several outputs show the same part of the desktop, and
+/// capturing any of them yields identical frames. Such mirrored outputs share
+/// their `desktop_rect`, and all but the first are marked with `mirror_of`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputInfo {
+    /// Index of the output across all adapters, in the order used by
+    /// [`DXGIManager::split`] and [`DXGIManager::output_count`], and accepted
+    /// by [`DXGIManager::set_capture_source_index`]
+    pub index: usize,
+    /// Index of the adapter the output is connected to
+    pub adapter_index: usize,
+    /// GDI device name of the output, such as `\\.\DISPLAY1`
+    pub device_name: String,
+    /// Area of the desktop shown on the output, in desktop coordinates
+    pub desktop_rect: Rect,
+    /// Rotation of the output
+    pub rotation: Rotation,
+    /// Index of the first output showing the same part of the desktop, if
+    /// this one mirrors it
+    pub mirror_of: Option<usize>,
+}
+
+impl OutputInfo {
+    /// Returns true if the output mirrors an earlier one
+    pub fn is_mirror(&self) -> bool {
+        self.mirror_of.is_some()
+    }
+}
+
+/// Time spent in each stage of a capture, as returned by
+/// [`DXGIManager::capture_frame_timed`].
+///
+/// All durations are zero when timing is disabled with
+/// [`DXGIManager::set_capture_timing`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CaptureTimings {
+    /// Waiting for the frame with `AcquireNextFrame` and copying it into the
+    /// staging texture
+    pub acquire: Duration,
+    /// Mapping the staging texture for CPU access, which waits for the GPU
+    /// copy to finish
+    pub map: Duration,
+    /// Copying the mapped pixels into the returned buffer, including rotation
+    /// and cursor compositing
+    pub copy: Duration,
+}
+
+impl CaptureTimings {
+    /// Returns the sum of all stages
+    pub fn total(&self) -> Duration {
+        self.acquire + self.map + self.copy
+    }
+}
+
+/// When a captured frame was presented, as returned by
+/// [`DXGIManager::capture_frame_with_present_time`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PresentTiming {
+    /// Performance counter value of the last desktop image update, as in
+    /// [`FrameMetadata::last_present_time`]. Zero if the frame only updated
+    /// the pointer.
+    pub present_qpc: i64,
+    /// `present_qpc` as an [`Instant`], comparable with `Instant::now()`.
+    /// `None` if the frame only updated the pointer.
+    pub presented_at: Option<Instant>,
+    /// Time spent waiting for the frame with `AcquireNextFrame` and copying it
+    /// into the staging texture
+    pub acquire_latency: Duration,
+}
+
+/// How the pixels of a [`PointerShape`] combine with the desktop beneath it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PointerShapeType {
+    /// 1 bit per pixel: an AND mask followed by an XOR mask, each `height / 2`
+    /// rows. Desktop pixels are ANDed with the first mask, then XORed with the
+    /// second.
+    Monochrome,
+    /// 32-bit BGRA pixels blended onto the desktop with straight alpha
+    Color,
+    /// 32-bit BGRA pixels whose alpha is a mask: where it is 0 the color
+    /// replaces the desktop pixel, where it is 0xFF the color is XORed into it
+    MaskedColor,
+}
+
+/// The image of the mouse pointer, as reported by DXGI.
+///
+/// Returned by [`DXGIManager::capture_layers`] to draw the pointer separately
+/// from the desktop image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PointerShape {
+    /// How `data` is interpreted
+    pub shape_type: PointerShapeType,
+    /// Width of the pointer in pixels
+    pub width: u32,
+    /// Height of `data` in rows. For [`PointerShapeType::Monochrome`] this
+    /// covers both masks, so the pointer is `height / 2` pixels tall
+    pub height: u32,
+    /// Distance in bytes between the starts of consecutive rows of `data`
+    pub pitch: u32,
+    /// Position of the pointer's hot spot, the pixel that clicks, relative to
+    /// its top-left corner
+    pub hot_spot: (i32, i32),
+    /// Pixel data of `height` rows of `pitch` bytes
+    pub data: Vec<u8>,
+}
+
+impl PointerShape {
+    /// Converts a shape from `GetFramePointerShape`, or returns `None` if its
+    /// type is unknown.
+    fn from_dxgi(info: &DXGI_OUTDUPL_POINTER_SHAPE_INFO, data: &[u8]) -> Option<Self> {
+        let shape_type = match info.Type as i32 {
+            t if t == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MONOCHROME.0 => PointerShapeType::Monochrome,
+            t if t == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_COLOR.0 => PointerShapeType::Color,
+            t if t == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MASKED_COLOR.0 => {
+                PointerShapeType::MaskedColor
+            }
+            _ => return None,
+        };
+        Some(Self {
+            shape_type,
+            width: info.Width,
+            height: info.Height,
+            pitch: info.Pitch,
+            hot_spot: (info.HotSpot.x, info.HotSpot.y),
+            data: data.to_vec(),
+        })
+    }
+}
+
+/// The desktop image and the mouse pointer of a frame as separate layers, as
+/// returned by [`DXGIManager::capture_layers`].
+#[derive(Clone, Debug)]
+pub struct Layers {
+    /// The desktop image and its dimensions `(width, height)`, without the
+    /// pointer. `None` if only the pointer changed since the last frame.
+    pub desktop: Option<(Vec<BGRA8>, (usize, usize))>,
+    /// The current pointer shape and the position of its top-left corner in
+    /// frame coordinates. `None` while the pointer is hidden, on another
+    /// output, or its shape has not been reported yet.
+    pub cursor: Option<(PointerShape, (i32, i32))>,
+}
+
+/// A frame with its original row pitch, as produced by [`DXGIManager::capture_frame_raw`].
+///
+/// Row `y` starts at byte `y * pitch` of `data`, and its first `width` pixels
+/// are valid. Any bytes after that up to the next row are padding with
+/// unspecified contents.
+#[derive(Clone, Debug)]
+pub struct RawFrame {
+    /// Pixel data of `height` rows of `pitch` bytes each
+    pub data: Vec<u8>,
+    /// Distance in bytes between the starts of consecutive rows
+    pub pitch: usize,
+    /// Width of the frame in pixels
+    pub width: usize,
+    /// Height of the frame in pixels
+    pub height: usize,
+    /// Pixel format of the data, normally `DXGI_FORMAT_B8G8R8A8_UNORM`
+    pub format: DXGI_FORMAT,
+}
+
+/// Everything needed to interpret the pixels of a frame, as returned by
+/// [`DXGIManager::capture_frame_described`].
+///
+/// All fields are read together with the frame, so they stay consistent even
+/// if the display mode changes between captures.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameDescriptor {
+    /// Width of the frame in pixels, in the native orientation of the display
+    pub width: usize,
+    /// Height of the frame in pixels, in the native orientation of the display
+    pub height: usize,
+    /// Distance in bytes between the starts of consecutive rows
+    pub pitch: usize,
+    /// Pixel format of the data, normally `DXGI_FORMAT_B8G8R8A8_UNORM`
+    pub format: DXGI_FORMAT,
+    /// Rotation of the display, to be applied to show the frame upright
+    pub rotation: Rotation,
+    /// Color space the pixel values are in
+    pub color_space: ColorSpace,
+}
+
+/// A frame in NV12 format, as produced by [`DXGIManager::capture_frame_nv12`].
+///
+/// NV12 stores a full-resolution luma (Y) plane followed by a half-resolution
+/// plane of interleaved chroma (U, V) pairs, which is the input most hardware
+/// H.264/HEVC encoders expect. Values use BT.709 coefficients in limited range
+/// (Y in 16..=235, U and V in 16..=240).
+#[cfg(feature = "nv12")]
+#[cfg_attr(docsrs, doc(cfg(feature = "nv12")))]
+#[derive(Clone, Debug)]
+pub struct Nv12Frame {
+    /// Luma plane with `height` rows of `y_stride` bytes, of which the first
+    /// `width` bytes of each row are used
+    pub y_plane: Vec<u8>,
+    /// Chroma plane with `(height + 1) / 2` rows of `uv_stride` bytes, each row
+    /// holding `(width + 1) / 2` interleaved U, V byte pairs
+    pub uv_plane: Vec<u8>,
+    /// Width of the frame in pixels
+    pub width: usize,
+    /// Height of the frame in pixels
+    pub height: usize,
+    /// Distance in bytes between luma rows, a multiple of 16
+    pub y_stride: usize,
+    /// Distance in bytes between chroma rows, a multiple of 16
+    pub uv_stride: usize,
 }
 
 /// Metadata about a captured frame.
@@ -210,7 +736,8 @@ pub struct MoveRect {
 /// This structure contains timing information, dirty regions, moved regions,
 /// and other metadata that can help optimize screen capture and streaming
 /// applications.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
 pub struct FrameMetadata {
     /// Timestamp of the last desktop image update (Windows performance counter)
     pub last_present_time: i64,
@@ -218,16 +745,32 @@ pub struct FrameMetadata {
     pub last_mouse_update_time: i64,
     /// Number of frames accumulated since the last processed frame
     pub accumulated_frames: u32,
-    /// Whether dirty regions were coalesced and may contain unmodified pixels
+    /// Whether DXGI merged the dirty rectangles into fewer, larger ones,
+    /// which may contain unmodified pixels. DXGI does this on its own when
+    /// updates pile up, regardless of buffer sizes, so the granular changes
+    /// are lost. Consumers that patch a previous frame can still use the
+    /// rectangles, but those that track precise changes should treat the
+    /// whole frame as updated, see [`FrameMetadata::requires_full_update`].
     pub rects_coalesced: bool,
+    /// Whether the frame had more dirty or move rectangles than
+    /// [`DXGIManager::set_max_rects`] allows. They were then not read, and
+    /// the frame is reported as fully dirty: `dirty_rects` holds a single
+    /// rectangle covering the whole desktop image and `move_rects` is empty.
+    pub rects_truncated: bool,
+    /// Whether DXGI provided dirty and move rectangles for this frame. When
+    /// false, empty rectangle lists say nothing about what changed: the
+    /// desktop may still have been updated, as [`FrameMetadata::is_desktop_update`]
+    /// reports, without any rectangles being available.
+    pub metadata_available: bool,
     /// Whether protected content was masked out in the captured frame
     pub protected_content_masked_out: bool,
-    /// Mouse cursor position and visibility
+    /// Mouse cursor position if visible. This is the most recent position
+    /// reported by DXGI, so it stays valid for frames without a mouse update.
     pub pointer_position: Option<(i32, i32)>,
     /// Whether the mouse cursor is visible
     pub pointer_visible: bool,
     /// List of dirty rectangles that have changed since the last frame
-    pub dirty_rects: Vec<(i32, i32, i32, i32)>, // (left, top, right, bottom)
+    pub dirty_rects: Vec<Rect>,
     /// List of move rectangles that have been moved since the last frame
     pub move_rects: Vec<MoveRect>,
 }
@@ -243,14 +786,299 @@ impl FrameMetadata {
         self.last_mouse_update_time > 0
     }
 
+    /// Returns true if the desktop image changed since the previous frame.
+    ///
+    /// DXGI reports a `last_present_time` of 0 when a frame was returned for a
+    /// pointer change only, in which case the pixels repeat the previous
+    /// desktop image and `accumulated_frames` is also 0. Streaming consumers
+    /// can skip re-encoding such frames and send only the new pointer state.
+    /// This is the complement of the desktop part of
+    /// [`FrameMetadata::is_mouse_only_update`].
+    pub fn is_desktop_update(&self) -> bool {
+        self.last_present_time > 0
+    }
+
+    /// Returns true if only the mouse cursor changed and the desktop image is
+    /// the same as in the previous frame
+    pub fn is_mouse_only_update(&self) -> bool {
+        self.last_present_time == 0 && self.last_mouse_update_time > 0
+    }
+
+    /// Returns true if the rectangles do not describe the changes precisely,
+    /// so consumers should process the whole frame as updated.
+    ///
+    /// This is the case when DXGI coalesced the rectangles
+    /// ([`FrameMetadata::rects_coalesced`]), did not provide any for an
+    /// updated desktop ([`FrameMetadata::metadata_available`]), or there were
+    /// more than [`DXGIManager::set_max_rects`] allows
+    /// ([`FrameMetadata::rects_truncated`]). Frames that only update the
+    /// pointer never require a full update.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (pixels, (width, height), metadata) = manager.capture_frame_with_metadata()?;
+    /// if metadata.requires_full_update() {
+    ///     // Re-encode the whole frame
+    /// } else {
+    ///     for rect in &metadata.dirty_rects {
+    ///         // Re-encode only the changed region
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn requires_full_update(&self) -> bool {
+        self.is_desktop_update()
+            && (self.rects_coalesced || self.rects_truncated || !self.metadata_available)
+    }
+
+    /// Returns the bounding box of all dirty rectangles and move destination
+    /// rectangles, or `None` if there are none.
+    ///
+    /// Encoders that update a single region can re-encode this rectangle
+    /// instead of each change. Empty rectangles are ignored. The box may
+    /// include unchanged pixels between the changes, and it is only complete
+    /// when [`FrameMetadata::requires_full_update`] is false.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (pixels, (width, height), metadata) = manager.capture_frame_with_metadata()?;
+    /// if let Some(bounds) = metadata.change_bounds() {
+    ///     println!("Re-encode {}x{} at {}, {}", bounds.width(), bounds.height(), bounds.left, bounds.top);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn change_bounds(&self) -> Option<Rect> {
+        self.dirty_rects
+            .iter()
+            .chain(
+                self.move_rects
+                    .iter()
+                    .map(|move_rect| &move_rect.destination_rect),
+            )
+            .filter(|rect| !rect.is_empty())
+            .copied()
+            .reduce(|bounds, rect| bounds.union(&rect))
+    }
+
     /// Returns the total number of changed regions
     pub fn total_change_count(&self) -> usize {
         self.dirty_rects.len() + self.move_rects.len()
     }
+
+    /// Clips all dirty and move rectangles to a `width` x `height` frame.
+    ///
+    /// Rectangles are taken as reported by DXGI without validation, so this is a
+    /// safeguard for consumers that index into a pixel buffer with them. Dirty
+    /// rectangles are intersected with the frame bounds. Move rectangles are
+    /// clipped so that both the destination and the source region lie within
+    /// the frame, with the source point shifted accordingly. Rectangles that end
+    /// up empty, including inverted ones, are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (pixels, (width, height), mut metadata) = manager.capture_frame_with_metadata()?;
+    /// metadata.clamp_to(width as i32, height as i32);
+    ///
+    /// for rect in &metadata.dirty_rects {
+    ///     // Indexing `pixels` within these bounds cannot panic
+    ///     let _first = pixels[rect.top as usize * width + rect.left as usize];
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn clamp_to(&mut self, width: i32, height: i32) {
+        let bounds = Rect::new(0, 0, width, height);
+        self.dirty_rects
+            .retain_mut(|rect| match rect.intersect(&bounds) {
+                Some(clipped) => {
+                    *rect = clipped;
+                    true
+                }
+                None => false,
+            });
+
+        self.move_rects.retain_mut(|move_rect| {
+            let Rect {
+                left,
+                top,
+                right,
+                bottom,
+            } = move_rect.destination_rect;
+            let (source_x, source_y) = move_rect.source_point;
+            let (dx, dy) = (source_x - left, source_y - top);
+
+            let left = left.max(0).max(-dx);
+            let top = top.max(0).max(-dy);
+            let right = right.min(width).min(width - dx);
+            let bottom = bottom.min(height).min(height - dy);
+
+            move_rect.destination_rect = Rect::new(left, top, right, bottom);
+            move_rect.source_point = (left + dx, top + dy);
+            left < right && top < bottom
+        });
+    }
+
+    /// Returns the time between `epoch_qpc` and the last desktop image update.
+    ///
+    /// `epoch_qpc` is a performance counter value of your choosing, typically the
+    /// `last_present_time` of an earlier frame to measure inter-frame intervals.
+    /// Returns [`Duration::ZERO`] if the update happened before the epoch or if
+    /// this frame has no desktop image update (`last_present_time` is 0).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (_, _, first) = manager.capture_frame_with_metadata()?;
+    /// let (_, _, second) = manager.capture_frame_with_metadata()?;
+    /// println!("Frame interval: {:?}", second.present_time_since(first.last_present_time));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn present_time_since(&self, epoch_qpc: i64) -> Duration {
+        if self.last_present_time == 0 {
+            return Duration::ZERO;
+        }
+        qpc_to_duration(self.last_present_time.saturating_sub(epoch_qpc))
+    }
+
+    /// Returns a [`FrameSummary`] of the scalar fields and rectangle counts,
+    /// which is `Copy` and cheap to keep for every frame.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, FrameSummary};
+    /// use std::collections::VecDeque;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let mut history: VecDeque<FrameSummary> = VecDeque::with_capacity(120);
+    ///
+    /// let (_, _, metadata) = manager.capture_frame_with_metadata()?;
+    /// if history.len() == history.capacity() {
+    ///     history.pop_front();
+    /// }
+    /// history.push_back(metadata.summary());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn summary(&self) -> FrameSummary {
+        FrameSummary {
+            present_time: self.last_present_time,
+            accumulated_frames: self.accumulated_frames,
+            dirty_count: self.dirty_rects.len(),
+            move_count: self.move_rects.len(),
+            rects_coalesced: self.rects_coalesced,
+            pointer_visible: self.pointer_visible,
+        }
+    }
+}
+
+/// A compact, `Copy` summary of a [`FrameMetadata`], without the rectangle
+/// lists, as returned by [`FrameMetadata::summary`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FrameSummary {
+    /// Timestamp of the last desktop image update (Windows performance
+    /// counter), or 0 for pointer-only updates
+    pub present_time: i64,
+    /// Number of desktop updates accumulated into the frame
+    pub accumulated_frames: u32,
+    /// Number of dirty rectangles
+    pub dirty_count: usize,
+    /// Number of move rectangles
+    pub move_count: usize,
+    /// Whether DXGI coalesced the dirty rectangles, see
+    /// [`FrameMetadata::rects_coalesced`]
+    pub rects_coalesced: bool,
+    /// Whether the mouse cursor is visible
+    pub pointer_visible: bool,
+}
+
+/// Selects which parts of [`FrameMetadata`] are queried, for use with
+/// [`DXGIManager::capture_frame_with_metadata_opts`].
+///
+/// Dirty and move rectangles each cost an extra COM call per frame, so
+/// skipping them reduces the overhead of metadata capture. Fields for parts
+/// that are not requested are left empty. Timing, accumulated frame count and
+/// protection fields come with every frame at no extra cost and are always
+/// filled in.
+///
+/// # Examples
+///
+/// ```rust
+/// use dxgi_capture_rs::MetadataFlags;
+///
+/// let flags = MetadataFlags::POINTER | MetadataFlags::DIRTY_RECTS;
+/// assert!(flags.contains(MetadataFlags::POINTER));
+/// assert!(!flags.contains(MetadataFlags::RECTS));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MetadataFlags(u8);
+
+impl MetadataFlags {
+    /// Query nothing beyond the always-present fields
+    pub const NONE: Self = Self(0);
+    /// Fill in [`FrameMetadata::pointer_position`] and [`FrameMetadata::pointer_visible`]
+    pub const POINTER: Self = Self(1);
+    /// Query [`FrameMetadata::dirty_rects`]
+    pub const DIRTY_RECTS: Self = Self(1 << 1);
+    /// Query [`FrameMetadata::move_rects`]
+    pub const MOVE_RECTS: Self = Self(1 << 2);
+    /// Query both dirty and move rectangles
+    pub const RECTS: Self = Self(Self::DIRTY_RECTS.0 | Self::MOVE_RECTS.0);
+    /// Query everything, as [`DXGIManager::capture_frame_with_metadata`] does
+    pub const ALL: Self = Self(Self::POINTER.0 | Self::RECTS.0);
+
+    /// Returns true if every flag in `other` is also set in `self`
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for MetadataFlags {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl BitOr for MetadataFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for MetadataFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// The outcome of waiting for a frame with [`DXGIManager::wait_for_frame`].
+#[derive(Clone, Debug)]
+pub enum FrameStatus {
+    /// A frame arrived with dirty or move rectangles describing what changed
+    Changed(FrameMetadata),
+    /// A frame arrived, but it carried no dirty or move rectangles, e.g. a
+    /// mouse-only update
+    Unchanged,
 }
 
 /// Errors that can occur during screen capture operations.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum CaptureError {
     /// Access to the output duplication was denied.
     ///
@@ -268,13 +1096,28 @@ pub enum CaptureError {
     /// - Connecting/disconnecting monitors
     /// - Graphics driver updates
     ///
-    /// **Recovery**: Recreate the [`DXGIManager`] instance.
+    /// **Recovery**: Recreate the [`DXGIManager`] instance, or enable
+    /// [`DXGIManager::set_auto_recover`] to re-acquire and retry automatically.
     AccessLost,
 
+    /// The Direct3D device used for capturing was removed or reset.
+    ///
+    /// This happens when the machine resumes from sleep or hibernation, the
+    /// graphics driver is updated or crashes, or the GPU is disconnected. The
+    /// output duplication and its device are discarded, so the next capture
+    /// creates a new device and duplicates the output again.
+    ///
+    /// **Recovery**: Capture again, or enable [`DXGIManager::set_auto_recover`]
+    /// to re-acquire and retry automatically.
+    DeviceLost,
+
     /// Failed to refresh the output duplication after a previous error.
     ///
+    /// Carries the [`OutputDuplicationError`] that caused re-acquisition to fail,
+    /// distinguishing a missing output from a device creation failure.
+    ///
     /// **Recovery**: Recreate the [`DXGIManager`] instance or wait before retrying.
-    RefreshFailure,
+    RefreshFailure(OutputDuplicationError),
 
     /// The capture operation timed out.
     ///
@@ -284,6 +1127,62 @@ pub enum CaptureError {
     /// **Recovery**: This is not an error condition. Simply retry the capture.
     Timeout,
 
+    /// The requested window is not on the display of the current capture source.
+    ///
+    /// **Recovery**: Select the capture source showing the window with
+    /// [`DXGIManager::set_capture_source_index`].
+    WindowNotOnOutput,
+
+    /// Access was lost or denied, or the output could not be duplicated again,
+    /// because the input desktop switched away from the user's desktop, e.g.
+    /// to the secure desktop of a UAC prompt or the lock screen.
+    ///
+    /// Nothing can be captured until the user's desktop is back, so retrying
+    /// immediately only produces more errors.
+    ///
+    /// **Recovery**: Pause capturing, poll [`is_secure_desktop`] until it no
+    /// longer returns `Some(true)`, then capture again. The output
+    /// duplication is re-acquired automatically on the next capture.
+    DesktopSwitch,
+
+    /// The Windows session was disconnected, e.g. when a remote desktop
+    /// client disconnects or the user switches to another account.
+    ///
+    /// The output duplication is released. While the session is disconnected
+    /// every capture fails with this error, including the re-acquisition of
+    /// the duplication, so auto-recover does not retry and [`CaptureSession`]
+    /// returns this error right away.
+    ///
+    /// **Recovery**: Pause capturing and defer re-acquisition until the
+    /// session is connected again, e.g. on a `WTS_CONSOLE_CONNECT` or
+    /// `WTS_REMOTE_CONNECT` session notification, or by retrying with a long
+    /// interval. The next capture then re-acquires the duplication.
+    SessionDisconnected,
+
+    /// The frame is larger than the configured maximum, or so large that its
+    /// size in bytes overflows `usize`.
+    ///
+    /// This protects against allocating absurd buffers when a driver reports a
+    /// malformed surface description.
+    ///
+    /// **Recovery**: Raise the limit with [`DXGIManager::set_max_frame_pixels`]
+    /// if the frame is genuinely that large; otherwise recreate the
+    /// [`DXGIManager`].
+    FrameTooLarge {
+        /// Width of the rejected frame in pixels
+        width: usize,
+        /// Height of the rejected frame in pixels
+        height: usize,
+    },
+
+    /// Writing a captured frame failed, e.g. in
+    /// [`DXGIManager::capture_frame_write`] when the reading end of a pipe
+    /// was closed.
+    ///
+    /// **Recovery**: Handle the IO error; the capture itself succeeded, so
+    /// the manager can keep capturing.
+    Io(io::Error),
+
     /// A general or unexpected failure occurred.
     ///
     /// **Recovery**: Log the error message and consider recreating the [`DXGIManager`].
@@ -295,8 +1194,22 @@ impl fmt::Display for CaptureError {
         match self {
             CaptureError::AccessDenied => write!(f, "Access to output duplication was denied"),
             CaptureError::AccessLost => write!(f, "Access to duplicated output was lost"),
-            CaptureError::RefreshFailure => write!(f, "Failed to refresh output duplication"),
+            CaptureError::DeviceLost => write!(f, "The Direct3D device was removed or reset"),
+            CaptureError::RefreshFailure(err) => {
+                write!(f, "Failed to refresh output duplication: {err}")
+            }
             CaptureError::Timeout => write!(f, "Capture operation timed out"),
+            CaptureError::WindowNotOnOutput => {
+                write!(f, "Window is not on the display of the capture source")
+            }
+            CaptureError::DesktopSwitch => {
+                write!(f, "The input desktop switched away from the user's desktop")
+            }
+            CaptureError::SessionDisconnected => write!(f, "The session was disconnected"),
+            CaptureError::FrameTooLarge { width, height } => {
+                write!(f, "Frame of {width}x{height} pixels is too large")
+            }
+            CaptureError::Io(err) => write!(f, "Writing the frame failed: {err}"),
             CaptureError::Fail(msg) => write!(f, "Capture failed: {msg}"),
         }
     }
@@ -306,12 +1219,54 @@ impl std::error::Error for CaptureError {}
 
 impl From<windows::core::Error> for CaptureError {
     fn from(err: windows::core::Error) -> Self {
-        CaptureError::Fail(err)
+        if is_device_lost(&err) {
+            CaptureError::DeviceLost
+        } else if err.code() == DXGI_ERROR_SESSION_DISCONNECTED {
+            CaptureError::SessionDisconnected
+        } else {
+            CaptureError::Fail(err)
+        }
+    }
+}
+
+/// Converts a capture error into an [`io::Error`] for use in IO-centric code.
+///
+/// [`CaptureError::Timeout`] maps to [`io::ErrorKind::WouldBlock`],
+/// [`CaptureError::AccessDenied`] and [`CaptureError::DesktopSwitch`] to
+/// [`io::ErrorKind::PermissionDenied`], [`CaptureError::SessionDisconnected`] to
+/// [`io::ErrorKind::NotConnected`], and everything else to [`io::ErrorKind::Other`]. The original error is kept as
+/// the inner error, so the message matches its `Display` text.
+/// [`CaptureError::Io`] is unwrapped into the IO error it carries.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use dxgi_capture_rs::DXGIManager;
+/// use std::io;
+///
+/// fn grab(manager: &mut DXGIManager) -> io::Result<usize> {
+///     let (pixels, _) = manager.capture_frame()?;
+///     Ok(pixels.len())
+/// }
+/// ```
+impl From<CaptureError> for io::Error {
+    fn from(err: CaptureError) -> Self {
+        let kind = match err {
+            CaptureError::Io(err) => return err,
+            CaptureError::Timeout => io::ErrorKind::WouldBlock,
+            CaptureError::AccessDenied | CaptureError::DesktopSwitch => {
+                io::ErrorKind::PermissionDenied
+            }
+            CaptureError::SessionDisconnected => io::ErrorKind::NotConnected,
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, err)
     }
 }
 
 /// Errors that can occur during output duplication initialization.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum OutputDuplicationError {
     /// No suitable output display was found.
     ///
@@ -328,6 +1283,17 @@ pub enum OutputDuplicationError {
     ///
     /// **Recovery**: Check graphics driver installation and system resources.
     DeviceError(windows::core::Error),
+
+    /// The output exists, but no adapter could duplicate it because the
+    /// process runs in a remote desktop (RDP) session.
+    ///
+    /// Remote sessions render through an indirect display driver, and some
+    /// configurations do not support Desktop Duplication on it. See
+    /// [`is_remote_session`].
+    ///
+    /// **Recovery**: Capture from the console session instead, or enable
+    /// hardware graphics for remote sessions on the host.
+    UnsupportedSession,
 }
 
 impl fmt::Display for OutputDuplicationError {
@@ -337,6 +1303,12 @@ impl fmt::Display for OutputDuplicationError {
             OutputDuplicationError::DeviceError(err) => {
                 write!(f, "Failed to create D3D11 device: {err}")
             }
+            OutputDuplicationError::UnsupportedSession => {
+                write!(
+                    f,
+                    "Desktop Duplication is not supported in this remote session"
+                )
+            }
         }
     }
 }
@@ -376,6 +1348,340 @@ pub fn hr_failed(hr: windows::core::HRESULT) -> bool {
     hr.is_err()
 }
 
+// ---------------------------------------------------------------------------
+// Frame diffing
+// ---------------------------------------------------------------------------
+
+/// Computes the regions that differ between two frames by pixel comparison.
+///
+/// The frames are divided into `tile` x `tile` tiles (smaller at the right and
+/// bottom edges), and every tile containing at least one changed pixel is
+/// reported. Horizontally adjacent changed tiles are merged into one rectangle,
+/// and rectangles spanning the same columns on consecutive tile rows are merged
+/// vertically. This complements [`FrameMetadata::dirty_rects`] when the DXGI
+/// rectangles were coalesced (see [`FrameMetadata::rects_coalesced`]).
+///
+/// Tiles are compared row by row as contiguous slices, which the compiler can
+/// vectorize, and a tile stops being compared at its first differing row.
+///
+/// # Returns
+///
+/// Rectangles in pixels, matching the format of [`FrameMetadata::dirty_rects`],
+/// ordered top-to-bottom and left-to-right.
+///
+/// # Panics
+///
+/// Panics if `tile` is zero or if either frame does not hold exactly
+/// `width * height` pixels.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use dxgi_capture_rs::{DXGIManager, compute_dirty_rects};
+///
+/// let mut manager = DXGIManager::new(1000)?;
+/// let (prev, _) = manager.capture_frame()?;
+/// let (cur, (width, height)) = manager.capture_frame()?;
+///
+/// for rect in compute_dirty_rects(&prev, &cur, width, height, 64) {
+///     println!("Changed: {}x{} at ({}, {})", rect.width(), rect.height(), rect.left, rect.top);
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn compute_dirty_rects(
+    prev: &[BGRA8],
+    cur: &[BGRA8],
+    width: usize,
+    height: usize,
+    tile: usize,
+) -> Vec<Rect> {
+    assert!(tile > 0, "tile size must be non-zero");
+    assert_eq!(prev.len(), width * height, "previous frame size mismatch");
+    assert_eq!(cur.len(), width * height, "current frame size mismatch");
+
+    let tiles_x = width.div_ceil(tile);
+    let tiles_y = height.div_ceil(tile);
+
+    let mut rects: Vec<Rect> = Vec::new();
+    // Rectangles that ended on the previous tile row and may still grow downwards
+    let mut open: Vec<usize> = Vec::new();
+
+    for ty in 0..tiles_y {
+        let top = ty * tile;
+        let bottom = (top + tile).min(height);
+
+        // Horizontal runs of changed tiles on this tile row, as pixel columns
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for tx in 0..tiles_x {
+            let left = tx * tile;
+            let right = (left + tile).min(width);
+            let changed = (top..bottom).any(|y| {
+                let row = y * width;
+                prev[row + left..row + right] != cur[row + left..row + right]
+            });
+            if !changed {
+                continue;
+            }
+            match runs.last_mut() {
+                Some((_, run_right)) if *run_right == left => *run_right = right,
+                _ => runs.push((left, right)),
+            }
+        }
+
+        let mut next_open = Vec::with_capacity(runs.len());
+        for (left, right) in runs {
+            let (left, right) = (left as i32, right as i32);
+            let extended = open
+                .iter()
+                .copied()
+                .find(|&i| rects[i].left == left && rects[i].right == right);
+            match extended {
+                Some(i) => {
+                    rects[i].bottom = bottom as i32;
+                    next_open.push(i);
+                }
+                None => {
+                    rects.push(Rect::new(left, top as i32, right, bottom as i32));
+                    next_open.push(rects.len() - 1);
+                }
+            }
+        }
+        open = next_open;
+    }
+
+    rects
+}
+
+// ---------------------------------------------------------------------------
+// Frame transforms
+// ---------------------------------------------------------------------------
+
+/// Copies a `w` x `h` image of BGRA pixels whose rows start `pitch` bytes
+/// apart out of `src` into `out`, rotating it by `rot`, and returns the
+/// dimensions of the rotated image.
+///
+/// This is the rotation-aware copy used by every capture method, exposed so it
+/// can be applied to frames in the native orientation of the display, such as
+/// those from [`DXGIManager::capture_frame_raw`]. Only the first `w * 4` bytes
+/// of each row are read and the last row needs no padding, so `src` must hold
+/// at least `pitch * (h - 1) + w * 4` bytes. `out` is cleared and filled with
+/// the tightly packed rotated image, reusing its allocation.
+///
+/// # Panics
+///
+/// Panics if `pitch` is less than `w * 4`, if `src` is shorter than described
+/// above, or if the size of the image in bytes overflows `usize`.
+///
+/// # Examples
+///
+/// ```rust
+/// use dxgi_capture_rs::{Rotation, copy_rotated};
+///
+/// // A 2x1 image with a padded row, rotated into a 1x2 image
+/// let src = [1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0];
+/// let mut out = Vec::new();
+/// let dimensions = copy_rotated(&src, 12, 2, 1, Rotation::Rotate90, &mut out);
+/// assert_eq!(dimensions, (1, 2));
+/// assert_eq!(out, [1, 1, 1, 1, 2, 2, 2, 2]);
+/// ```
+pub fn copy_rotated(
+    src: &[u8],
+    pitch: usize,
+    w: usize,
+    h: usize,
+    rot: Rotation,
+    out: &mut Vec<u8>,
+) -> (usize, usize) {
+    copy_rotated_flipped(src, pitch, w, h, rot, false, out)
+}
+
+/// Like [`copy_rotated`], but flips the image vertically after rotating it if
+/// `flip_vertical` is set, so its rows run bottom to top.
+///
+/// This is the copy used when [`DXGIManagerBuilder::flip_vertical`] is
+/// enabled. The flip is part of the same pass, so it costs nothing extra.
+///
+/// # Panics
+///
+/// Panics under the same conditions as [`copy_rotated`].
+///
+/// # Examples
+///
+/// ```rust
+/// use dxgi_capture_rs::{Rotation, copy_rotated_flipped};
+///
+/// // A 1x2 image, flipped so the bottom row comes first
+/// let src = [1, 1, 1, 1, 2, 2, 2, 2];
+/// let mut out = Vec::new();
+/// let dimensions = copy_rotated_flipped(&src, 4, 1, 2, Rotation::Identity, true, &mut out);
+/// assert_eq!(dimensions, (1, 2));
+/// assert_eq!(out, [2, 2, 2, 2, 1, 1, 1, 1]);
+/// ```
+pub fn copy_rotated_flipped(
+    src: &[u8],
+    pitch: usize,
+    w: usize,
+    h: usize,
+    rot: Rotation,
+    flip_vertical: bool,
+    out: &mut Vec<u8>,
+) -> (usize, usize) {
+    let len = frame_len(w, h, 4).expect("Image size overflows usize");
+    out.clear();
+    out.resize(len, 0);
+    rotate_pixels(src, pitch, w, h, 4, rot, flip_vertical, out)
+}
+
+/// Converts `pixels` to premultiplied alpha in place, turning each color
+/// channel `c` into `c * a / 255`, rounded down, where `a` is the pixel's
+/// alpha. The alpha itself is kept.
+///
+/// This is the conversion applied when [`DXGIManagerBuilder::premultiply_alpha`]
+/// is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use dxgi_capture_rs::{BGRA8, premultiply_alpha};
+///
+/// let mut pixels = [BGRA8 { b: 255, g: 100, r: 10, a: 128 }];
+/// premultiply_alpha(&mut pixels);
+/// assert_eq!(pixels[0], BGRA8 { b: 128, g: 50, r: 5, a: 128 });
+/// ```
+pub fn premultiply_alpha(pixels: &mut [BGRA8]) {
+    premultiply(pixels);
+}
+
+// ---------------------------------------------------------------------------
+// Timestamps
+// ---------------------------------------------------------------------------
+
+/// Converts a performance counter value into a [`Duration`].
+///
+/// Timestamps such as [`FrameMetadata::last_present_time`] are raw
+/// `QueryPerformanceCounter` values, counted in ticks of a system-defined
+/// frequency since an unspecified epoch (usually system boot). The frequency is
+/// queried once with `QueryPerformanceFrequency` and cached.
+///
+/// Converting an absolute timestamp gives the time since that epoch; converting
+/// the difference of two timestamps gives the interval between them. On Windows,
+/// [`std::time::Instant`] is also based on the performance counter, so intervals
+/// line up with `Instant` measurements. Negative values yield [`Duration::ZERO`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use dxgi_capture_rs::{DXGIManager, qpc_to_duration};
+///
+/// let mut manager = DXGIManager::new(1000)?;
+/// let (_, _, metadata) = manager.capture_frame_with_metadata()?;
+/// println!("Presented {:?} after boot", qpc_to_duration(metadata.last_present_time));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn qpc_to_duration(qpc: i64) -> Duration {
+    static FREQUENCY: OnceLock<i64> = OnceLock::new();
+    let frequency = *FREQUENCY.get_or_init(|| {
+        let mut frequency = 0;
+        // Cannot fail on Windows XP and later
+        let _ = unsafe { QueryPerformanceFrequency(&mut frequency) };
+        frequency
+    });
+    if qpc <= 0 || frequency <= 0 {
+        return Duration::ZERO;
+    }
+
+    let (qpc, frequency) = (qpc as u64, frequency as u64);
+    let secs = qpc / frequency;
+    let nanos = (qpc % frequency) as u128 * 1_000_000_000 / frequency as u128;
+    Duration::new(secs, nanos as u32)
+}
+
+/// Converts a past performance counter value into an [`Instant`] by measuring
+/// its distance from the current counter value. Returns `None` for values that
+/// are not positive.
+fn qpc_to_instant(qpc: i64) -> Option<Instant> {
+    if qpc <= 0 {
+        return None;
+    }
+    let mut now_qpc = 0;
+    let now = Instant::now();
+    // Cannot fail on Windows XP and later
+    let _ = unsafe { QueryPerformanceCounter(&mut now_qpc) };
+    now.checked_sub(qpc_to_duration(now_qpc.saturating_sub(qpc)))
+}
+
+/// Returns whether the input desktop is currently something other than the
+/// user's desktop, such as the secure desktop of a UAC prompt or the lock
+/// screen.
+///
+/// Desktop Duplication cannot capture the secure desktop from a normal user
+/// process, so captures fail with [`CaptureError::DesktopSwitch`] while this
+/// returns `Some(true)`. Returns `None` if the input desktop cannot be opened
+/// or its name cannot be read, in which case it is unknown which desktop is
+/// active.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use dxgi_capture_rs::{CaptureError, DXGIManager, is_secure_desktop};
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// let mut manager = DXGIManager::new(1000)?;
+/// loop {
+///     match manager.capture_frame() {
+///         Ok(_) => break,
+///         Err(CaptureError::DesktopSwitch) => {
+///             // Wait for the UAC prompt to be dismissed
+///             while is_secure_desktop() == Some(true) {
+///                 thread::sleep(Duration::from_millis(250));
+///             }
+///         }
+///         Err(CaptureError::Timeout) => continue,
+///         Err(e) => return Err(e.into()),
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn is_secure_desktop() -> Option<bool> {
+    let desktop =
+        unsafe { OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_READOBJECTS) }.ok()?;
+    let mut name = [0u16; 64];
+    let named = unsafe {
+        GetUserObjectInformationW(
+            HANDLE(desktop.0),
+            UOI_NAME,
+            Some(name.as_mut_ptr().cast()),
+            mem::size_of_val(&name) as u32,
+            None,
+        )
+    };
+    let _ = unsafe { CloseDesktop(desktop) };
+    named.ok()?;
+
+    let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+    Some(!String::from_utf16_lossy(&name[..len]).eq_ignore_ascii_case("Default"))
+}
+
+/// Returns whether the process runs in a remote desktop (RDP) session.
+///
+/// When duplication fails on every adapter in a remote session, it is
+/// reported as [`OutputDuplicationError::UnsupportedSession`] rather than
+/// [`OutputDuplicationError::NoOutput`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use dxgi_capture_rs::is_remote_session;
+///
+/// if is_remote_session() {
+///     println!("Running over RDP, capture may be unavailable");
+/// }
+/// ```
+pub fn is_remote_session() -> bool {
+    unsafe { GetSystemMetrics(SM_REMOTESESSION) != 0 }
+}
+
 // ---------------------------------------------------------------------------
 // Internal helpers
 // ---------------------------------------------------------------------------
@@ -408,36 +1714,500 @@ fn d3d11_create_device(
     Ok((device.unwrap(), device_context.unwrap()))
 }
 
-/// Enumerates the desktop-attached outputs for a given adapter and returns
-/// only the one at the requested index (if it exists).
+/// Measures the time between laps, or reports zero without reading the clock
+/// when disabled.
+struct Stopwatch(Option<Instant>);
+
+impl Stopwatch {
+    fn new(enabled: bool) -> Self {
+        Self(enabled.then(Instant::now))
+    }
+
+    /// Returns the time since the previous lap, or since creation
+    fn lap(&mut self) -> Duration {
+        match &mut self.0 {
+            Some(last) => {
+                let now = Instant::now();
+                let elapsed = now - *last;
+                *last = now;
+                elapsed
+            }
+            None => Duration::ZERO,
+        }
+    }
+}
+
+/// Returns true if `format` stores the red channel first, as opposed to the
+/// blue-first layout of `DXGI_FORMAT_B8G8R8A8_UNORM`.
+fn is_rgba_format(format: DXGI_FORMAT) -> bool {
+    matches!(
+        format,
+        DXGI_FORMAT_R8G8B8A8_TYPELESS
+            | DXGI_FORMAT_R8G8B8A8_UNORM
+            | DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+    )
+}
+
+/// Fails with `DXGI_ERROR_UNSUPPORTED` unless `format` is one of the 8-bit
+/// BGRA or RGBA formats that captures of 4-byte pixels can read. Wider formats
+/// are only duplicated when requested with
+/// [`DXGIManagerBuilder::duplication_formats`], and are read with
+/// [`DXGIManager::capture_frame_u16`].
+fn check_8bit_format(format: DXGI_FORMAT) -> Result<(), CaptureError> {
+    match format {
+        DXGI_FORMAT_B8G8R8A8_TYPELESS
+        | DXGI_FORMAT_B8G8R8A8_UNORM
+        | DXGI_FORMAT_B8G8R8A8_UNORM_SRGB => Ok(()),
+        format if is_rgba_format(format) => Ok(()),
+        _ => Err(CaptureError::Fail(windows::core::Error::from(
+            DXGI_ERROR_UNSUPPORTED,
+        ))),
+    }
+}
+
+/// Swaps the red and blue channels of `pixels` in place if the channel order
+/// of `format` differs from `order`, so that captures come out in `order`
+/// regardless of the output's channel order.
+fn convert_pixel_order<T: Copy>(pixels: &mut [T], format: DXGI_FORMAT, order: PixelOrder) {
+    if is_rgba_format(format) != (order == PixelOrder::Rgba) {
+        let bytes = unsafe {
+            slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, mem::size_of_val(pixels))
+        };
+        simd::bgra_to_rgba(bytes);
+    }
+}
+
+/// Sets the alpha channel of every 4-byte pixel in `pixels` to 255.
+fn force_opaque<T: Copy>(pixels: &mut [T]) {
+    let bytes = unsafe {
+        slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, mem::size_of_val(pixels))
+    };
+    for pixel in bytes.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+}
+
+/// Multiplies the color channels of every 4-byte pixel in `pixels` by its
+/// alpha channel, the fourth byte, in either channel order.
+fn premultiply<T: Copy>(pixels: &mut [T]) {
+    let bytes = unsafe {
+        slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, mem::size_of_val(pixels))
+    };
+    for pixel in bytes.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u16;
+        if alpha == 255 {
+            continue;
+        }
+        for channel in &mut pixel[..3] {
+            *channel = (*channel as u16 * alpha / 255) as u8;
+        }
+    }
+}
+
+/// Applies [`DXGIManager::set_force_opaque_alpha`] or
+/// [`DXGIManager::set_premultiply_alpha`] to copied pixels.
+fn apply_alpha<T: Copy>(pixels: &mut [T], opaque: bool, premultiplied: bool) {
+    if opaque {
+        force_opaque(pixels);
+    } else if premultiplied {
+        premultiply(pixels);
+    }
+}
+
+/// Resolves `DXGI_MODE_ROTATION_UNSPECIFIED`, which some drivers report when
+/// the rotation is unknown, from the current orientation of the display named
+/// `device_name`. Falls back to identity if the display settings cannot be
+/// queried. Other rotations are returned unchanged.
+fn resolve_rotation(rotation: DXGI_MODE_ROTATION, device_name: &[u16; 32]) -> DXGI_MODE_ROTATION {
+    if rotation != DXGI_MODE_ROTATION_UNSPECIFIED {
+        return rotation;
+    }
+
+    let mut mode = DEVMODEW {
+        dmSize: mem::size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    };
+    let found = unsafe {
+        EnumDisplaySettingsW(
+            PCWSTR(device_name.as_ptr()),
+            ENUM_CURRENT_SETTINGS,
+            &mut mode,
+        )
+    };
+    if !found.as_bool() {
+        return DXGI_MODE_ROTATION_IDENTITY;
+    }
+
+    match unsafe { mode.Anonymous1.Anonymous2.dmDisplayOrientation } {
+        DMDO_90 => DXGI_MODE_ROTATION_ROTATE90,
+        DMDO_180 => DXGI_MODE_ROTATION_ROTATE180,
+        DMDO_270 => DXGI_MODE_ROTATION_ROTATE270,
+        _ => DXGI_MODE_ROTATION_IDENTITY,
+    }
+}
+
+/// Returns the desktop-attached output at `index`, counting the outputs of
+/// every adapter in adapter order and then output order, together with its
+/// adapter and the adapter's enumeration index (if it exists).
 fn get_output_at_index(
-    adapter: &IDXGIAdapter1,
+    factory: &IDXGIFactory1,
     index: usize,
-) -> WindowsResult<Option<IDXGIOutput>> {
-    let mut current = 0usize;
+) -> WindowsResult<Option<(u32, IDXGIAdapter1, IDXGIOutput)>> {
+    let mut first_index = 0usize;
+    for i in 0.. {
+        let adapter = match unsafe { factory.EnumAdapters1(i) } {
+            Ok(adapter) => adapter,
+            Err(e) if e.code() == DXGI_ERROR_NOT_FOUND => break,
+            Err(e) => return Err(e),
+        };
+        let mut outputs = get_adapter_outputs(&adapter)?;
+        if index < first_index + outputs.len() {
+            let output = outputs.swap_remove(index - first_index);
+            return Ok(Some((i, adapter, output)));
+        }
+        first_index += outputs.len();
+    }
+    Ok(None)
+}
+
+/// Enumerates all desktop-attached outputs for a given adapter.
+fn get_adapter_outputs(adapter: &IDXGIAdapter1) -> WindowsResult<Vec<IDXGIOutput>> {
+    let mut outputs = Vec::new();
     for i in 0.. {
         match unsafe { adapter.EnumOutputs(i) } {
             Ok(output) => {
                 let desc: DXGI_OUTPUT_DESC = unsafe { output.GetDesc()? };
                 if desc.AttachedToDesktop.as_bool() {
-                    if current == index {
-                        return Ok(Some(output));
-                    }
-                    current += 1;
+                    outputs.push(output);
                 }
             }
             Err(_) => break,
         }
     }
-    Ok(None)
+    Ok(outputs)
+}
+
+/// Snapshots the desktop-attached outputs of every adapter, grouped by adapter
+/// in enumeration order.
+fn output_topology(factory: &IDXGIFactory1) -> WindowsResult<Vec<Vec<OutputSnapshot>>> {
+    let mut topology = Vec::new();
+    for i in 0.. {
+        let adapter = match unsafe { factory.EnumAdapters1(i) } {
+            Ok(adapter) => adapter,
+            Err(e) if e.code() == DXGI_ERROR_NOT_FOUND => break,
+            Err(e) => return Err(e),
+        };
+        let mut outputs = Vec::new();
+        for output in get_adapter_outputs(&adapter)? {
+            outputs.push(OutputSnapshot::from(&unsafe { output.GetDesc()? }));
+        }
+        topology.push(outputs);
+    }
+    Ok(topology)
+}
+
+/// Lists the desktop-attached outputs of every adapter, marking outputs whose
+/// desktop coordinates match an earlier output as mirrors of it.
+fn enumerate_outputs(factory: &IDXGIFactory1) -> WindowsResult<Vec<OutputInfo>> {
+    let mut outputs: Vec<OutputInfo> = Vec::new();
+    for adapter_index in 0.. {
+        let adapter = match unsafe { factory.EnumAdapters1(adapter_index) } {
+            Ok(adapter) => adapter,
+            Err(e) if e.code() == DXGI_ERROR_NOT_FOUND => break,
+            Err(e) => return Err(e),
+        };
+        for output in get_adapter_outputs(&adapter)? {
+            let desc = unsafe { output.GetDesc()? };
+            let RECT {
+                left,
+                top,
+                right,
+                bottom,
+            } = desc.DesktopCoordinates;
+            let desktop_rect = Rect::new(left, top, right, bottom);
+            let len = desc.DeviceName.iter().position(|&c| c == 0).unwrap_or(32);
+            // The first output with these coordinates is never a mirror itself
+            let mirror_of = outputs
+                .iter()
+                .position(|other| other.desktop_rect == desktop_rect);
+            outputs.push(OutputInfo {
+                index: outputs.len(),
+                adapter_index: adapter_index as usize,
+                device_name: String::from_utf16_lossy(&desc.DeviceName[..len]),
+                desktop_rect,
+                rotation: Rotation::from_dxgi(resolve_rotation(desc.Rotation, &desc.DeviceName)),
+                mirror_of,
+            });
+        }
+    }
+    Ok(outputs)
+}
+
+/// Largest frame accepted by default: the area of the largest Direct3D 11
+/// texture, 16384 x 16384 pixels.
+const DEFAULT_MAX_FRAME_PIXELS: usize = 16384 * 16384;
+
+/// Returns the number of values in a `width` x `height` frame with
+/// `values_per_pixel` values each, or `None` if it overflows `usize`.
+fn frame_len(width: usize, height: usize, values_per_pixel: usize) -> Option<usize> {
+    width.checked_mul(height)?.checked_mul(values_per_pixel)
+}
+
+/// Fails with [`CaptureError::FrameTooLarge`] if a `width` x `height` frame
+/// has more than `max_pixels` pixels or its BGRA size overflows `usize`.
+fn check_frame_size(width: usize, height: usize, max_pixels: usize) -> Result<(), CaptureError> {
+    match (width.checked_mul(height), frame_len(width, height, 4)) {
+        (Some(pixels), Some(_)) if pixels <= max_pixels => Ok(()),
+        _ => Err(CaptureError::FrameTooLarge { width, height }),
+    }
+}
+
+/// Checks the dimensions of `surface` with [`check_frame_size`].
+fn check_surface_size(surface: &IDXGISurface1, max_pixels: usize) -> Result<(), CaptureError> {
+    let desc = unsafe { surface.GetDesc()? };
+    check_frame_size(desc.Width as usize, desc.Height as usize, max_pixels)
+}
+
+/// Converts a timeout into whole milliseconds for DXGI, saturating at
+/// `u32::MAX`, which DXGI treats as an infinite timeout.
+fn duration_to_timeout_ms(timeout: Duration) -> u32 {
+    u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX)
+}
+
+/// Clamps a changed-area threshold to `0.0..=1.0`. NaN, which `clamp` would
+/// pass through and which no fraction ever exceeds, becomes 0.0.
+fn clamp_change_fraction(fraction: f32) -> f32 {
+    if fraction.is_nan() {
+        0.0
+    } else {
+        fraction.clamp(0.0, 1.0)
+    }
+}
+
+/// Converts a downscale factor into the mip level whose size is closest to it,
+/// i.e. `scale` snapped to the nearest power of two. Returns `None` for factors
+/// outside `(0, 1]`.
+fn mip_level_for_scale(scale: f32) -> Option<u32> {
+    if !(scale > 0.0 && scale <= 1.0) {
+        return None;
+    }
+    Some((-scale.log2()).round() as u32)
+}
+
+/// Copies a `width` x `height` image whose rows start `pitch` values apart out
+/// of `source` into `out`, applying `rotation` and then, if `flip` is set, a
+/// vertical flip, and returns the rotated dimensions. Each pixel is
+/// `values_per_pixel` consecutive values. This is the single source of truth
+/// for the rotation-aware copy logic.
+///
+/// Every index is bounds checked, so a `source` or `out` that is too small
+/// panics instead of reading or writing out of bounds.
+#[allow(clippy::too_many_arguments)]
+fn rotate_pixels<T: Copy>(
+    source: &[T],
+    pitch: usize,
+    width: usize,
+    height: usize,
+    values_per_pixel: usize,
+    rotation: Rotation,
+    flip: bool,
+    out: &mut [T],
+) -> (usize, usize) {
+    let row_len = width * values_per_pixel;
+    assert!(pitch >= row_len, "pitch is smaller than a row of pixels");
+    assert!(
+        out.len() >= row_len * height,
+        "output is smaller than the image"
+    );
+
+    let (rotated_width, rotated_height) = match rotation {
+        Rotation::Rotate90 | Rotation::Rotate270 => (height, width),
+        Rotation::Identity | Rotation::Rotate180 | Rotation::Unspecified => (width, height),
+    };
+
+    // Destination pixels in output order, each paired with the index of its
+    // source pixel
+    let pixels = out.chunks_exact_mut(values_per_pixel);
+    let copy_pixel = |(dest, index): (&mut [T], usize)| {
+        dest.copy_from_slice(&source[index..index + values_per_pixel])
+    };
+    // The outer loops below produce output rows, so walking them in reverse
+    // flips the rotated image
+    let rows = |count: usize, reverse: bool| {
+        (0..count).map(move |i| if reverse { count - 1 - i } else { i })
+    };
+
+    match rotation {
+        Rotation::Identity | Rotation::Unspecified => {
+            for (dest, i) in out
+                .chunks_exact_mut(row_len)
+                .take(height)
+                .zip(rows(height, flip))
+            {
+                dest.copy_from_slice(&source[i * pitch..i * pitch + row_len]);
+            }
+        }
+        Rotation::Rotate90 => pixels
+            .zip(rows(width, flip).flat_map(|i| {
+                (0..height)
+                    .rev()
+                    .map(move |j| j * pitch + i * values_per_pixel)
+            }))
+            .for_each(copy_pixel),
+        Rotation::Rotate180 => pixels
+            .zip(rows(height, !flip).flat_map(|i| {
+                (0..width)
+                    .rev()
+                    .map(move |j| i * pitch + j * values_per_pixel)
+            }))
+            .for_each(copy_pixel),
+        Rotation::Rotate270 => pixels
+            .zip(
+                rows(width, !flip)
+                    .flat_map(|i| (0..height).map(move |j| j * pitch + i * values_per_pixel)),
+            )
+            .for_each(copy_pixel),
+    }
+
+    (rotated_width, rotated_height)
+}
+
+/// Copies row `row` of the image [`rotate_pixels`] would produce from the
+/// `width` x `height` BGRA image in `source` into `out`, which must hold
+/// exactly one rotated row of pixels.
+fn rotated_row(
+    source: &[u8],
+    pitch: usize,
+    (width, height): (usize, usize),
+    rotation: Rotation,
+    flip: bool,
+    row: usize,
+    out: &mut [u8],
+) {
+    let rotated_height = match rotation {
+        Rotation::Rotate90 | Rotation::Rotate270 => width,
+        Rotation::Identity | Rotation::Rotate180 | Rotation::Unspecified => height,
+    };
+    let row = if flip { rotated_height - 1 - row } else { row };
+    let index = |x: usize, y: usize| y * pitch + x * 4;
+
+    for (column, dest) in out.chunks_exact_mut(4).enumerate() {
+        let source_index = match rotation {
+            Rotation::Identity | Rotation::Unspecified => index(column, row),
+            Rotation::Rotate90 => index(row, height - 1 - column),
+            Rotation::Rotate180 => index(width - 1 - column, height - 1 - row),
+            Rotation::Rotate270 => index(width - 1 - row, column),
+        };
+        dest.copy_from_slice(&source[source_index..source_index + 4]);
+    }
+}
+
+/// Hashes the first `row_bytes` bytes of each of `height` rows that are `pitch`
+/// bytes apart, skipping row padding. Words are mixed eight bytes at a time in
+/// the style of FxHash, which is fast but not cryptographic.
+fn hash_rows(data: &[u8], pitch: usize, row_bytes: usize, height: usize) -> u64 {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+    let mix = |hash: u64, word: u64| (hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+
+    let mut hash = 0;
+    for row in data.chunks(pitch).take(height) {
+        let mut words = row[..row_bytes].chunks_exact(8);
+        for word in &mut words {
+            hash = mix(hash, u64::from_le_bytes(word.try_into().unwrap()));
+        }
+        for &byte in words.remainder() {
+            hash = mix(hash, byte as u64);
+        }
+    }
+    hash
+}
+
+/// Fetches a metadata array using the size-query pattern of
+/// `GetFrameDirtyRects` and `GetFrameMoveRects`.
+///
+/// The required size can grow between the size query and the fetch during
+/// rapid UI changes, in which case `fetch` reports `DXGI_ERROR_MORE_DATA` with
+/// the new size and the buffer is regrown. Gives up with an empty list after a
+/// few attempts or on any other error. Returns `None` without growing the
+/// buffer if more than `max_len` elements are required.
+///
+/// `buffer` is scratch space kept across frames: all of its capacity is
+/// offered to `fetch`, so it is only reallocated when a frame needs more.
+fn fetch_frame_metadata<T: Copy + Default>(
+    buffer: &mut Vec<T>,
+    max_len: usize,
+    mut fetch: impl FnMut(u32, *mut T, &mut u32) -> WindowsResult<()>,
+) -> Option<&[T]> {
+    const MAX_ATTEMPTS: usize = 4;
+
+    buffer.resize(buffer.capacity(), T::default());
+    for _ in 0..MAX_ATTEMPTS {
+        let buffer_size = mem::size_of_val(buffer.as_slice()) as u32;
+        let mut required_size = 0u32;
+        match fetch(buffer_size, buffer.as_mut_ptr(), &mut required_size) {
+            Ok(()) => {
+                // On success the size is the number of bytes actually written
+                let len = required_size as usize / mem::size_of::<T>();
+                if len > max_len {
+                    return None;
+                }
+                buffer.truncate(len);
+                return Some(buffer);
+            }
+            Err(e) if e.code() == DXGI_ERROR_MORE_DATA => {
+                let len = (required_size as usize).div_ceil(mem::size_of::<T>());
+                if len > max_len {
+                    return None;
+                }
+                buffer.resize(len, T::default());
+            }
+            Err(_) => break,
+        }
+    }
+    buffer.clear();
+    Some(buffer)
+}
+
+/// Returns true if `e` means the Direct3D device was removed or reset.
+fn is_device_lost(e: &windows::core::Error) -> bool {
+    let code = e.code();
+    code == DXGI_ERROR_DEVICE_REMOVED || code == DXGI_ERROR_DEVICE_RESET
+}
+
+/// Maps a failure to re-acquire the output duplication into a
+/// [`CaptureError`]. Duplication fails while the secure desktop is active,
+/// which becomes [`CaptureError::DesktopSwitch`] like the error that caused
+/// the re-acquisition, and with `DXGI_ERROR_SESSION_DISCONNECTED` while the
+/// session is disconnected, which becomes [`CaptureError::SessionDisconnected`].
+fn map_refresh_error(e: OutputDuplicationError) -> CaptureError {
+    if let OutputDuplicationError::DeviceError(err) = &e
+        && err.code() == DXGI_ERROR_SESSION_DISCONNECTED
+    {
+        CaptureError::SessionDisconnected
+    } else if is_secure_desktop() == Some(true) {
+        CaptureError::DesktopSwitch
+    } else {
+        CaptureError::RefreshFailure(e)
+    }
 }
 
 /// Maps a Windows error from a capture operation into the appropriate
 /// [`CaptureError`] variant.
+///
+/// Lost or denied access while the secure desktop is active becomes
+/// [`CaptureError::DesktopSwitch`].
 fn map_capture_error(e: windows::core::Error) -> CaptureError {
     let code = e.code();
-    if code == DXGI_ERROR_ACCESS_LOST {
+    if (code == DXGI_ERROR_ACCESS_LOST || code == DXGI_ERROR_ACCESS_DENIED)
+        && is_secure_desktop() == Some(true)
+    {
+        CaptureError::DesktopSwitch
+    } else if code == DXGI_ERROR_ACCESS_LOST {
         CaptureError::AccessLost
+    } else if is_device_lost(&e) {
+        CaptureError::DeviceLost
+    } else if code == DXGI_ERROR_SESSION_DISCONNECTED {
+        CaptureError::SessionDisconnected
     } else if code == DXGI_ERROR_WAIT_TIMEOUT {
         CaptureError::Timeout
     } else if code == DXGI_ERROR_ACCESS_DENIED {
@@ -456,20 +2226,548 @@ struct DuplicatedOutput {
     device_context: ID3D11DeviceContext,
     output: IDXGIOutput1,
     output_duplication: IDXGIOutputDuplication,
+    /// Staging textures reused across captures, one per [`StagingSlot`], with
+    /// the desc each was created from.
+    staging_textures: [Option<(ID3D11Texture2D, D3D11_TEXTURE2D_DESC)>; 3],
+    /// Mip chain texture of the scaled path and its shader resource view,
+    /// reused across captures, with the desc the texture was created from.
+    mip_texture: Option<(
+        ID3D11Texture2D,
+        ID3D11ShaderResourceView,
+        D3D11_TEXTURE2D_DESC,
+    )>,
+    /// Whether staging textures are created with CPU write access in addition
+    /// to read access.
+    cpu_write_access: bool,
+    /// Last reported pointer position and visibility. DXGI only fills in the
+    /// pointer when `LastMouseUpdateTime` is non-zero.
+    pointer_position: (i32, i32),
+    pointer_visible: bool,
+    /// Whether protected content was masked out of the last acquired frame.
+    protected_content_masked_out: bool,
+    /// Number of desktop updates accumulated into the last acquired frame.
+    accumulated_frames: u32,
+    /// Whether DXGI coalesced the rectangles of the last acquired frame.
+    rects_coalesced: bool,
+    /// Last reported pointer shape. DXGI only provides it when it changes.
+    pointer_shape: Option<cursor::PointerShape>,
+    /// Whether the last acquired frame is still held because its texture was
+    /// mapped directly or lazy release is enabled. It is released before the
+    /// next frame is acquired.
+    frame_held: bool,
+    /// Whether frames are kept acquired until the next acquire instead of
+    /// being released as soon as they are copied.
+    lazy_release: bool,
+    /// Most dirty or move rectangles read per frame, see
+    /// [`DXGIManager::set_max_rects`].
+    max_rects: usize,
+    /// Surface left mapped for the rows handed out by
+    /// [`DXGIManager::capture_frame_rows`], unmapped before the next acquire.
+    rows_surface: Option<IDXGISurface1>,
+    /// Converted copy of an RGBA surface for [`DXGIManager::capture_frame_rows`]
+    rows_buffer: Vec<BGRA8>,
+    /// Scratch buffers for the raw rectangles of a frame, reused across
+    /// frames by [`DuplicatedOutput::extract_frame_metadata`].
+    dirty_rects_buffer: Vec<RECT>,
+    move_rects_buffer: Vec<DXGI_OUTDUPL_MOVE_RECT>,
+    /// Size of the last acquired desktop texture, which may be padded beyond
+    /// the desktop span.
+    texture_size: Option<(u32, u32)>,
+    #[cfg(test)]
+    staging_textures_created: usize,
 }
 
-impl DuplicatedOutput {
+/// Which capture path a cached staging texture belongs to. The full-frame,
+/// region and scaled paths copy into textures of different sizes, so each
+/// keeps its own and alternating between them does not recreate textures.
+#[derive(Clone, Copy)]
+enum StagingSlot {
+    Full,
+    Region,
+    Scaled,
+}
+
+impl DuplicatedOutput {
+    /// Creates a D3D11 device on `adapter` and duplicates `output` with it.
+    fn new(
+        adapter: &IDXGIAdapter1,
+        output: IDXGIOutput,
+        cpu_write_access: bool,
+        formats: &[DXGI_FORMAT],
+    ) -> WindowsResult<Self> {
+        let (device, device_context) = d3d11_create_device(Some(&adapter.cast()?))?;
+        Self::with_device(device, device_context, output, cpu_write_access, formats)
+    }
+
+    /// Duplicates `output` with an existing device, which must have been
+    /// created on the adapter the output belongs to.
+    ///
+    /// If `formats` is not empty, `IDXGIOutput5::DuplicateOutput1` is tried
+    /// first with those formats, falling back to `DuplicateOutput` when it is
+    /// unavailable or fails.
+    fn with_device(
+        device: ID3D11Device,
+        device_context: ID3D11DeviceContext,
+        output: IDXGIOutput,
+        cpu_write_access: bool,
+        formats: &[DXGI_FORMAT],
+    ) -> WindowsResult<Self> {
+        let output: IDXGIOutput1 = output.cast()?;
+        let output_duplication = match output.cast::<IDXGIOutput5>() {
+            Ok(output5) if !formats.is_empty() => {
+                match unsafe { output5.DuplicateOutput1(&device, 0, formats) } {
+                    Ok(output_duplication) => output_duplication,
+                    Err(_) => unsafe { output.DuplicateOutput(&device)? },
+                }
+            }
+            _ => unsafe { output.DuplicateOutput(&device)? },
+        };
+        Ok(Self {
+            device,
+            device_context,
+            output,
+            output_duplication,
+            staging_textures: Default::default(),
+            mip_texture: None,
+            cpu_write_access,
+            pointer_position: (0, 0),
+            pointer_visible: false,
+            protected_content_masked_out: false,
+            accumulated_frames: 0,
+            rects_coalesced: false,
+            pointer_shape: None,
+            frame_held: false,
+            lazy_release: false,
+            max_rects: usize::MAX,
+            rows_surface: None,
+            rows_buffer: Vec::new(),
+            dirty_rects_buffer: Vec::new(),
+            move_rects_buffer: Vec::new(),
+            texture_size: None,
+            #[cfg(test)]
+            staging_textures_created: 0,
+        })
+    }
+
     fn get_desc(&self) -> WindowsResult<DXGI_OUTPUT_DESC> {
         unsafe { self.output.GetDesc() }
     }
 
+    /// Returns the rotation of the output, resolving an unspecified one.
+    fn rotation(&self) -> WindowsResult<DXGI_MODE_ROTATION> {
+        let desc = self.get_desc()?;
+        Ok(resolve_rotation(desc.Rotation, &desc.DeviceName))
+    }
+
+    /// Returns the size of the desktop in the native orientation of the
+    /// display. Desktop textures can be larger than this; only this part of
+    /// them holds the desktop image.
+    fn desktop_span(&self) -> WindowsResult<(u32, u32)> {
+        let desc = self.get_desc()?;
+        let RECT {
+            left,
+            top,
+            right,
+            bottom,
+        } = desc.DesktopCoordinates;
+        let (width, height) = ((right - left) as u32, (bottom - top) as u32);
+        match resolve_rotation(desc.Rotation, &desc.DeviceName) {
+            DXGI_MODE_ROTATION_ROTATE90 | DXGI_MODE_ROTATION_ROTATE270 => Ok((height, width)),
+            _ => Ok((width, height)),
+        }
+    }
+
+    fn get_duplication_desc(&self) -> DXGI_OUTDUPL_DESC {
+        unsafe { self.output_duplication.GetDesc() }
+    }
+
+    /// Returns the number of pixels covered by the dirty and move rectangles
+    /// of `metadata`, clipped to the desktop image, and the total number of
+    /// pixels. Overlapping rectangles are counted once per rectangle, capped
+    /// at the total. Coalesced or unavailable rectangles count as the whole
+    /// desktop, and frames without a desktop update as nothing.
+    fn changed_area(&self, metadata: &FrameMetadata) -> (u64, u64) {
+        let mode = self.get_duplication_desc().ModeDesc;
+        let total = mode.Width as u64 * mode.Height as u64;
+        if !metadata.is_desktop_update() {
+            return (0, total);
+        }
+        if metadata.rects_coalesced || !metadata.metadata_available {
+            return (total, total);
+        }
+
+        let mut metadata = metadata.clone();
+        metadata.clamp_to(mode.Width as i32, mode.Height as i32);
+        let area = |rect: &Rect| rect.width() as u64 * rect.height() as u64;
+        let changed = metadata.dirty_rects.iter().map(area).sum::<u64>()
+            + metadata
+                .move_rects
+                .iter()
+                .map(|move_rect| area(&move_rect.destination_rect))
+                .sum::<u64>();
+        (changed.min(total), total)
+    }
+
+    /// Returns the CPU access flags for staging textures.
+    fn staging_cpu_access_flags(&self) -> u32 {
+        if self.cpu_write_access {
+            (D3D11_CPU_ACCESS_READ.0 | D3D11_CPU_ACCESS_WRITE.0) as u32
+        } else {
+            D3D11_CPU_ACCESS_READ.0 as u32
+        }
+    }
+
+    /// Returns a staging texture matching `desc`, reusing the one cached for
+    /// `slot` when the desc is unchanged and recreating it otherwise (e.g.
+    /// after a resolution or format change).
+    fn get_staging_texture(
+        &mut self,
+        slot: StagingSlot,
+        desc: &D3D11_TEXTURE2D_DESC,
+    ) -> WindowsResult<ID3D11Texture2D> {
+        if let Some((texture, cached_desc)) = &self.staging_textures[slot as usize]
+            && cached_desc == desc
+        {
+            return Ok(texture.clone());
+        }
+
+        let mut staged_texture: Option<ID3D11Texture2D> = None;
+        unsafe {
+            self.device
+                .CreateTexture2D(desc, None, Some(&mut staged_texture))?
+        };
+        let staged_texture = staged_texture.unwrap();
+
+        self.staging_textures[slot as usize] = Some((staged_texture.clone(), *desc));
+        #[cfg(test)]
+        {
+            self.staging_textures_created += 1;
+        }
+        Ok(staged_texture)
+    }
+
+    /// Returns a mip chain texture matching `desc` and a shader resource view
+    /// of it, reusing the cached pair when the desc is unchanged and
+    /// recreating both otherwise, like [`DuplicatedOutput::get_staging_texture`].
+    fn get_mip_texture(
+        &mut self,
+        desc: &D3D11_TEXTURE2D_DESC,
+    ) -> WindowsResult<(ID3D11Texture2D, ID3D11ShaderResourceView)> {
+        if let Some((texture, view, cached_desc)) = &self.mip_texture
+            && cached_desc == desc
+        {
+            return Ok((texture.clone(), view.clone()));
+        }
+
+        let mut mip_texture: Option<ID3D11Texture2D> = None;
+        unsafe {
+            self.device
+                .CreateTexture2D(desc, None, Some(&mut mip_texture))?
+        };
+        let mip_texture = mip_texture.unwrap();
+
+        let mut view: Option<ID3D11ShaderResourceView> = None;
+        unsafe {
+            self.device
+                .CreateShaderResourceView(&mip_texture, None, Some(&mut view))?
+        };
+        let view = view.unwrap();
+
+        self.mip_texture = Some((mip_texture.clone(), view.clone(), *desc));
+        Ok((mip_texture, view))
+    }
+
+    /// Records the state of an acquired frame that outlives it: whether
+    /// protected content was masked out, how many updates it accumulated, the
+    /// pointer state if the frame carries a mouse update (otherwise the pointer
+    /// fields are stale), and the pointer shape if it changed.
+    fn update_frame_state(&mut self, frame_info: &DXGI_OUTDUPL_FRAME_INFO) {
+        self.protected_content_masked_out = frame_info.ProtectedContentMaskedOut.as_bool();
+        self.accumulated_frames = frame_info.AccumulatedFrames;
+        self.rects_coalesced = frame_info.RectsCoalesced.as_bool();
+        if frame_info.LastMouseUpdateTime != 0 {
+            self.pointer_position = (
+                frame_info.PointerPosition.Position.x,
+                frame_info.PointerPosition.Position.y,
+            );
+            self.pointer_visible = frame_info.PointerPosition.Visible.as_bool();
+        }
+        if frame_info.PointerShapeBufferSize > 0 {
+            // A shape that cannot be read is dropped rather than drawn stale
+            self.pointer_shape = self
+                .get_pointer_shape(frame_info.PointerShapeBufferSize)
+                .ok();
+        }
+    }
+
+    /// Reads the pointer shape of the currently acquired frame.
+    fn get_pointer_shape(&self, buffer_size: u32) -> WindowsResult<cursor::PointerShape> {
+        let mut data = vec![0u8; buffer_size as usize];
+        let mut required_size = 0u32;
+        let mut info = DXGI_OUTDUPL_POINTER_SHAPE_INFO::default();
+        unsafe {
+            self.output_duplication.GetFramePointerShape(
+                buffer_size,
+                data.as_mut_ptr() as *mut _,
+                &mut required_size,
+                &mut info,
+            )?
+        };
+        data.truncate(required_size as usize);
+        Ok(cursor::PointerShape { info, data })
+    }
+
+    /// Returns the last known pointer shape and position if the pointer is
+    /// visible and its shape is known.
+    fn pointer_layer(&self) -> Option<(PointerShape, (i32, i32))> {
+        let shape = self
+            .pointer_shape
+            .as_ref()
+            .filter(|_| self.pointer_visible)?;
+        let shape = PointerShape::from_dxgi(&shape.info, &shape.data)?;
+        Some((shape, self.pointer_position))
+    }
+
+    /// Draws the last known pointer into `frame`, a tightly packed `width` x
+    /// `height` image in `order` whose rows run bottom to top if `flip` is
+    /// set, if the pointer is visible and its shape is known.
+    fn composite_pointer(
+        &self,
+        frame: &mut [u8],
+        width: usize,
+        height: usize,
+        order: PixelOrder,
+        flip: bool,
+    ) {
+        if let Some(shape) = &self.pointer_shape
+            && self.pointer_visible
+        {
+            cursor::composite(
+                frame,
+                width,
+                height,
+                order,
+                flip,
+                shape,
+                self.pointer_position,
+            );
+        }
+    }
+
+    /// Like [`DuplicatedOutput::composite_pointer`], for a `frame` that is a
+    /// region of the full frame with its top-left corner at `origin`.
+    fn composite_pointer_at(
+        &self,
+        frame: &mut [u8],
+        width: usize,
+        height: usize,
+        order: PixelOrder,
+        origin: (i32, i32),
+    ) {
+        if let Some(shape) = &self.pointer_shape
+            && self.pointer_visible
+        {
+            let (x, y) = self.pointer_position;
+            let position = (x - origin.0, y - origin.1);
+            cursor::composite(frame, width, height, order, false, shape, position);
+        }
+    }
+
     /// Acquires a frame, optionally extracts metadata, copies it to a staging
     /// texture, releases the DXGI frame, and returns the mapped surface.
     fn capture_frame_to_surface(
         &mut self,
         timeout_ms: u32,
-        with_metadata: bool,
+        metadata: Option<MetadataFlags>,
     ) -> WindowsResult<(IDXGISurface1, Option<FrameMetadata>)> {
+        let (texture, frame_info) = self.acquire_next_frame(timeout_ms)?;
+
+        let metadata = match metadata {
+            Some(flags) => Some(self.extract_frame_metadata(&frame_info, flags)?),
+            None => None,
+        };
+
+        Ok((self.finish_frame(&texture)?, metadata))
+    }
+
+    /// Like [`DuplicatedOutput::capture_frame_to_surface`], but returns the
+    /// frame info as reported by DXGI instead of the parsed metadata.
+    #[cfg(feature = "raw-dxgi")]
+    fn capture_frame_info_to_surface(
+        &mut self,
+        timeout_ms: u32,
+    ) -> WindowsResult<(IDXGISurface1, DXGI_OUTDUPL_FRAME_INFO)> {
+        let (texture, frame_info) = self.acquire_next_frame(timeout_ms)?;
+        Ok((self.finish_frame(&texture)?, frame_info))
+    }
+
+    /// Acquires a frame, copies it into a new texture that is shared through
+    /// an NT handle and guarded by a keyed mutex, and releases the frame. The
+    /// mutex is released with key 1 once the copy is queued. Returns the
+    /// handle, which the caller owns.
+    #[cfg(feature = "raw-dxgi")]
+    fn capture_frame_to_shared_handle(&mut self, timeout_ms: u32) -> WindowsResult<HANDLE> {
+        let (texture, _) = self.acquire_next_frame(timeout_ms)?;
+        let handle = self.copy_to_shared_texture(&texture);
+        unsafe { self.output_duplication.ReleaseFrame()? };
+        handle
+    }
+
+    /// Copies `texture` into a new shareable texture and returns an NT handle
+    /// to it. See [`DuplicatedOutput::capture_frame_to_shared_handle`].
+    #[cfg(feature = "raw-dxgi")]
+    fn copy_to_shared_texture(&self, texture: &ID3D11Texture2D) -> WindowsResult<HANDLE> {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { texture.GetDesc(&mut desc) };
+        desc.MipLevels = 1;
+        desc.ArraySize = 1;
+        desc.Usage = D3D11_USAGE_DEFAULT;
+        desc.BindFlags = D3D11_BIND_SHADER_RESOURCE.0 as u32;
+        desc.CPUAccessFlags = 0;
+        desc.MiscFlags = (D3D11_RESOURCE_MISC_SHARED_NTHANDLE.0
+            | D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX.0) as u32;
+
+        let mut shared_texture: Option<ID3D11Texture2D> = None;
+        unsafe {
+            self.device
+                .CreateTexture2D(&desc, None, Some(&mut shared_texture))?
+        };
+        let shared_texture = shared_texture.unwrap();
+
+        // Nobody else can hold the mutex of a new texture, so this never waits
+        let mutex: IDXGIKeyedMutex = shared_texture.cast()?;
+        unsafe { mutex.AcquireSync(0, 0)? };
+        unsafe { self.device_context.CopyResource(&shared_texture, texture) };
+        unsafe { mutex.ReleaseSync(1)? };
+
+        let resource: IDXGIResource1 = shared_texture.cast()?;
+        unsafe {
+            resource.CreateSharedHandle(
+                None,
+                (DXGI_SHARED_RESOURCE_READ | DXGI_SHARED_RESOURCE_WRITE).0,
+                PCWSTR::null(),
+            )
+        }
+    }
+
+    /// Returns a mappable surface for the acquired `texture`, releasing the
+    /// frame unless the texture itself is mapped or lazy release is enabled.
+    fn finish_frame(&mut self, texture: &ID3D11Texture2D) -> WindowsResult<IDXGISurface1> {
+        let (surface, direct) = self.mappable_surface(texture)?;
+
+        // A directly mapped texture is only valid while the frame is acquired
+        if direct || self.lazy_release {
+            self.frame_held = true;
+        } else {
+            unsafe { self.output_duplication.ReleaseFrame()? };
+        }
+
+        Ok(surface)
+    }
+
+    /// Acquires a frame and returns its surface if the desktop image was
+    /// updated. Frames that only update the pointer are released right away
+    /// and yield `None`.
+    fn capture_desktop_layer_to_surface(
+        &mut self,
+        timeout_ms: u32,
+    ) -> WindowsResult<Option<IDXGISurface1>> {
+        let (texture, frame_info) = self.acquire_next_frame(timeout_ms)?;
+        if frame_info.LastPresentTime == 0 {
+            unsafe { self.output_duplication.ReleaseFrame()? };
+            return Ok(None);
+        }
+        Ok(Some(self.finish_frame(&texture)?))
+    }
+
+    /// Acquires frames until one has dirty or move rectangles covering more
+    /// than `min_fraction` of the desktop, releasing the others, and returns
+    /// its surface and full metadata. Fails with `DXGI_ERROR_WAIT_TIMEOUT`
+    /// once `deadline` passes without such a frame.
+    fn capture_changed_to_surface(
+        &mut self,
+        deadline: Instant,
+        min_fraction: f32,
+    ) -> WindowsResult<(IDXGISurface1, FrameMetadata)> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let (texture, frame_info) =
+                self.acquire_next_frame(duration_to_timeout_ms(remaining))?;
+            let metadata = match self.extract_frame_metadata(&frame_info, MetadataFlags::ALL) {
+                Ok(metadata)
+                    if metadata.has_updates()
+                        && (min_fraction <= 0.0 || {
+                            let (changed, total) = self.changed_area(&metadata);
+                            changed as f64 > min_fraction as f64 * total as f64
+                        }) =>
+                {
+                    metadata
+                }
+                result => {
+                    unsafe { self.output_duplication.ReleaseFrame()? };
+                    result?;
+                    if remaining.is_zero() {
+                        return Err(DXGI_ERROR_WAIT_TIMEOUT.into());
+                    }
+                    continue;
+                }
+            };
+
+            return Ok((self.finish_frame(&texture)?, metadata));
+        }
+    }
+
+    /// Acquires a frame and then every frame that is immediately available,
+    /// up to `max_drain` more, copying each into the staging texture and
+    /// releasing it. Returns the staging surface holding the last frame and
+    /// the number of frames skipped before it.
+    fn drain_to_surface(
+        &mut self,
+        mut timeout_ms: u32,
+        max_drain: usize,
+    ) -> WindowsResult<(IDXGISurface1, usize)> {
+        let mut latest = None;
+        let mut skipped = 0;
+        while latest.is_none() || skipped < max_drain {
+            let texture = match self.acquire_next_frame(timeout_ms) {
+                Ok((texture, _)) => texture,
+                Err(e) if latest.is_some() && e.code() == DXGI_ERROR_WAIT_TIMEOUT => break,
+                Err(e) => return Err(e),
+            };
+            // Always copy, as a directly mapped texture is invalid once released
+            let surface = self.copy_to_staging(&texture);
+            unsafe { self.output_duplication.ReleaseFrame()? };
+
+            if latest.replace(surface?).is_some() {
+                skipped += 1;
+            }
+            timeout_ms = 0;
+        }
+        // The loop only ends once a frame was acquired
+        Ok((latest.unwrap(), skipped))
+    }
+
+    /// Releases a frame that was kept acquired for direct mapping or lazy
+    /// release, which must happen before the next `AcquireNextFrame`. A
+    /// surface mapped for row iteration is unmapped first.
+    fn release_held_frame(&mut self) -> WindowsResult<()> {
+        if let Some(surface) = self.rows_surface.take() {
+            unsafe { surface.Unmap()? };
+        }
+        if mem::take(&mut self.frame_held) {
+            unsafe { self.output_duplication.ReleaseFrame()? };
+        }
+        Ok(())
+    }
+
+    /// Acquires the next frame and records its pointer and protection state.
+    /// The caller is responsible for calling `ReleaseFrame` once done with the
+    /// returned texture.
+    fn acquire_next_frame(
+        &mut self,
+        timeout_ms: u32,
+    ) -> WindowsResult<(ID3D11Texture2D, DXGI_OUTDUPL_FRAME_INFO)> {
+        self.release_held_frame()?;
         let mut resource: Option<IDXGIResource> = None;
         let mut frame_info: DXGI_OUTDUPL_FRAME_INFO = unsafe { mem::zeroed() };
 
@@ -477,146 +2775,813 @@ impl DuplicatedOutput {
             self.output_duplication
                 .AcquireNextFrame(timeout_ms, &mut frame_info, &mut resource)?
         };
+        self.update_frame_state(&frame_info);
+
+        match resource.unwrap().cast::<ID3D11Texture2D>() {
+            Ok(texture) => {
+                let mut desc = D3D11_TEXTURE2D_DESC::default();
+                unsafe { texture.GetDesc(&mut desc) };
+                self.texture_size = Some((desc.Width, desc.Height));
+                Ok((texture, frame_info))
+            }
+            Err(e) => {
+                let _ = unsafe { self.output_duplication.ReleaseFrame() };
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns a surface with the contents of an acquired desktop texture,
+    /// ready to be mapped, and whether it is the texture itself.
+    ///
+    /// On WARP and other software adapters the desktop texture may already be
+    /// a CPU-readable staging texture. It is then mapped directly, skipping
+    /// the copy, and the frame must stay acquired while the surface is in use.
+    /// Otherwise, or if the texture is padded beyond the desktop span, the
+    /// texture is copied into the cached staging texture.
+    fn mappable_surface(
+        &mut self,
+        texture: &ID3D11Texture2D,
+    ) -> WindowsResult<(IDXGISurface1, bool)> {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { texture.GetDesc(&mut desc) };
+        let required = self.staging_cpu_access_flags();
+        let (span_width, span_height) = self.desktop_span()?;
+        if desc.Usage == D3D11_USAGE_STAGING
+            && desc.CPUAccessFlags & required == required
+            && desc.Width <= span_width
+            && desc.Height <= span_height
+        {
+            return Ok((texture.cast()?, true));
+        }
+        Ok((self.copy_to_staging(texture)?, false))
+    }
 
-        let metadata = if with_metadata {
-            Some(self.extract_frame_metadata(&frame_info)?)
+    /// Copies an acquired desktop texture into the cached staging texture and
+    /// returns it as a surface ready to be mapped.
+    ///
+    /// Only the desktop span is copied, so the staging texture, and with it
+    /// every frame, has the width and height of the desktop even when the
+    /// desktop texture carries padding on its right or bottom edge.
+    fn copy_to_staging(&mut self, texture: &ID3D11Texture2D) -> WindowsResult<IDXGISurface1> {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { texture.GetDesc(&mut desc) };
+        let (span_width, span_height) = self.desktop_span()?;
+        let padded = desc.Width > span_width || desc.Height > span_height;
+        desc.Width = desc.Width.min(span_width);
+        desc.Height = desc.Height.min(span_height);
+        desc.Usage = D3D11_USAGE_STAGING;
+        desc.BindFlags = 0;
+        desc.CPUAccessFlags = self.staging_cpu_access_flags();
+        desc.MiscFlags = 0;
+
+        let staged_texture = self.get_staging_texture(StagingSlot::Full, &desc)?;
+
+        if padded {
+            let source_box = D3D11_BOX {
+                left: 0,
+                top: 0,
+                front: 0,
+                right: desc.Width,
+                bottom: desc.Height,
+                back: 1,
+            };
+            unsafe {
+                self.device_context.CopySubresourceRegion(
+                    &staged_texture,
+                    0,
+                    0,
+                    0,
+                    0,
+                    texture,
+                    0,
+                    Some(&source_box),
+                )
+            };
         } else {
-            None
-        };
+            unsafe { self.device_context.CopyResource(&staged_texture, texture) };
+        }
 
-        let texture: ID3D11Texture2D = resource.unwrap().cast()?;
+        staged_texture.cast()
+    }
+
+    /// Acquires a frame, copies the part inside `region` into a staging
+    /// texture of that size, and releases the frame. `region` is clipped to
+    /// the desktop texture, which may have changed size since the caller
+    /// checked it; if nothing is left, `E_INVALIDARG` is returned.
+    fn capture_region_to_surface(
+        &mut self,
+        timeout_ms: u32,
+        region: Rect,
+    ) -> WindowsResult<IDXGISurface1> {
+        let (texture, _) = self.acquire_next_frame(timeout_ms)?;
+        let surface = self.copy_region_to_staging(&texture, region);
+        unsafe { self.output_duplication.ReleaseFrame()? };
+        surface
+    }
+
+    /// Copies the part of `texture` inside `region` into the cached region
+    /// staging texture, sized to the region, and returns it ready to be mapped.
+    fn copy_region_to_staging(
+        &mut self,
+        texture: &ID3D11Texture2D,
+        region: Rect,
+    ) -> WindowsResult<IDXGISurface1> {
         let mut desc = D3D11_TEXTURE2D_DESC::default();
         unsafe { texture.GetDesc(&mut desc) };
+        let (span_width, span_height) = self.desktop_span()?;
+        let bounds = Rect::new(
+            0,
+            0,
+            desc.Width.min(span_width) as i32,
+            desc.Height.min(span_height) as i32,
+        );
+        let region = region
+            .intersect(&bounds)
+            .ok_or(windows::core::Error::from(E_INVALIDARG))?;
+
+        desc.Width = region.width() as u32;
+        desc.Height = region.height() as u32;
+        desc.MipLevels = 1;
+        desc.ArraySize = 1;
         desc.Usage = D3D11_USAGE_STAGING;
         desc.BindFlags = 0;
-        desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ.0 as u32;
+        desc.CPUAccessFlags = self.staging_cpu_access_flags();
         desc.MiscFlags = 0;
 
-        let mut staged_texture: Option<ID3D11Texture2D> = None;
+        let staged_texture = self.get_staging_texture(StagingSlot::Region, &desc)?;
+
+        let source_box = D3D11_BOX {
+            left: region.left as u32,
+            top: region.top as u32,
+            front: 0,
+            right: region.right as u32,
+            bottom: region.bottom as u32,
+            back: 1,
+        };
         unsafe {
-            self.device
-                .CreateTexture2D(&desc, None, Some(&mut staged_texture))?
+            self.device_context.CopySubresourceRegion(
+                &staged_texture,
+                0,
+                0,
+                0,
+                0,
+                texture,
+                0,
+                Some(&source_box),
+            )
         };
-        let staged_texture = staged_texture.unwrap();
 
-        unsafe { self.device_context.CopyResource(&staged_texture, &texture) };
+        staged_texture.cast()
+    }
+
+    /// Acquires a frame, extracts its metadata, and releases it again without
+    /// copying any pixels. The frame is released even if extraction fails.
+    fn peek_frame_metadata(
+        &mut self,
+        timeout_ms: u32,
+        flags: MetadataFlags,
+    ) -> WindowsResult<FrameMetadata> {
+        self.release_held_frame()?;
+        let mut resource: Option<IDXGIResource> = None;
+        let mut frame_info: DXGI_OUTDUPL_FRAME_INFO = unsafe { mem::zeroed() };
+
+        unsafe {
+            self.output_duplication
+                .AcquireNextFrame(timeout_ms, &mut frame_info, &mut resource)?
+        };
+        self.update_frame_state(&frame_info);
+
+        let metadata = self.extract_frame_metadata(&frame_info, flags);
+        unsafe { self.output_duplication.ReleaseFrame()? };
+        metadata
+    }
+
+    /// Acquires a frame, downsamples it on the GPU by generating mips, and
+    /// copies mip level `mip_level` into a staging texture. The level is
+    /// clamped so that neither dimension drops below one pixel. Only the
+    /// desktop span is copied into the mip texture, so padding on the right or
+    /// bottom edge of the desktop texture is never averaged into the result.
+    /// Returns the mapped surface and its dimensions.
+    fn capture_scaled_frame_to_surface(
+        &mut self,
+        timeout_ms: u32,
+        mip_level: u32,
+    ) -> WindowsResult<(IDXGISurface1, (usize, usize))> {
+        self.release_held_frame()?;
+        let mut resource: Option<IDXGIResource> = None;
+        let mut frame_info: DXGI_OUTDUPL_FRAME_INFO = unsafe { mem::zeroed() };
+
+        unsafe {
+            self.output_duplication
+                .AcquireNextFrame(timeout_ms, &mut frame_info, &mut resource)?
+        };
+        self.update_frame_state(&frame_info);
+
+        let texture: ID3D11Texture2D = resource.unwrap().cast()?;
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { texture.GetDesc(&mut desc) };
+        let (span_width, span_height) = self.desktop_span()?;
+        desc.Width = desc.Width.min(span_width);
+        desc.Height = desc.Height.min(span_height);
+
+        let max_level = desc.Width.max(desc.Height).ilog2();
+        let mip_level = mip_level.min(max_level);
+
+        let mut mip_desc = desc;
+        mip_desc.MipLevels = mip_level + 1;
+        mip_desc.ArraySize = 1;
+        mip_desc.Usage = D3D11_USAGE_DEFAULT;
+        mip_desc.BindFlags = (D3D11_BIND_RENDER_TARGET.0 | D3D11_BIND_SHADER_RESOURCE.0) as u32;
+        mip_desc.CPUAccessFlags = 0;
+        mip_desc.MiscFlags = D3D11_RESOURCE_MISC_GENERATE_MIPS.0 as u32;
+
+        let (mip_texture, view) = self.get_mip_texture(&mip_desc)?;
+
+        let source_box = D3D11_BOX {
+            left: 0,
+            top: 0,
+            front: 0,
+            right: desc.Width,
+            bottom: desc.Height,
+            back: 1,
+        };
+        unsafe {
+            self.device_context.CopySubresourceRegion(
+                &mip_texture,
+                0,
+                0,
+                0,
+                0,
+                &texture,
+                0,
+                Some(&source_box),
+            )
+        };
 
         unsafe { self.output_duplication.ReleaseFrame()? };
 
+        unsafe { self.device_context.GenerateMips(&view) };
+
+        let mut staged_desc = desc;
+        staged_desc.Width = (desc.Width >> mip_level).max(1);
+        staged_desc.Height = (desc.Height >> mip_level).max(1);
+        staged_desc.MipLevels = 1;
+        staged_desc.ArraySize = 1;
+        staged_desc.Usage = D3D11_USAGE_STAGING;
+        staged_desc.BindFlags = 0;
+        staged_desc.CPUAccessFlags = self.staging_cpu_access_flags();
+        staged_desc.MiscFlags = 0;
+
+        let staged_texture = self.get_staging_texture(StagingSlot::Scaled, &staged_desc)?;
+
+        unsafe {
+            self.device_context.CopySubresourceRegion(
+                &staged_texture,
+                0,
+                0,
+                0,
+                0,
+                &mip_texture,
+                mip_level,
+                None,
+            )
+        };
+
         let surface: IDXGISurface1 = staged_texture.cast()?;
-        Ok((surface, metadata))
+        Ok((
+            surface,
+            (staged_desc.Width as usize, staged_desc.Height as usize),
+        ))
     }
 
+    /// Builds the metadata of an acquired frame, querying only the parts
+    /// selected by `flags`, with the output's scratch buffers.
     fn extract_frame_metadata(
+        &mut self,
+        frame_info: &DXGI_OUTDUPL_FRAME_INFO,
+        flags: MetadataFlags,
+    ) -> WindowsResult<FrameMetadata> {
+        let mut dirty_buffer = mem::take(&mut self.dirty_rects_buffer);
+        let mut move_buffer = mem::take(&mut self.move_rects_buffer);
+        let metadata =
+            self.read_frame_metadata(frame_info, flags, &mut dirty_buffer, &mut move_buffer);
+        self.dirty_rects_buffer = dirty_buffer;
+        self.move_rects_buffer = move_buffer;
+        metadata
+    }
+
+    /// Builds the metadata of an acquired frame like
+    /// [`DuplicatedOutput::extract_frame_metadata`], reading the raw
+    /// rectangles into the given buffers.
+    fn read_frame_metadata(
         &self,
         frame_info: &DXGI_OUTDUPL_FRAME_INFO,
+        flags: MetadataFlags,
+        dirty_buffer: &mut Vec<RECT>,
+        move_buffer: &mut Vec<DXGI_OUTDUPL_MOVE_RECT>,
     ) -> WindowsResult<FrameMetadata> {
         let mut dirty_rects = Vec::new();
         let mut move_rects = Vec::new();
 
-        if frame_info.TotalMetadataBufferSize > 0 {
-            // Get dirty rectangles
-            let mut dirty_rects_buffer_size = 0u32;
-            let dirty_result = unsafe {
-                self.output_duplication.GetFrameDirtyRects(
-                    0,
-                    std::ptr::null_mut(),
-                    &mut dirty_rects_buffer_size,
-                )
-            };
+        let has_rects = frame_info.TotalMetadataBufferSize > 0;
+        let mut rects_truncated = false;
 
-            if dirty_result.is_ok() && dirty_rects_buffer_size > 0 {
-                let dirty_rect_count = dirty_rects_buffer_size / mem::size_of::<RECT>() as u32;
-                let mut dirty_rects_buffer: Vec<RECT> =
-                    vec![RECT::default(); dirty_rect_count as usize];
-                unsafe {
-                    let get_result = self.output_duplication.GetFrameDirtyRects(
-                        dirty_rects_buffer_size,
-                        dirty_rects_buffer.as_mut_ptr(),
-                        &mut dirty_rects_buffer_size,
-                    );
-                    if get_result.is_ok() {
-                        dirty_rects = dirty_rects_buffer
-                            .into_iter()
-                            .map(|rect| (rect.left, rect.top, rect.right, rect.bottom))
-                            .collect();
-                    }
+        if has_rects && flags.contains(MetadataFlags::DIRTY_RECTS) {
+            match fetch_frame_metadata(
+                dirty_buffer,
+                self.max_rects,
+                |size, buffer, required| unsafe {
+                    self.output_duplication
+                        .GetFrameDirtyRects(size, buffer, required)
+                },
+            ) {
+                Some(rects) => {
+                    dirty_rects = rects
+                        .iter()
+                        .map(|rect| Rect::new(rect.left, rect.top, rect.right, rect.bottom))
+                        .collect();
                 }
+                None => rects_truncated = true,
             }
+        }
 
-            // Get move rectangles
-            let mut move_rects_buffer_size = 0u32;
-            let move_result = unsafe {
-                self.output_duplication.GetFrameMoveRects(
-                    0,
-                    std::ptr::null_mut(),
-                    &mut move_rects_buffer_size,
-                )
-            };
-
-            if move_result.is_ok() && move_rects_buffer_size > 0 {
-                let move_rect_count =
-                    move_rects_buffer_size / mem::size_of::<DXGI_OUTDUPL_MOVE_RECT>() as u32;
-                let mut move_rects_buffer: Vec<DXGI_OUTDUPL_MOVE_RECT> =
-                    vec![unsafe { mem::zeroed() }; move_rect_count as usize];
-                unsafe {
-                    let get_result = self.output_duplication.GetFrameMoveRects(
-                        move_rects_buffer_size,
-                        move_rects_buffer.as_mut_ptr(),
-                        &mut move_rects_buffer_size,
-                    );
-                    if get_result.is_ok() {
-                        move_rects = move_rects_buffer
-                            .into_iter()
-                            .map(|move_rect| MoveRect {
-                                source_point: (move_rect.SourcePoint.x, move_rect.SourcePoint.y),
-                                destination_rect: (
-                                    move_rect.DestinationRect.left,
-                                    move_rect.DestinationRect.top,
-                                    move_rect.DestinationRect.right,
-                                    move_rect.DestinationRect.bottom,
-                                ),
-                            })
-                            .collect();
-                    }
+        if has_rects && !rects_truncated && flags.contains(MetadataFlags::MOVE_RECTS) {
+            match fetch_frame_metadata(
+                move_buffer,
+                self.max_rects,
+                |size, buffer, required| unsafe {
+                    self.output_duplication
+                        .GetFrameMoveRects(size, buffer, required)
+                },
+            ) {
+                Some(rects) => {
+                    move_rects = rects
+                        .iter()
+                        .map(|move_rect| MoveRect {
+                            source_point: (move_rect.SourcePoint.x, move_rect.SourcePoint.y),
+                            destination_rect: Rect::new(
+                                move_rect.DestinationRect.left,
+                                move_rect.DestinationRect.top,
+                                move_rect.DestinationRect.right,
+                                move_rect.DestinationRect.bottom,
+                            ),
+                        })
+                        .collect();
                 }
+                None => rects_truncated = true,
             }
         }
 
-        let pointer_position = if frame_info.PointerPosition.Visible.as_bool() {
-            Some((
-                frame_info.PointerPosition.Position.x,
-                frame_info.PointerPosition.Position.y,
-            ))
-        } else {
-            None
-        };
+        if rects_truncated {
+            // Too many rectangles to read: report the whole image as dirty
+            let mode = self.get_duplication_desc().ModeDesc;
+            dirty_rects = vec![Rect::new(0, 0, mode.Width as i32, mode.Height as i32)];
+            move_rects = Vec::new();
+        }
+
+        let pointer_visible = flags.contains(MetadataFlags::POINTER) && self.pointer_visible;
+        let pointer_position = pointer_visible.then_some(self.pointer_position);
 
         Ok(FrameMetadata {
             last_present_time: frame_info.LastPresentTime,
             last_mouse_update_time: frame_info.LastMouseUpdateTime,
             accumulated_frames: frame_info.AccumulatedFrames,
             rects_coalesced: frame_info.RectsCoalesced.as_bool(),
+            rects_truncated,
+            metadata_available: has_rects,
             protected_content_masked_out: frame_info.ProtectedContentMaskedOut.as_bool(),
             pointer_position,
-            pointer_visible: frame_info.PointerPosition.Visible.as_bool(),
+            pointer_visible,
             dirty_rects,
             move_rects,
         })
     }
 }
 
-// ---------------------------------------------------------------------------
-// DXGIManager — public API
-// ---------------------------------------------------------------------------
+/// The identity and placement of an output, compared across enumerations to
+/// detect display topology changes.
+#[derive(Clone, Copy, PartialEq)]
+struct OutputSnapshot {
+    device_name: [u16; 32],
+    desktop_rect: RECT,
+    rotation: DXGI_MODE_ROTATION,
+}
 
-/// The main manager for handling DXGI desktop duplication.
-///
-/// `DXGIManager` provides a high-level interface to the Windows DXGI Desktop
-/// Duplication API, enabling efficient screen capture operations. It manages
-/// the underlying DXGI resources and provides methods to capture screen content
-/// as pixel data.
-///
+impl From<&DXGI_OUTPUT_DESC> for OutputSnapshot {
+    fn from(desc: &DXGI_OUTPUT_DESC) -> Self {
+        Self {
+            device_name: desc.DeviceName,
+            desktop_rect: desc.DesktopCoordinates,
+            rotation: desc.Rotation,
+        }
+    }
+}
+
+/// One output taking part in a virtual desktop capture, along with the last
+/// image captured from it.
+struct VirtualOutput {
+    duplicated_output: DuplicatedOutput,
+    desktop_rect: RECT,
+    pixels: Vec<BGRA8>,
+    dimensions: (usize, usize),
+}
+
+impl VirtualOutput {
+    /// Captures a new image from this output if one is available within
+    /// `timeout_ms`. Returns `Ok(false)` on timeout, keeping the last image.
+    fn update(&mut self, timeout_ms: u32) -> Result<bool, CaptureError> {
+        let (surface, _) = match self
+            .duplicated_output
+            .capture_frame_to_surface(timeout_ms, None)
+        {
+            Ok(result) => result,
+            Err(e) => {
+                return match map_capture_error(e) {
+                    CaptureError::Timeout => Ok(false),
+                    err => Err(err),
+                };
+            }
+        };
+
+        let surface_desc = unsafe { surface.GetDesc()? };
+        let rotation = self.duplicated_output.rotation()?;
+        let (pixels, dimensions) = DXGIManager::copy_mapped_surface(
+            &surface,
+            surface_desc.Width as usize,
+            surface_desc.Height as usize,
+            rotation,
+            false,
+            PixelOrder::Bgra,
+        )?;
+        self.pixels = pixels;
+        self.dimensions = dimensions;
+        Ok(true)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DXGIManagerBuilder
+// ---------------------------------------------------------------------------
+
+/// Builder for configuring a [`DXGIManager`] before it acquires its output.
+///
+/// Obtained from [`DXGIManager::builder`]. Options that can only be chosen up
+/// front, such as CPU write access to staging textures, are only available here.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use dxgi_capture_rs::DXGIManagerBuilder;
+///
+/// let mut manager = DXGIManagerBuilder::new()
+///     .timeout_ms(100)
+///     .auto_recover(true)
+///     .build()?;
+/// let (pixels, (width, height)) = manager.capture_frame()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct DXGIManagerBuilder {
+    timeout_ms: u32,
+    capture_source_index: usize,
+    auto_recover: bool,
+    cpu_write_access: bool,
+    composite_cursor: bool,
+    acquire_retries: u32,
+    acquire_retry_delay: Duration,
+    duplication_formats: Vec<DXGI_FORMAT>,
+    output_order: PixelOrder,
+    logger: Option<Logger>,
+    max_frame_pixels: usize,
+    capture_timing: bool,
+    force_opaque_alpha: bool,
+    premultiply_alpha: bool,
+    skip_mirrored_outputs: bool,
+    lazy_release: bool,
+    flip_vertical: bool,
+    min_change_fraction: f32,
+    max_rects: usize,
+    external_device: Option<ID3D11Device>,
+}
+
+impl Default for DXGIManagerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DXGIManagerBuilder {
+    /// Creates a builder with a 1000 ms timeout, the primary display as the
+    /// capture source, and all options disabled.
+    pub fn new() -> Self {
+        Self {
+            timeout_ms: 1000,
+            capture_source_index: 0,
+            auto_recover: false,
+            cpu_write_access: false,
+            composite_cursor: false,
+            acquire_retries: 0,
+            acquire_retry_delay: Duration::from_millis(100),
+            duplication_formats: Vec::new(),
+            output_order: PixelOrder::Bgra,
+            logger: None,
+            max_frame_pixels: DEFAULT_MAX_FRAME_PIXELS,
+            capture_timing: true,
+            force_opaque_alpha: false,
+            premultiply_alpha: false,
+            skip_mirrored_outputs: false,
+            lazy_release: false,
+            flip_vertical: false,
+            min_change_fraction: 0.0,
+            max_rects: usize::MAX,
+            external_device: None,
+        }
+    }
+
+    /// Sets the frame capture timeout. See [`DXGIManager::set_timeout_ms`].
+    pub fn timeout_ms(mut self, timeout_ms: u32) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Sets the frame capture timeout as a [`Duration`]. See [`DXGIManager::set_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout_ms = duration_to_timeout_ms(timeout);
+        self
+    }
+
+    /// Sets the capture source index. See [`DXGIManager::set_capture_source_index`].
+    pub fn capture_source_index(mut self, index: usize) -> Self {
+        self.capture_source_index = index;
+        self
+    }
+
+    /// Enables automatic recovery. See [`DXGIManager::set_auto_recover`].
+    pub fn auto_recover(mut self, enabled: bool) -> Self {
+        self.auto_recover = enabled;
+        self
+    }
+
+    /// Creates staging textures with CPU write access in addition to read
+    /// access, which is required by [`DXGIManager::capture_frame_with_mut`].
+    ///
+    /// Write access may make mapping slightly slower on some drivers, so it is
+    /// disabled by default.
+    pub fn cpu_write_access(mut self, enabled: bool) -> Self {
+        self.cpu_write_access = enabled;
+        self
+    }
+
+    /// Draws the mouse pointer into captured frames. See
+    /// [`DXGIManager::set_composite_cursor`].
+    pub fn composite_cursor(mut self, enabled: bool) -> Self {
+        self.composite_cursor = enabled;
+        self
+    }
+
+    /// Sets the byte order of the component capture methods. See
+    /// [`DXGIManager::set_output_order`].
+    pub fn output_order(mut self, order: PixelOrder) -> Self {
+        self.output_order = order;
+        self
+    }
+
+    /// Sets a callback receiving diagnostics, including those emitted while
+    /// [`DXGIManagerBuilder::build`] duplicates the output. See
+    /// [`DXGIManager::set_logger`].
+    pub fn logger(mut self, logger: impl Fn(LogLevel, &str) + Send + Sync + 'static) -> Self {
+        self.logger = Some(Logger(Arc::new(logger)));
+        self
+    }
+
+    /// Sets how many times acquiring the output duplication is retried before
+    /// giving up with [`OutputDuplicationError::NoOutput`].
+    ///
+    /// Duplicating an output can fail transiently, for example right after a
+    /// display mode change or while another application briefly holds the
+    /// duplication. The retries apply to [`DXGIManagerBuilder::build`] and to
+    /// every later call to [`DXGIManager::acquire_output_duplication`],
+    /// including automatic re-acquisition. Defaults to 0, which fails on the
+    /// first attempt.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManagerBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let manager = DXGIManagerBuilder::new()
+    ///     .acquire_retries(5)
+    ///     .acquire_retry_delay(Duration::from_millis(200))
+    ///     .build()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn acquire_retries(mut self, retries: u32) -> Self {
+        self.acquire_retries = retries;
+        self
+    }
+
+    /// Sets how long to wait between output duplication retries. Defaults to
+    /// 100 ms. See [`DXGIManagerBuilder::acquire_retries`].
+    pub fn acquire_retry_delay(mut self, delay: Duration) -> Self {
+        self.acquire_retry_delay = delay;
+        self
+    }
+
+    /// Sets the largest frame, in pixels, that captures accept. See
+    /// [`DXGIManager::set_max_frame_pixels`].
+    pub fn max_frame_pixels(mut self, pixels: usize) -> Self {
+        self.max_frame_pixels = pixels;
+        self
+    }
+
+    /// Enables the timing of [`DXGIManager::capture_frame_timed`]. See
+    /// [`DXGIManager::set_capture_timing`].
+    pub fn capture_timing(mut self, enabled: bool) -> Self {
+        self.capture_timing = enabled;
+        self
+    }
+
+    /// Sets the alpha channel of captured pixels to 255. See
+    /// [`DXGIManager::set_force_opaque_alpha`].
+    pub fn force_opaque_alpha(mut self, enabled: bool) -> Self {
+        self.force_opaque_alpha = enabled;
+        if enabled {
+            self.premultiply_alpha = false;
+        }
+        self
+    }
+
+    /// Multiplies the color channels of captured pixels by their alpha. See
+    /// [`DXGIManager::set_premultiply_alpha`].
+    pub fn premultiply_alpha(mut self, enabled: bool) -> Self {
+        self.premultiply_alpha = enabled;
+        if enabled {
+            self.force_opaque_alpha = false;
+        }
+        self
+    }
+
+    /// Leaves mirrored outputs out of [`DXGIManager::split`]. See
+    /// [`DXGIManager::set_skip_mirrored_outputs`].
+    pub fn skip_mirrored_outputs(mut self, enabled: bool) -> Self {
+        self.skip_mirrored_outputs = enabled;
+        self
+    }
+
+    /// Keeps each frame acquired until the next capture. See
+    /// [`DXGIManager::set_lazy_release`].
+    pub fn lazy_release(mut self, enabled: bool) -> Self {
+        self.lazy_release = enabled;
+        self
+    }
+
+    /// Returns frames with their rows bottom to top. See
+    /// [`DXGIManager::set_flip_vertical`].
+    pub fn flip_vertical(mut self, enabled: bool) -> Self {
+        self.flip_vertical = enabled;
+        self
+    }
+
+    /// Only returns frames in which more than `fraction` of the screen
+    /// changed. See [`DXGIManager::set_min_change_fraction`].
+    pub fn min_change_fraction(mut self, fraction: f32) -> Self {
+        self.min_change_fraction = clamp_change_fraction(fraction);
+        self
+    }
+
+    /// Limits the number of dirty and move rectangles read per frame. See
+    /// [`DXGIManager::set_max_rects`].
+    pub fn max_rects(mut self, max_rects: usize) -> Self {
+        self.max_rects = max_rects;
+        self
+    }
+
+    /// Duplicates the capture source with an existing D3D11 device instead of
+    /// creating one, so captured textures live on the caller's device.
+    ///
+    /// The capture source index still counts the outputs of all adapters, but
+    /// only outputs attached to the device's adapter can be captured; others
+    /// fail with [`OutputDuplicationError::NoOutput`]. The device
+    /// must have been created with `D3D11_CREATE_DEVICE_BGRA_SUPPORT`;
+    /// otherwise [`DXGIManagerBuilder::build`] fails with
+    /// [`OutputDuplicationError::DeviceError`].
+    ///
+    /// Captures use the device's immediate context. A device context is not
+    /// thread-safe, so do not capture while another thread renders with the
+    /// same device, unless multithread protection is enabled on it with
+    /// `ID3D11Multithread::SetMultithreadProtected`. The device is kept
+    /// across re-acquisitions; once it is removed, re-acquiring fails until a
+    /// new manager is built with a new device. Virtual desktop and
+    /// multi-output captures still create their own devices.
+    ///
+    /// Requires the `raw-dxgi` feature. See [`DXGIManager::from_device`].
+    #[cfg(feature = "raw-dxgi")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "raw-dxgi")))]
+    pub fn device(mut self, device: ID3D11Device) -> Self {
+        self.external_device = Some(device);
+        self
+    }
+
+    /// Requests the desktop image in one of `formats`, in order of preference,
+    /// using `IDXGIOutput5::DuplicateOutput1` (DXGI 1.5, Windows 10 1703+).
+    ///
+    /// This can give more efficiently mappable textures on some virtual and
+    /// WARP adapters, and lets HDR formats such as
+    /// `DXGI_FORMAT_R10G10B10A2_UNORM` or `DXGI_FORMAT_R16G16B16A16_FLOAT` be
+    /// requested explicitly. Include `DXGI_FORMAT_B8G8R8A8_UNORM` to keep the
+    /// default format available. When `DuplicateOutput1` is unavailable or
+    /// fails, which also happens if the process is not per-monitor DPI aware,
+    /// the output is duplicated with plain `DuplicateOutput` instead. An empty
+    /// list, the default, always uses `DuplicateOutput`.
+    ///
+    /// Capture methods returning 4-byte pixels fail with
+    /// [`CaptureError::Fail`] carrying `DXGI_ERROR_UNSUPPORTED` when the
+    /// desktop is in a wider format. Read such frames with
+    /// [`DXGIManager::capture_frame_u16`], or with
+    /// [`DXGIManager::capture_frame_raw`], which reports the actual format.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManagerBuilder;
+    /// use windows::Win32::Graphics::Dxgi::Common::{
+    ///     DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_R10G10B10A2_UNORM,
+    /// };
+    ///
+    /// let mut manager = DXGIManagerBuilder::new()
+    ///     .duplication_formats(&[DXGI_FORMAT_R10G10B10A2_UNORM, DXGI_FORMAT_B8G8R8A8_UNORM])
+    ///     .build()?;
+    /// let frame = manager.capture_frame_raw()?;
+    /// println!("Desktop format: {:?}", frame.format);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn duplication_formats(mut self, formats: &[DXGI_FORMAT]) -> Self {
+        self.duplication_formats = formats.to_vec();
+        self
+    }
+
+    /// Creates the manager and acquires the output duplication for the
+    /// configured capture source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the DXGI factory cannot be created or the capture
+    /// source cannot be duplicated.
+    pub fn build(self) -> Result<DXGIManager, OutputDuplicationError> {
+        let factory = create_dxgi_factory_1()?;
+        let mut manager = DXGIManager {
+            factory,
+            duplicated_output: None,
+            capture_source_index: self.capture_source_index,
+            timeout_ms: self.timeout_ms,
+            auto_recover: self.auto_recover,
+            cpu_write_access: self.cpu_write_access,
+            composite_cursor: self.composite_cursor,
+            acquire_retries: self.acquire_retries,
+            acquire_retry_delay: self.acquire_retry_delay,
+            duplication_formats: self.duplication_formats,
+            output_order: self.output_order,
+            logger: self.logger,
+            max_frame_pixels: self.max_frame_pixels,
+            capture_timing: self.capture_timing,
+            force_opaque_alpha: self.force_opaque_alpha,
+            premultiply_alpha: self.premultiply_alpha,
+            skip_mirrored_outputs: self.skip_mirrored_outputs,
+            lazy_release: self.lazy_release,
+            flip_vertical: self.flip_vertical,
+            min_change_fraction: self.min_change_fraction,
+            max_rects: self.max_rects,
+            external_device: self.external_device,
+            adapter_device: None,
+            output_topology: Vec::new(),
+            virtual_outputs: Vec::new(),
+            source_outputs: Vec::new(),
+            clear_color: BGRA8 {
+                b: 0,
+                g: 0,
+                r: 0,
+                a: 0,
+            },
+            access_lost_streak: 0,
+        };
+        manager.acquire_output_duplication()?;
+        Ok(manager)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DXGIManager — public API
+// ---------------------------------------------------------------------------
+
+/// The main manager for handling DXGI desktop duplication.
+///
+/// `DXGIManager` provides a high-level interface to the Windows DXGI Desktop
+/// Duplication API, enabling efficient screen capture operations. It manages
+/// the underlying DXGI resources and provides methods to capture screen content
+/// as pixel data.
+///
 /// # Usage
 ///
 /// The typical workflow involves:
@@ -653,12 +3618,13 @@ impl DuplicatedOutput {
 /// let mut manager = DXGIManager::new(1000)?;
 ///
 /// // Capture from primary display (default)
-/// manager.set_capture_source_index(0);
+/// manager.set_capture_source_index(0)?;
 /// let primary_frame = manager.capture_frame();
 ///
 /// // Capture from secondary display (if available)
-/// manager.set_capture_source_index(1);
-/// let secondary_frame = manager.capture_frame();
+/// if manager.set_capture_source_index(1).is_ok() {
+///     let secondary_frame = manager.capture_frame();
+/// }
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 ///
@@ -691,35 +3657,149 @@ pub struct DXGIManager {
     duplicated_output: Option<DuplicatedOutput>,
     capture_source_index: usize,
     timeout_ms: u32,
+    auto_recover: bool,
+    cpu_write_access: bool,
+    composite_cursor: bool,
+    acquire_retries: u32,
+    acquire_retry_delay: Duration,
+    duplication_formats: Vec<DXGI_FORMAT>,
+    output_order: PixelOrder,
+    logger: Option<Logger>,
+    max_frame_pixels: usize,
+    capture_timing: bool,
+    force_opaque_alpha: bool,
+    premultiply_alpha: bool,
+    skip_mirrored_outputs: bool,
+    lazy_release: bool,
+    flip_vertical: bool,
+    min_change_fraction: f32,
+    max_rects: usize,
+    /// Device supplied with [`DXGIManagerBuilder::device`], used for the
+    /// single-source duplication instead of one created per adapter.
+    external_device: Option<ID3D11Device>,
+    /// Device of the adapter last duplicated from, identified by its LUID, so
+    /// switching between outputs of the same adapter reuses it.
+    adapter_device: Option<(LUID, ID3D11Device, ID3D11DeviceContext)>,
+    /// Outputs of each adapter as of the last acquisition, for detecting
+    /// topology changes in [`DXGIManager::refresh_outputs`].
+    output_topology: Vec<Vec<OutputSnapshot>>,
+    virtual_outputs: Vec<VirtualOutput>,
+    /// Duplications of every output with their indices, kept between calls
+    /// to [`DXGIManager::capture_all_sources`].
+    source_outputs: Vec<(usize, DuplicatedOutput)>,
+    clear_color: BGRA8,
+    /// Number of captures in a row that lost access or failed to re-acquire
+    /// the duplication, see [`DXGIManager::is_output_in_fullscreen_exclusive`].
+    access_lost_streak: u32,
 }
 
+/// Consecutive lost captures after which the output is assumed to be taken
+/// over by an exclusive fullscreen application.
+const FULLSCREEN_EXCLUSIVE_STREAK: u32 = 3;
+
 impl DXGIManager {
     /// Creates a new `DXGIManager` instance.
     ///
     /// This initializes the DXGI factory and sets up the necessary resources
     /// for screen capture. The `timeout_ms` parameter specifies the default
-    /// timeout for frame capture operations.
+    /// timeout for frame capture operations. Use [`DXGIManager::builder`] for
+    /// further configuration.
     ///
     /// # Errors
     ///
     /// Returns an error if the DXGI manager cannot be initialized, which
     /// typically occurs if the required graphics components are not available.
     pub fn new(timeout_ms: u32) -> Result<Self, OutputDuplicationError> {
-        let factory = create_dxgi_factory_1()?;
-        let mut manager = Self {
-            factory,
-            duplicated_output: None,
-            capture_source_index: 0,
-            timeout_ms,
-        };
-        manager.acquire_output_duplication()?;
-        Ok(manager)
+        Self::builder().timeout_ms(timeout_ms).build()
+    }
+
+    /// Returns a [`DXGIManagerBuilder`] for configuring a new manager.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let manager = DXGIManager::builder()
+    ///     .timeout_ms(500)
+    ///     .capture_source_index(1)
+    ///     .build()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn builder() -> DXGIManagerBuilder {
+        DXGIManagerBuilder::new()
+    }
+
+    /// Creates a manager that duplicates output `output_index`, counted
+    /// across all adapters as in [`DXGIManager::outputs`], using `device`
+    /// rather than a new one. The output must be attached to the adapter
+    /// `device` was created on.
+    ///
+    /// This shares resources with an existing renderer: textures such as the
+    /// one returned by [`DXGIManager::capture_frame_shared_handle`] are
+    /// created on `device`. See [`DXGIManagerBuilder::device`] for the
+    /// requirements on the device and threading; use the builder to combine
+    /// it with other options.
+    ///
+    /// Requires the `raw-dxgi` feature, like [`DXGIManagerBuilder::device`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutputDuplicationError::DeviceError`] if the device lacks
+    /// BGRA support or the output cannot be duplicated with it, and
+    /// [`OutputDuplicationError::NoOutput`] if there is no output
+    /// `output_index` or it is attached to another adapter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use windows::Win32::Foundation::HMODULE;
+    /// use windows::Win32::Graphics::Direct3D::D3D_DRIVER_TYPE_HARDWARE;
+    /// use windows::Win32::Graphics::Direct3D11::{
+    ///     D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_SDK_VERSION, D3D11CreateDevice,
+    /// };
+    ///
+    /// // The renderer's device
+    /// let mut device = None;
+    /// unsafe {
+    ///     D3D11CreateDevice(
+    ///         None,
+    ///         D3D_DRIVER_TYPE_HARDWARE,
+    ///         HMODULE::default(),
+    ///         D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+    ///         None,
+    ///         D3D11_SDK_VERSION,
+    ///         Some(&mut device),
+    ///         None,
+    ///         None,
+    ///     )?
+    /// };
+    ///
+    /// let mut manager = DXGIManager::from_device(device.unwrap(), 0)?;
+    /// let (pixels, (width, height)) = manager.capture_frame()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "raw-dxgi")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "raw-dxgi")))]
+    pub fn from_device(
+        device: ID3D11Device,
+        output_index: usize,
+    ) -> Result<Self, OutputDuplicationError> {
+        Self::builder()
+            .capture_source_index(output_index)
+            .device(device)
+            .build()
     }
 
     /// Returns the screen geometry (width, height) of the current capture source.
     ///
-    /// Returns the width and height of the display being captured, in pixels.
-    /// This corresponds to the resolution of the selected capture source.
+    /// Returns the width and height of the display being captured, in pixels,
+    /// as laid out on the desktop. Desktop coordinates already include the
+    /// display rotation, so on a portrait monitor the height is larger than the
+    /// width, matching the dimensions returned by [`DXGIManager::capture_frame`].
+    /// This is the same as [`DXGIManager::frame_geometry`]; see
+    /// [`DXGIManager::output_geometry`] for the unrotated size.
     ///
     /// # Returns
     ///
@@ -752,18 +3832,23 @@ impl DXGIManager {
         }
     }
 
-    /// Sets the capture source index to select which display to capture from.
-    ///
-    /// In multi-monitor setups, this method allows you to choose which display
-    /// to capture from. Index 0 always refers to the primary display, while
-    /// indices 1 and higher refer to secondary displays.
+    /// Returns the dimensions `(width, height)` of frames returned by the
+    /// rotation-corrected capture methods such as [`DXGIManager::capture_frame`].
     ///
-    /// # Arguments
+    /// This is an explicitly named alias of [`DXGIManager::geometry`]. Use it to
+    /// size buffers for rotation-corrected captures.
+    pub fn frame_geometry(&self) -> (usize, usize) {
+        self.geometry()
+    }
+
+    /// Returns the dimensions `(width, height)` of the current capture source
+    /// in the native orientation of the display, before rotation is applied.
     ///
-    /// * `cs` - The capture source index:
-    ///   - `0` = Primary display (default)
-    ///   - `1` = First secondary display
-    ///   - `2` = Second secondary display, etc.
+    /// This is the size of the desktop image as the display scans it out, and
+    /// of the frames returned by methods without rotation correction, such as
+    /// [`DXGIManager::capture_frame_fast`] and [`DXGIManager::capture_frame_with`].
+    /// On displays rotated by 90 or 270 degrees the width and height of
+    /// [`DXGIManager::frame_geometry`] are swapped; otherwise both are equal.
     ///
     /// # Examples
     ///
@@ -771,43 +3856,29 @@ impl DXGIManager {
     /// use dxgi_capture_rs::DXGIManager;
     ///
     /// let mut manager = DXGIManager::new(1000)?;
-    ///
-    /// // Capture from primary display (default)
-    /// manager.set_capture_source_index(0);
-    /// let primary_frame = manager.capture_frame();
-    ///
-    /// // Switch to secondary display
-    /// manager.set_capture_source_index(1);
-    /// let secondary_frame = manager.capture_frame();
+    /// let (width, height) = manager.output_geometry();
+    /// let mut buffer = vec![0u8; width * height * 4];
+    /// let (pixels, _) = manager.capture_frame_fast()?;
+    /// buffer.copy_from_slice(&pixels);
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    ///
-    /// # Notes
-    ///
-    /// - Setting an invalid index (e.g., for a non-existent display) will not
-    ///   cause an immediate error, but subsequent capture operations may fail
-    /// - This method automatically reinitializes the capture system for the new display
-    /// - The geometry may change when switching between displays of different resolutions
-    pub fn set_capture_source_index(&mut self, cs: usize) {
-        let previous_index = self.capture_source_index;
-        self.capture_source_index = cs;
-
-        if self.acquire_output_duplication().is_err() && cs == 0 && cs != previous_index {
-            self.capture_source_index = previous_index;
-            let _ = self.acquire_output_duplication();
-        }
+    pub fn output_geometry(&self) -> (usize, usize) {
+        self.duplicated_output
+            .as_ref()
+            .and_then(|output| output.desktop_span().ok())
+            .map_or((0, 0), |(width, height)| (width as usize, height as usize))
     }
 
-    /// Gets the current capture source index.
-    ///
-    /// Returns the index of the display currently being used for capture operations.
-    ///
-    /// # Returns
+    /// Returns the dimensions `(width, height)` of the desktop texture of the
+    /// last acquired frame, or `None` if no frame has been acquired yet.
     ///
-    /// The current capture source index:
-    /// - `0` = Primary display
-    /// - `1` = First secondary display  
-    /// - `2` = Second secondary display, etc.
+    /// On some drivers, notably on scaled displays or at widths that are not
+    /// a multiple of 16, the texture DXGI hands out is padded beyond the
+    /// desktop. Captured frames never include that padding: they always have
+    /// the size of [`DXGIManager::output_geometry`] (or
+    /// [`DXGIManager::frame_geometry`] after rotation), and rows are read
+    /// using the pitch of the mapped texture. Comparing both sizes tells
+    /// whether a display is affected.
     ///
     /// # Examples
     ///
@@ -815,31 +3886,32 @@ impl DXGIManager {
     /// use dxgi_capture_rs::DXGIManager;
     ///
     /// let mut manager = DXGIManager::new(1000)?;
-    ///
-    /// // Initially set to primary display
-    /// assert_eq!(manager.get_capture_source_index(), 0);
-    ///
-    /// // Switch to secondary display
-    /// manager.set_capture_source_index(1);
-    /// assert_eq!(manager.get_capture_source_index(), 1);
+    /// manager.capture_frame()?;
+    /// if let Some(texture) = manager.texture_dimensions() {
+    ///     if texture != manager.output_geometry() {
+    ///         println!("Desktop texture is padded to {}x{}", texture.0, texture.1);
+    ///     }
+    /// }
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn get_capture_source_index(&self) -> usize {
-        self.capture_source_index
+    pub fn texture_dimensions(&self) -> Option<(usize, usize)> {
+        self.duplicated_output
+            .as_ref()?
+            .texture_size
+            .map(|(width, height)| (width as usize, height as usize))
     }
 
-    /// Sets the timeout for capture operations.
+    /// Converts a point in the coordinates of the current capture source into
+    /// virtual desktop coordinates.
     ///
-    /// This timeout determines how long capture operations will wait for a new
-    /// frame to become available before returning with a timeout error.
-    ///
-    /// # Arguments
-    ///
-    /// * `timeout_ms` - The timeout in milliseconds:
-    ///   - `0` = No timeout (immediate return if no frame available)
-    ///   - `1-1000` = Short timeout for real-time applications
-    ///   - `1000-5000` = Medium timeout for interactive applications
-    ///   - `>5000` = Long timeout for less frequent captures
+    /// Local coordinates are those of frames returned by
+    /// [`DXGIManager::capture_frame`] and of
+    /// [`FrameMetadata::pointer_position`], with the origin at the top-left
+    /// corner of the output. Global coordinates span all displays, with the
+    /// origin at the top-left corner of the primary display, so outputs left
+    /// of or above it have negative coordinates. The offset is the origin of
+    /// the output's desktop coordinates. Without an output duplication the
+    /// point is returned unchanged.
     ///
     /// # Examples
     ///
@@ -847,446 +3919,5429 @@ impl DXGIManager {
     /// use dxgi_capture_rs::DXGIManager;
     ///
     /// let mut manager = DXGIManager::new(1000)?;
+    /// manager.set_capture_source_index(1)?;
+    /// let (_, _, metadata) = manager.capture_frame_with_metadata()?;
+    /// if let Some((x, y)) = metadata.pointer_position {
+    ///     let (global_x, global_y) = manager.to_global(x, y);
+    ///     println!("Pointer at {global_x}, {global_y} on the virtual desktop");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_global(&self, x: i32, y: i32) -> (i32, i32) {
+        let (left, top) = self.desktop_origin();
+        (x + left, y + top)
+    }
+
+    /// Converts a point in virtual desktop coordinates into the coordinates of
+    /// the current capture source. This is the inverse of
+    /// [`DXGIManager::to_global`].
     ///
-    /// // Set short timeout for real-time capture
-    /// manager.set_timeout_ms(100);
+    /// The result lies outside the frame if the point is not on the current
+    /// output, so compare it against [`DXGIManager::frame_geometry`] when that
+    /// matters.
     ///
-    /// // Set no timeout for immediate return
-    /// manager.set_timeout_ms(0);
+    /// # Examples
     ///
-    /// // Set longer timeout for less frequent captures
-    /// manager.set_timeout_ms(5000);
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let manager = DXGIManager::new(1000)?;
+    /// let (x, y) = manager.to_local(-1920, 200);
+    /// let (width, height) = manager.frame_geometry();
+    /// let on_output = (0..width as i32).contains(&x) && (0..height as i32).contains(&y);
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn set_timeout_ms(&mut self, timeout_ms: u32) {
-        self.timeout_ms = timeout_ms
+    pub fn to_local(&self, x: i32, y: i32) -> (i32, i32) {
+        let (left, top) = self.desktop_origin();
+        (x - left, y - top)
     }
 
-    /// Gets the current timeout value for capture operations.
+    /// Returns the top-left corner of the current output in virtual desktop
+    /// coordinates, or the origin if there is no output duplication.
+    fn desktop_origin(&self) -> (i32, i32) {
+        self.duplicated_output
+            .as_ref()
+            .and_then(|output| output.get_desc().ok())
+            .map_or((0, 0), |desc| {
+                (desc.DesktopCoordinates.left, desc.DesktopCoordinates.top)
+            })
+    }
+
+    /// Returns the display mode of the current capture source.
     ///
-    /// Returns the timeout in milliseconds that capture operations will wait
-    /// for a new frame to become available.
+    /// The mode includes the desktop image dimensions, the refresh rate as a
+    /// rational number, and the display rotation. This avoids having to query
+    /// the display settings through separate Win32 calls.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// The current timeout in milliseconds:
-    /// - `0` = No timeout (immediate return)
-    /// - `>0` = Timeout in milliseconds
+    /// Returns [`CaptureError::RefreshFailure`] if there is no active output
+    /// duplication, e.g. after an invalid capture source index was selected.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// use dxgi_capture_rs::DXGIManager;
     ///
-    /// let mut manager = DXGIManager::new(1000)?;
-    ///
-    /// // Check initial timeout
-    /// assert_eq!(manager.get_timeout_ms(), 1000);
-    ///
-    /// // Change timeout and verify
-    /// manager.set_timeout_ms(500);
-    /// assert_eq!(manager.get_timeout_ms(), 500);
+    /// let manager = DXGIManager::new(1000)?;
+    /// let mode = manager.current_mode()?;
+    /// println!(
+    ///     "{}x{} @ {:.2} Hz, rotation {:?}",
+    ///     mode.width,
+    ///     mode.height,
+    ///     mode.refresh_rate_hz(),
+    ///     mode.rotation
+    /// );
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn get_timeout_ms(&self) -> u32 {
-        self.timeout_ms
+    pub fn current_mode(&self) -> Result<DisplayMode, CaptureError> {
+        let output = self
+            .duplicated_output
+            .as_ref()
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?;
+        let desc = output.get_duplication_desc();
+        let device_name = output.get_desc()?.DeviceName;
+
+        Ok(DisplayMode {
+            width: desc.ModeDesc.Width,
+            height: desc.ModeDesc.Height,
+            refresh_numerator: desc.ModeDesc.RefreshRate.Numerator,
+            refresh_denominator: desc.ModeDesc.RefreshRate.Denominator,
+            rotation: Rotation::from_dxgi(resolve_rotation(desc.Rotation, &device_name)),
+            reported_rotation: Rotation::from_dxgi(desc.Rotation),
+        })
     }
 
-    /// Reinitializes the output duplication for the selected capture source.
-    ///
-    /// This method is automatically called when needed, but can be called manually
-    /// to recover from certain error conditions. It reinitializes the DXGI
-    /// Desktop Duplication system for the currently selected capture source.
-    ///
-    /// # Returns
+    /// Returns the pixel format of the desktop image of the current capture source.
     ///
-    /// Returns `Ok(())` on success, or `Err(OutputDuplicationError)` if the
-    /// reinitialization fails.
+    /// This is `DXGI_FORMAT_B8G8R8A8_UNORM` on nearly all outputs, but some
+    /// drivers use an RGBA format. Methods returning copied pixels, such as
+    /// [`DXGIManager::capture_frame`], always convert to BGRA. Methods that
+    /// expose the mapped surface as is, namely [`DXGIManager::capture_frame_with`],
+    /// [`DXGIManager::capture_frame_with_mut`] and [`DXGIManager::capture_frame_raw`],
+    /// keep this format.
     ///
     /// # Errors
     ///
-    /// - [`OutputDuplicationError::NoOutput`] if no suitable display is found
-    /// - [`OutputDuplicationError::DeviceError`] if device creation fails
+    /// Returns [`CaptureError::RefreshFailure`] if there is no active output
+    /// duplication.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
-    /// use dxgi_capture_rs::{DXGIManager, CaptureError};
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_R8G8B8A8_UNORM;
     ///
     /// let mut manager = DXGIManager::new(1000)?;
+    /// let rgba = manager.current_format()? == DXGI_FORMAT_R8G8B8A8_UNORM;
     ///
-    /// // Manually reinitialize if needed
-    /// match manager.acquire_output_duplication() {
-    ///     Ok(()) => println!("Successfully reinitialized"),
-    ///     Err(e) => println!("Failed to reinitialize: {:?}", e),
-    /// }
+    /// manager.capture_frame_with(|data, _, _, _| {
+    ///     let (red, blue) = if rgba { (0, 2) } else { (2, 0) };
+    ///     println!("First pixel: r={} b={}", data[red], data[blue]);
+    /// })?;
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn acquire_output_duplication(&mut self) -> Result<(), OutputDuplicationError> {
-        // Drop any existing output duplication first, releasing the COM
-        // resources before attempting to acquire new ones.
-        self.duplicated_output = None;
-
-        for i in 0.. {
-            let adapter = match unsafe { self.factory.EnumAdapters1(i) } {
-                Ok(adapter) => adapter,
-                Err(e) if e.code() == DXGI_ERROR_NOT_FOUND => break,
-                Err(e) => return Err(e.into()),
-            };
-
-            let (d3d11_device, device_context) = match d3d11_create_device(Some(&adapter.cast()?)) {
-                Ok(device) => device,
-                Err(_) => continue,
-            };
-
-            // Only look up and duplicate the single output we actually need.
-            let output = match get_output_at_index(&adapter, self.capture_source_index)? {
-                Some(output) => output,
-                None => continue,
-            };
-
-            let output1: IDXGIOutput1 = output.cast()?;
-            let output_duplication = match unsafe { output1.DuplicateOutput(&d3d11_device) } {
-                Ok(dup) => dup,
-                Err(_) => continue,
-            };
-
-            self.duplicated_output = Some(DuplicatedOutput {
-                device: d3d11_device,
-                device_context,
-                output: output1,
-                output_duplication,
-            });
-            return Ok(());
-        }
-        Err(OutputDuplicationError::NoOutput)
-    }
-
-    // -----------------------------------------------------------------------
-    // Internal capture helpers
-    // -----------------------------------------------------------------------
-
-    /// Acquires a frame surface, optionally with metadata.  On recoverable
-    /// DXGI errors the internal `duplicated_output` is reset so the next
-    /// capture attempt will re-acquire.
-    fn acquire_surface(
-        &mut self,
-        with_metadata: bool,
-    ) -> Result<(IDXGISurface1, Option<FrameMetadata>), CaptureError> {
-        if self.duplicated_output.is_none() && self.acquire_output_duplication().is_err() {
-            return Err(CaptureError::RefreshFailure);
-        }
-
-        let timeout_ms = self.timeout_ms;
-        let dup = self.duplicated_output.as_mut().unwrap();
-
-        match dup.capture_frame_to_surface(timeout_ms, with_metadata) {
-            Ok(result) => Ok(result),
-            Err(e) => {
-                let err = map_capture_error(e);
-                // On non-timeout errors, drop the output so it is re-acquired.
-                if !matches!(err, CaptureError::Timeout) {
-                    self.duplicated_output = None;
-                }
-                Err(err)
-            }
-        }
-    }
-
-    /// Reads pixel data from a mapped surface, handling rotation. This is the
-    /// single source of truth for the rotation-aware copy logic. `T` is either
-    /// [`BGRA8`] or `u8`.
-    fn copy_surface_data<T: Copy + Send + Sync + Sized>(
-        &self,
-        surface: &IDXGISurface1,
-    ) -> Result<(Vec<T>, (usize, usize)), CaptureError> {
-        let mut rect = DXGI_MAPPED_RECT::default();
-        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
-
-        let desc = self
+    pub fn current_format(&self) -> Result<DXGI_FORMAT, CaptureError> {
+        let output = self
             .duplicated_output
             .as_ref()
-            .ok_or(CaptureError::RefreshFailure)?
-            .get_desc()?;
-        let width = (desc.DesktopCoordinates.right - desc.DesktopCoordinates.left) as usize;
-        let height = (desc.DesktopCoordinates.bottom - desc.DesktopCoordinates.top) as usize;
-
-        let pitch = rect.Pitch as usize;
-        let source = rect.pBits;
-
-        let (rotated_width, rotated_height) = match desc.Rotation {
-            DXGI_MODE_ROTATION_ROTATE90 | DXGI_MODE_ROTATION_ROTATE270 => (height, width),
-            _ => (width, height),
-        };
-
-        let bytes_per_pixel = mem::size_of::<BGRA8>() / mem::size_of::<T>();
-        let source_slice = unsafe {
-            slice::from_raw_parts(source as *const T, pitch * height / mem::size_of::<T>())
-        };
-
-        let mut data_vec: Vec<T> =
-            Vec::with_capacity(rotated_width * rotated_height * bytes_per_pixel);
-
-        match desc.Rotation {
-            DXGI_MODE_ROTATION_IDENTITY | DXGI_MODE_ROTATION_UNSPECIFIED => {
-                for i in 0..height {
-                    let start = i * pitch / mem::size_of::<T>();
-                    let end = start + width * bytes_per_pixel;
-                    data_vec.extend_from_slice(&source_slice[start..end]);
-                }
-            }
-            DXGI_MODE_ROTATION_ROTATE90 => {
-                for i in 0..width {
-                    for j in (0..height).rev() {
-                        let index = j * pitch / mem::size_of::<T>() + i * bytes_per_pixel;
-                        data_vec.extend_from_slice(&source_slice[index..index + bytes_per_pixel]);
-                    }
-                }
-            }
-            DXGI_MODE_ROTATION_ROTATE180 => {
-                for i in (0..height).rev() {
-                    for j in (0..width).rev() {
-                        let index = i * pitch / mem::size_of::<T>() + j * bytes_per_pixel;
-                        data_vec.extend_from_slice(&source_slice[index..index + bytes_per_pixel]);
-                    }
-                }
-            }
-            DXGI_MODE_ROTATION_ROTATE270 => {
-                for i in (0..width).rev() {
-                    for j in 0..height {
-                        let index = j * pitch / mem::size_of::<T>() + i * bytes_per_pixel;
-                        data_vec.extend_from_slice(&source_slice[index..index + bytes_per_pixel]);
-                    }
-                }
-            }
-            _ => {}
-        }
-
-        unsafe { surface.Unmap()? };
-
-        Ok((data_vec, (rotated_width, rotated_height)))
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?;
+        Ok(output.get_duplication_desc().ModeDesc.Format)
     }
 
-    // -----------------------------------------------------------------------
-    // Public capture methods
-    // -----------------------------------------------------------------------
-
-    /// Captures a single frame and returns it as a `Vec<BGRA8>`.
+    /// Returns the color space of the current capture source.
     ///
-    /// This method captures the current screen content and returns it as a vector
-    /// of [`BGRA8`] pixels along with the frame dimensions. The method waits for
-    /// a new frame to become available, up to the configured timeout.
+    /// This is read from `IDXGIOutput6::GetDesc1`, so it reflects whether HDR
+    /// is enabled for the display. Pair it with [`DXGIManager::current_format`]
+    /// to interpret captured values correctly. Systems without DXGI 1.6
+    /// (before Windows 10 version 1703) cannot report a color space and do not
+    /// support HDR output, so [`ColorSpace::Srgb`] is assumed there.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// On success, returns `Ok((pixels, (width, height)))` where:
-    /// - `pixels` is a `Vec<BGRA8>` containing the pixel data
-    /// - `width` and `height` are the frame dimensions in pixels
-    /// - Pixels are stored in row-major order (left-to-right, top-to-bottom)
+    /// Returns [`CaptureError::RefreshFailure`] if there is no active output
+    /// duplication, or [`CaptureError::Fail`] if the description cannot be read.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
-    /// use dxgi_capture_rs::{DXGIManager, CaptureError};
-    ///
-    /// let mut manager = DXGIManager::new(1000)?;
+    /// use dxgi_capture_rs::DXGIManager;
     ///
-    /// match manager.capture_frame() {
-    ///     Ok((pixels, (width, height))) => {
-    ///         println!("Captured {}x{} frame with {} pixels", width, height, pixels.len());
-    ///     }
-    ///     Err(CaptureError::Timeout) => {
-    ///         // No new frame available within timeout
-    ///     }
-    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// let manager = DXGIManager::new(1000)?;
+    /// let color_space = manager.color_space()?;
+    /// if color_space.is_hdr() {
+    ///     let format = manager.current_format()?;
+    ///     println!("{:?} output in {:?}, tone-map before saving", color_space, format);
     /// }
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn capture_frame(&mut self) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
-        let (surface, _) = self.acquire_surface(false)?;
-        self.copy_surface_data(&surface)
+    pub fn color_space(&self) -> Result<ColorSpace, CaptureError> {
+        let output = self
+            .duplicated_output
+            .as_ref()
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?;
+        match output.output.cast::<IDXGIOutput6>() {
+            Ok(output6) => Ok(ColorSpace::from_dxgi(
+                unsafe { output6.GetDesc1()? }.ColorSpace,
+            )),
+            Err(_) => Ok(ColorSpace::Srgb),
+        }
     }
 
-    /// Captures a single frame and returns it as a `Vec<u8>`.
-    ///
-    /// This method captures the current screen content and returns it as a vector
-    /// of raw bytes representing the pixel components. Each pixel is represented
-    /// by 4 consecutive bytes in BGRA order.
-    ///
-    /// # Returns
+    /// Returns the luminance range of the current capture source if it is in
+    /// HDR mode.
     ///
-    /// On success, returns `Ok((components, (width, height)))` where:
-    /// - `components` is a `Vec<u8>` containing the raw pixel component data
-    /// - `width` and `height` are the frame dimensions in pixels
-    /// - Components are stored as [B, G, R, A, B, G, R, A, ...] in row-major order
+    /// This is read from `IDXGIOutput6::GetDesc1` like
+    /// [`DXGIManager::color_space`], and gives the target range for tone
+    /// mapping captured HDR values. Returns `None` when the display is in SDR
+    /// mode, the system lacks DXGI 1.6, there is no active output duplication
+    /// or the description cannot be read.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// use dxgi_capture_rs::DXGIManager;
     ///
-    /// let mut manager = DXGIManager::new(1000)?;
-    ///
-    /// match manager.capture_frame_components() {
-    ///     Ok((components, (width, height))) => {
-    ///         println!("Captured {}x{} frame with {} bytes", width, height, components.len());
-    ///     }
-    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// let manager = DXGIManager::new(1000)?;
+    /// if let Some(hdr) = manager.hdr_metadata() {
+    ///     println!(
+    ///         "HDR display: {}-{} nits, {} nits full frame",
+    ///         hdr.min_luminance, hdr.max_luminance, hdr.max_full_frame_luminance
+    ///     );
     /// }
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn capture_frame_components(&mut self) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
-        let (surface, _) = self.acquire_surface(false)?;
-        self.copy_surface_data(&surface)
+    pub fn hdr_metadata(&self) -> Option<HdrMetadata> {
+        let output6 = self
+            .duplicated_output
+            .as_ref()?
+            .output
+            .cast::<IDXGIOutput6>()
+            .ok()?;
+        let desc = unsafe { output6.GetDesc1() }.ok()?;
+        if !ColorSpace::from_dxgi(desc.ColorSpace).is_hdr() {
+            return None;
+        }
+        Some(HdrMetadata {
+            min_luminance: desc.MinLuminance,
+            max_luminance: desc.MaxLuminance,
+            max_full_frame_luminance: desc.MaxFullFrameLuminance,
+        })
     }
 
-    /// Captures a single frame with minimal overhead for performance-critical applications.
+    /// Returns the effective DPI `(x, y)` of the current capture source.
     ///
-    /// This method provides the fastest possible screen capture by minimizing memory
-    /// allocations and copying. Returns raw pixel data without rotation handling.
+    /// The effective DPI reflects the user's display scaling setting, where 96
+    /// corresponds to 100% scaling. Dividing it by 96 gives the factor between
+    /// the captured pixel coordinates and the logical coordinates reported by
+    /// DPI-virtualized APIs.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// On success, returns `Ok((pixels, (width, height)))` where:
-    /// - `pixels` is a `Vec<u8>` containing raw BGRA pixel data
-    /// - `width` and `height` are the frame dimensions in pixels
-    /// - Data is in the native orientation (no rotation correction)
+    /// Returns [`CaptureError::RefreshFailure`] if there is no active output
+    /// duplication, or [`CaptureError::Fail`] if the DPI cannot be queried.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// use dxgi_capture_rs::DXGIManager;
     ///
-    /// let mut manager = DXGIManager::new(100)?;
-    ///
-    /// match manager.capture_frame_fast() {
-    ///     Ok((pixels, (width, height))) => {
-    ///         println!("Fast captured {}x{} frame", width, height);
-    ///     }
-    ///     Err(e) => eprintln!("Fast capture failed: {:?}", e),
-    /// }
+    /// let manager = DXGIManager::new(1000)?;
+    /// let (dpi_x, dpi_y) = manager.output_dpi()?;
+    /// println!("Scaling: {:.0}% x {:.0}%", dpi_x as f64 / 0.96, dpi_y as f64 / 0.96);
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn capture_frame_fast(&mut self) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
-        let (surface, _) = self.acquire_surface(false)?;
-
-        let mut rect = DXGI_MAPPED_RECT::default();
-        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
-
+    pub fn output_dpi(&self) -> Result<(u32, u32), CaptureError> {
         let desc = self
             .duplicated_output
             .as_ref()
-            .ok_or(CaptureError::RefreshFailure)?
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?
             .get_desc()?;
-        let width = (desc.DesktopCoordinates.right - desc.DesktopCoordinates.left) as usize;
-        let height = (desc.DesktopCoordinates.bottom - desc.DesktopCoordinates.top) as usize;
 
-        let pitch = rect.Pitch as usize;
-        let source = rect.pBits;
-
-        let bytes_per_row = width * 4;
-        let mut data_vec = Vec::with_capacity(width * height * 4);
-
-        unsafe {
-            if pitch == bytes_per_row {
-                let total_bytes = width * height * 4;
-                let source_slice = slice::from_raw_parts(source as *const u8, total_bytes);
-                data_vec.extend_from_slice(source_slice);
-            } else {
-                let source_slice = slice::from_raw_parts(source as *const u8, pitch * height);
-                for row in 0..height {
-                    let row_start = row * pitch;
-                    let row_end = row_start + bytes_per_row;
-                    data_vec.extend_from_slice(&source_slice[row_start..row_end]);
-                }
-            }
-        }
-
-        unsafe { surface.Unmap()? };
-
-        Ok((data_vec, (width, height)))
+        let mut dpi_x = 0;
+        let mut dpi_y = 0;
+        unsafe { GetDpiForMonitor(desc.Monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y)? };
+        Ok((dpi_x, dpi_y))
     }
 
-    /// Captures a single frame and returns it as `Vec<BGRA8>` along with frame metadata.
+    /// Returns the monitor handle of the current capture source.
     ///
-    /// This method captures the current screen content and returns it as a vector
-    /// of [`BGRA8`] pixels along with comprehensive metadata about the frame, including
-    /// dirty rectangles, moved rectangles, and timing information.
+    /// This is the `HMONITOR` that `MonitorFromWindow`, `MonitorFromPoint` and
+    /// `EnumDisplayMonitors` report for the same display, so it can be used to
+    /// match the capture source to window placement. It is read from the
+    /// output description without capturing.
     ///
-    /// # Returns
+    /// Returns `None` if there is no active output duplication or the output
+    /// is not associated with a monitor.
     ///
-    /// On success, returns `Ok((pixels, (width, height), metadata))` where:
-    /// - `pixels` is a `Vec<BGRA8>` containing the pixel data
-    /// - `width` and `height` are the frame dimensions in pixels
-    /// - `metadata` is a [`FrameMetadata`] struct containing detailed frame information
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use windows::Win32::Foundation::HWND;
+    /// use windows::Win32::Graphics::Gdi::{MONITOR_DEFAULTTONEAREST, MonitorFromWindow};
+    ///
+    /// let manager = DXGIManager::new(1000)?;
+    /// # let hwnd = HWND::default();
+    /// let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+    /// if manager.output_hmonitor() == Some(monitor) {
+    ///     println!("The window is on the captured display");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_hmonitor(&self) -> Option<HMONITOR> {
+        let desc = self.duplicated_output.as_ref()?.get_desc().ok()?;
+        (!desc.Monitor.is_invalid()).then_some(desc.Monitor)
+    }
+
+    /// Returns whether the current capture source is showing protected content.
+    ///
+    /// This reflects [`FrameMetadata::protected_content_masked_out`] of the most
+    /// recently acquired frame, including frames acquired by
+    /// [`DXGIManager::peek_metadata`] and captures without metadata. DXGI does
+    /// not describe content protection in the output or duplication
+    /// descriptions, so this cannot be known before a frame was acquired.
+    ///
+    /// Returns `false` if no frame has been acquired yet from the current
+    /// output duplication, or if there is none. Note that the check is based on
+    /// DXGI masking the content: capture still succeeds, but protected areas
+    /// are blacked out.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
-    /// use dxgi_capture_rs::{DXGIManager, CaptureError};
+    /// use dxgi_capture_rs::DXGIManager;
     ///
     /// let mut manager = DXGIManager::new(1000)?;
-    ///
-    /// match manager.capture_frame_with_metadata() {
-    ///     Ok((pixels, (width, height), metadata)) => {
-    ///         println!("Captured {}x{} frame with {} dirty rects and {} move rects",
-    ///                  width, height, metadata.dirty_rects.len(), metadata.move_rects.len());
-    ///     }
-    ///     Err(CaptureError::Timeout) => {
-    ///         // No new frame available within timeout
-    ///     }
-    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// let (pixels, _) = manager.capture_frame()?;
+    /// if manager.is_protected() {
+    ///     println!("Protected content is on screen and was masked out");
     /// }
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn capture_frame_with_metadata(&mut self) -> CaptureFrameWithMetadataResult {
-        let (surface, metadata) = self.acquire_surface(true)?;
-        let (data, dims) = self.copy_surface_data::<BGRA8>(&surface)?;
-        Ok((data, dims, metadata.unwrap()))
+    pub fn is_protected(&self) -> bool {
+        self.duplicated_output
+            .as_ref()
+            .is_some_and(|output| output.protected_content_masked_out)
     }
 
-    /// Captures a single frame and returns it as `Vec<u8>` along with frame metadata.
-    ///
-    /// This method captures the current screen content and returns it as a vector
-    /// of raw bytes representing the pixel components along with comprehensive
-    /// metadata about the frame.
+    /// Returns whether the consumer fell behind the desktop at the last
+    /// acquire, i.e. the frame coalesced more than one desktop update.
     ///
-    /// # Returns
+    /// DXGI merges all updates presented since the previous acquire into one
+    /// frame and counts them in [`FrameMetadata::accumulated_frames`]. A count
+    /// above one means frames were produced faster than they were captured, so
+    /// a streaming application can lower its encode quality or skip
+    /// processing to catch up.
     ///
-    /// On success, returns `Ok((components, (width, height), metadata))` where:
-    /// - `components` is a `Vec<u8>` containing the raw pixel component data
-    /// - `width` and `height` are the frame dimensions in pixels
-    /// - `metadata` is a [`FrameMetadata`] struct containing detailed frame information
+    /// The count is read from the frame info of every acquired frame, including
+    /// captures without metadata such as [`DXGIManager::capture_frame_fast`]
+    /// and frames acquired by [`DXGIManager::peek_metadata`]. Returns `false`
+    /// if no frame has been acquired from the current output duplication yet.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// use dxgi_capture_rs::DXGIManager;
     ///
-    /// let mut manager = DXGIManager::new(1000)?;
+    /// let mut manager = DXGIManager::new(16)?;
+    /// let mut quality = 90;
     ///
-    /// match manager.capture_frame_components_with_metadata() {
-    ///     Ok((components, (width, height), metadata)) => {
-    ///         println!("Captured {}x{} frame with {} bytes", width, height, components.len());
-    ///         if metadata.has_updates() {
-    ///             println!("Frame has {} total changes", metadata.total_change_count());
-    ///         }
-    ///     }
-    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// let (pixels, _) = manager.capture_frame_fast()?;
+    /// if manager.fell_behind() {
+    ///     quality = (quality - 10).max(50);
     /// }
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn capture_frame_components_with_metadata(
-        &mut self,
-    ) -> CaptureFrameComponentsWithMetadataResult {
-        let (surface, metadata) = self.acquire_surface(true)?;
-        let (data, dims) = self.copy_surface_data::<u8>(&surface)?;
-        Ok((data, dims, metadata.unwrap()))
+    pub fn fell_behind(&self) -> bool {
+        self.last_accumulated_frames() > 1
     }
-}
 
-pub type CaptureFrameWithMetadataResult =
-    Result<(Vec<BGRA8>, (usize, usize), FrameMetadata), CaptureError>;
+    /// Returns the number of desktop updates coalesced into the most recently
+    /// acquired frame, or 0 if there is none.
+    ///
+    /// This is 0 for frames that only update the pointer. See
+    /// [`DXGIManager::fell_behind`].
+    pub fn last_accumulated_frames(&self) -> u32 {
+        self.duplicated_output
+            .as_ref()
+            .map_or(0, |output| output.accumulated_frames)
+    }
 
-pub type CaptureFrameComponentsWithMetadataResult =
+    /// Returns whether DXGI coalesced the dirty rectangles of the most
+    /// recently acquired frame, or `false` if there is none.
+    ///
+    /// Like [`DXGIManager::fell_behind`], this is read from the frame info of
+    /// every acquired frame, so it is also known for captures without
+    /// metadata. When it is `true`, the rectangles of the frame may cover
+    /// unchanged pixels, and consumers tracking changes should do a full-frame
+    /// update. See [`FrameMetadata::rects_coalesced`].
+    ///
+    /// DXGI decides to coalesce on its own, typically when updates pile up
+    /// between acquires; the size of the buffer the rectangles are read into
+    /// has no influence. That buffer is grown as needed up to
+    /// [`DXGIManager::set_max_rects`]; beyond it the rectangles are dropped
+    /// and the frame is reported as fully dirty, see
+    /// [`FrameMetadata::rects_truncated`]. Capturing more often is the only
+    /// way to reduce coalescing.
+    /// Metadata captures also report coalesced frames to the logger at
+    /// [`LogLevel::Debug`], which helps tuning the capture rate.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(16)?;
+    /// let (pixels, _) = manager.capture_frame()?;
+    /// if manager.rects_coalesced() {
+    ///     // Send the whole frame instead of the changed regions
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn rects_coalesced(&self) -> bool {
+        self.duplicated_output
+            .as_ref()
+            .is_some_and(|output| output.rects_coalesced)
+    }
+
+    /// Sets the capture source index to select which display to capture from.
+    ///
+    /// In multi-monitor setups, this method allows you to choose which display
+    /// to capture from. Index 0 always refers to the primary display, while
+    /// indices 1 and higher refer to secondary displays. Displays are counted
+    /// across all adapters, so the index matches [`OutputInfo::index`] and
+    /// ranges up to [`DXGIManager::output_count`].
+    ///
+    /// # Arguments
+    ///
+    /// * `cs` - The capture source index:
+    ///   - `0` = Primary display (default)
+    ///   - `1` = First secondary display
+    ///   - `2` = Second secondary display, etc.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// // Capture from primary display (default)
+    /// manager.set_capture_source_index(0)?;
+    /// let primary_frame = manager.capture_frame();
+    ///
+    /// // Switch to secondary display, if there is one
+    /// if manager.set_capture_source_index(1).is_ok() {
+    ///     let secondary_frame = manager.capture_frame();
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`DXGIManager::acquire_output_duplication`] if the
+    /// display at `cs` cannot be duplicated, such as
+    /// [`OutputDuplicationError::NoOutput`] for an index past the last
+    /// display. The previous capture source is then restored, so the index
+    /// and geometry are unchanged and capturing continues from the previous
+    /// display. Restoring can itself fail, for instance if that display was
+    /// just disconnected, in which case the next capture tries again.
+    ///
+    /// # Notes
+    ///
+    /// - This method automatically reinitializes the capture system for the new display
+    /// - The geometry may change when switching between displays of different resolutions
+    pub fn set_capture_source_index(&mut self, cs: usize) -> Result<(), OutputDuplicationError> {
+        let previous_index = self.capture_source_index;
+        self.capture_source_index = cs;
+        self.access_lost_streak = 0;
+
+        if let Err(e) = self.acquire_output_duplication() {
+            self.capture_source_index = previous_index;
+            if cs != previous_index {
+                let _ = self.acquire_output_duplication();
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Gets the current capture source index.
+    ///
+    /// Returns the index of the display currently being used for capture operations.
+    ///
+    /// # Returns
+    ///
+    /// The current capture source index:
+    /// - `0` = Primary display
+    /// - `1` = First secondary display  
+    /// - `2` = Second secondary display, etc.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// // Initially set to primary display
+    /// assert_eq!(manager.get_capture_source_index(), 0);
+    ///
+    /// // Switch to secondary display
+    /// manager.set_capture_source_index(1)?;
+    /// assert_eq!(manager.get_capture_source_index(), 1);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn get_capture_source_index(&self) -> usize {
+        self.capture_source_index
+    }
+
+    /// Returns the number of desktop-attached outputs across all adapters.
+    ///
+    /// Use this to find out how many capture sources exist instead of probing
+    /// increasing indices with [`DXGIManager::set_capture_source_index`]. Only
+    /// the outputs are enumerated, so the active duplication is not affected.
+    /// Adapters whose outputs cannot be enumerated are not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// for index in 0..manager.output_count() {
+    ///     manager.set_capture_source_index(index)?;
+    ///     let (width, height) = manager.geometry();
+    ///     println!("Output {index}: {width}x{height}");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_count(&self) -> usize {
+        let mut count = 0;
+        for i in 0.. {
+            let adapter = match unsafe { self.factory.EnumAdapters1(i) } {
+                Ok(adapter) => adapter,
+                Err(_) => break,
+            };
+            count += get_adapter_outputs(&adapter).map_or(0, |outputs| outputs.len());
+        }
+        count
+    }
+
+    /// Lists the desktop-attached outputs of all adapters.
+    ///
+    /// Outputs are listed in adapter order and then output order, the order
+    /// used by [`DXGIManager::split`]. Outputs that show the same part of the
+    /// desktop as an earlier one, as in clone mode, have
+    /// [`OutputInfo::mirror_of`] set to that output's index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the adapters or outputs cannot be enumerated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let manager = DXGIManager::new(1000)?;
+    ///
+    /// for output in manager.outputs()? {
+    ///     match output.mirror_of {
+    ///         Some(primary) => println!("{} mirrors output {primary}", output.device_name),
+    ///         None => println!("{}: {:?}", output.device_name, output.desktop_rect),
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn outputs(&self) -> Result<Vec<OutputInfo>, OutputDuplicationError> {
+        Ok(enumerate_outputs(&self.factory)?)
+    }
+
+    /// Checks for display topology changes and re-acquires the output
+    /// duplication if the selected capture source is affected.
+    ///
+    /// Docking, undocking or rearranging monitors otherwise goes unnoticed
+    /// until a capture fails with [`CaptureError::AccessLost`]. Call this
+    /// periodically to recover proactively: the outputs are enumerated again
+    /// and compared against those seen at the last acquisition, and the
+    /// duplication is only recreated when the selected source was removed,
+    /// moved, resized or rotated.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` if the set of outputs changed, `Ok(false)` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if enumerating the outputs fails or the selected
+    /// source can no longer be duplicated, for example because it was
+    /// disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// if manager.refresh_outputs()? {
+    ///     println!("Displays changed, now capturing {:?}", manager.geometry());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn refresh_outputs(&mut self) -> Result<bool, OutputDuplicationError> {
+        // A factory only enumerates the adapters and outputs that existed when
+        // it was created, so replace it once it is out of date
+        if !unsafe { self.factory.IsCurrent() }.as_bool() {
+            self.factory = create_dxgi_factory_1()?;
+        }
+
+        let topology = output_topology(&self.factory)?;
+        if topology == self.output_topology {
+            return Ok(false);
+        }
+
+        // Sources are indexed across all adapters, matching acquisition
+        let selected = topology
+            .iter()
+            .flatten()
+            .nth(self.capture_source_index)
+            .copied();
+        let current = match &self.duplicated_output {
+            Some(duplicated_output) => Some(OutputSnapshot::from(&unsafe {
+                duplicated_output.output.GetDesc()?
+            })),
+            None => None,
+        };
+        self.output_topology = topology;
+
+        if !self.virtual_outputs.is_empty() || !self.source_outputs.is_empty() {
+            // Virtual desktop and round-robin capture re-acquire every output
+            // on their next call
+            self.virtual_outputs.clear();
+            self.source_outputs.clear();
+        } else if selected.is_none() || selected != current {
+            self.acquire_output_duplication()?;
+        }
+        Ok(true)
+    }
+
+    /// Sets the timeout for capture operations.
+    ///
+    /// This timeout determines how long capture operations will wait for a new
+    /// frame to become available before returning with a timeout error.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout_ms` - The timeout in milliseconds:
+    ///   - `0` = No timeout (immediate return if no frame available)
+    ///   - `1-1000` = Short timeout for real-time applications
+    ///   - `1000-5000` = Medium timeout for interactive applications
+    ///   - `>5000` = Long timeout for less frequent captures
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// // Set short timeout for real-time capture
+    /// manager.set_timeout_ms(100);
+    ///
+    /// // Set no timeout for immediate return
+    /// manager.set_timeout_ms(0);
+    ///
+    /// // Set longer timeout for less frequent captures
+    /// manager.set_timeout_ms(5000);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_timeout_ms(&mut self, timeout_ms: u32) {
+        self.timeout_ms = timeout_ms
+    }
+
+    /// Gets the current timeout value for capture operations.
+    ///
+    /// Returns the timeout in milliseconds that capture operations will wait
+    /// for a new frame to become available.
+    ///
+    /// # Returns
+    ///
+    /// The current timeout in milliseconds:
+    /// - `0` = No timeout (immediate return)
+    /// - `>0` = Timeout in milliseconds
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// // Check initial timeout
+    /// assert_eq!(manager.get_timeout_ms(), 1000);
+    ///
+    /// // Change timeout and verify
+    /// manager.set_timeout_ms(500);
+    /// assert_eq!(manager.get_timeout_ms(), 500);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn get_timeout_ms(&self) -> u32 {
+        self.timeout_ms
+    }
+
+    /// Sets the timeout for capture operations as a [`Duration`].
+    ///
+    /// This is equivalent to [`DXGIManager::set_timeout_ms`] with the duration
+    /// in whole milliseconds, so sub-millisecond parts are truncated. Durations
+    /// of `u32::MAX` milliseconds (about 49.7 days) or longer saturate to
+    /// `u32::MAX`, which DXGI treats as an infinite timeout.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use std::time::Duration;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// manager.set_timeout(Duration::from_millis(250));
+    /// assert_eq!(manager.get_timeout_ms(), 250);
+    ///
+    /// // Saturates instead of wrapping around
+    /// manager.set_timeout(Duration::MAX);
+    /// assert_eq!(manager.get_timeout_ms(), u32::MAX);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout_ms = duration_to_timeout_ms(timeout)
+    }
+
+    /// Gets the timeout for capture operations as a [`Duration`].
+    ///
+    /// See [`DXGIManager::get_timeout_ms`].
+    pub fn get_timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms as u64)
+    }
+
+    /// Enables or disables automatic recovery from lost duplication access.
+    ///
+    /// When enabled, a capture that fails with [`CaptureError::AccessLost`],
+    /// [`CaptureError::DeviceLost`] or [`CaptureError::AccessDenied`] tears
+    /// down the output duplication, re-acquires it for the same capture source
+    /// index, and retries the capture once before returning an error. This
+    /// smooths over resolution changes, resuming from sleep and secure desktop
+    /// (UAC) transitions. Disabled by default.
+    ///
+    /// At most one recovery attempt is made per capture call. If re-acquisition
+    /// itself fails, [`CaptureError::RefreshFailure`] is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// manager.set_auto_recover(true);
+    ///
+    /// // Transient access loss is now retried transparently
+    /// let frame = manager.capture_frame();
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_auto_recover(&mut self, enabled: bool) {
+        self.auto_recover = enabled
+    }
+
+    /// Returns whether automatic recovery from lost duplication access is enabled.
+    ///
+    /// See [`DXGIManager::set_auto_recover`].
+    pub fn get_auto_recover(&self) -> bool {
+        self.auto_recover
+    }
+
+    /// Returns whether the capture source appears to be taken over by an
+    /// application in exclusive fullscreen mode.
+    ///
+    /// Exclusive fullscreen games bypass the desktop compositor, so every
+    /// capture fails with [`CaptureError::AccessLost`] and re-duplicating the
+    /// output fails or is lost again right away. DXGI offers no direct query
+    /// for this, so it is a heuristic: it returns `true` after several
+    /// captures failed with [`CaptureError::AccessLost`] without a successful
+    /// or timed out capture in between, which resets it, as does changing the
+    /// capture source. Other errors, such as a missing output, an unsupported
+    /// session or the secure desktop, neither count nor reset it.
+    ///
+    /// A recorder can use this to warn the user or switch to another capture
+    /// strategy instead of retrying in a tight loop.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{CaptureError, DXGIManager};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// loop {
+    ///     match manager.capture_frame() {
+    ///         Ok(_) | Err(CaptureError::Timeout) => {}
+    ///         Err(_) if manager.is_output_in_fullscreen_exclusive() => {
+    ///             eprintln!("A fullscreen application is blocking capture");
+    ///             break;
+    ///         }
+    ///         Err(_) => {}
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn is_output_in_fullscreen_exclusive(&self) -> bool {
+        self.access_lost_streak >= FULLSCREEN_EXCLUSIVE_STREAK
+    }
+
+    /// Enables or disables drawing the mouse pointer into captured frames.
+    ///
+    /// Desktop Duplication captures the desktop without the pointer. With this
+    /// enabled, [`DXGIManager::capture_frame`], [`DXGIManager::capture_frame_components`],
+    /// [`DXGIManager::capture_frame_rgba`] and their metadata variants draw the
+    /// current pointer shape at its last reported position, so the result
+    /// matches what the user sees. Color pointers are alpha-blended, while
+    /// monochrome and masked-color pointers are applied with their AND/XOR
+    /// masks. Nothing is drawn while the pointer is hidden, and the shape is
+    /// clipped at the frame edges.
+    ///
+    /// Methods returning the mapped surface as is never draw the pointer,
+    /// including [`DXGIManager::capture_frame_fast`],
+    /// [`DXGIManager::capture_frame_fast_rotated`] and
+    /// [`DXGIManager::capture_frame_nv12`], also where the latter two fall
+    /// back to the rotation-aware copy on rotated displays.
+    ///
+    /// DXGI only reports the pointer shape when it changes, so the pointer may
+    /// be missing from the first frames after enabling this or after the
+    /// output duplication is re-acquired, until the pointer changes shape.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// manager.set_composite_cursor(true);
+    ///
+    /// // The screenshot now includes the mouse pointer
+    /// let (pixels, (width, height)) = manager.capture_frame()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_composite_cursor(&mut self, enabled: bool) {
+        self.composite_cursor = enabled
+    }
+
+    /// Returns whether the mouse pointer is drawn into captured frames.
+    ///
+    /// See [`DXGIManager::set_composite_cursor`].
+    pub fn get_composite_cursor(&self) -> bool {
+        self.composite_cursor
+    }
+
+    /// Sets the largest frame, in pixels, that captures accept.
+    ///
+    /// Captures of larger frames fail with [`CaptureError::FrameTooLarge`]
+    /// before any buffer is allocated, as do frames whose size in bytes would
+    /// overflow `usize`. This guards against malformed surface descriptions
+    /// from misbehaving drivers. The limit applies to the stitched image of
+    /// [`DXGIManager::capture_virtual_desktop`] as well. Defaults to
+    /// 16384 x 16384 pixels, the largest Direct3D 11 texture.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// // Never allocate more than a 4K frame
+    /// manager.set_max_frame_pixels(3840 * 2160);
+    /// let (pixels, (width, height)) = manager.capture_frame()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_max_frame_pixels(&mut self, pixels: usize) {
+        self.max_frame_pixels = pixels;
+    }
+
+    /// Returns the largest frame, in pixels, that captures accept.
+    ///
+    /// See [`DXGIManager::set_max_frame_pixels`].
+    pub fn get_max_frame_pixels(&self) -> usize {
+        self.max_frame_pixels
+    }
+
+    /// Enables or disables the timing of [`DXGIManager::capture_frame_timed`].
+    ///
+    /// When disabled, `Instant::now` is never called and all reported
+    /// durations are zero, so the timed capture can stay in place in release
+    /// builds at no cost. Enabled by default.
+    pub fn set_capture_timing(&mut self, enabled: bool) {
+        self.capture_timing = enabled;
+    }
+
+    /// Returns whether [`DXGIManager::capture_frame_timed`] measures timings.
+    ///
+    /// See [`DXGIManager::set_capture_timing`].
+    pub fn get_capture_timing(&self) -> bool {
+        self.capture_timing
+    }
+
+    /// Sets the alpha channel of every captured pixel to 255.
+    ///
+    /// Microsoft does not define the alpha channel of the duplicated desktop
+    /// image. In practice it is often 0, which makes frames disappear in
+    /// compositors and image viewers that honor alpha. With this enabled, the
+    /// alpha is overwritten while the frame is copied: the `a` field of
+    /// [`BGRA8`] captures, and every fourth byte of component captures.
+    ///
+    /// This applies to all methods that copy the frame into a new buffer,
+    /// including [`FrameGuard::map`]. Methods that expose the mapped surface,
+    /// such as [`DXGIManager::capture_frame_with`],
+    /// [`DXGIManager::capture_frame_borrowed`] and
+    /// [`DXGIManager::capture_frame_raw`], return the alpha as is. Disabled by
+    /// default. Enabling it disables [`DXGIManager::set_premultiply_alpha`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::builder().force_opaque_alpha(true).build()?;
+    /// let (pixels, _) = manager.capture_frame()?;
+    /// assert!(pixels.iter().all(|pixel| pixel.a == 255));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_force_opaque_alpha(&mut self, enabled: bool) {
+        self.force_opaque_alpha = enabled;
+        if enabled {
+            self.premultiply_alpha = false;
+        }
+    }
+
+    /// Returns whether captured pixels are made opaque.
+    ///
+    /// See [`DXGIManager::set_force_opaque_alpha`].
+    pub fn get_force_opaque_alpha(&self) -> bool {
+        self.force_opaque_alpha
+    }
+
+    /// Returns captured pixels with premultiplied alpha, as compositors
+    /// blending with premultiplied alpha expect.
+    ///
+    /// Each color channel `c` of a pixel with alpha `a` becomes `c * a / 255`,
+    /// rounded down; the alpha is kept. See [`premultiply_alpha`] for the
+    /// arithmetic. This applies to the same methods as
+    /// [`DXGIManager::set_force_opaque_alpha`], after the pixels are copied
+    /// and the pointer is drawn into them. In 16-bit frames of
+    /// [`DXGIManager::capture_frame_u16`], `c` becomes `c * a / 65535`.
+    ///
+    /// The two options are mutually exclusive: enabling one disables the
+    /// other. Since the alpha of the desktop image is undefined and often 0,
+    /// premultiplying usually turns the frame black. Prefer forcing it opaque
+    /// unless the source is known to carry meaningful alpha. Disabled by
+    /// default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::builder().premultiply_alpha(true).build()?;
+    /// let (pixels, _) = manager.capture_frame()?;
+    /// assert!(pixels.iter().all(|pixel| pixel.r <= pixel.a && pixel.b <= pixel.a));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_premultiply_alpha(&mut self, enabled: bool) {
+        self.premultiply_alpha = enabled;
+        if enabled {
+            self.force_opaque_alpha = false;
+        }
+    }
+
+    /// Returns whether captured pixels have premultiplied alpha.
+    ///
+    /// See [`DXGIManager::set_premultiply_alpha`].
+    pub fn get_premultiply_alpha(&self) -> bool {
+        self.premultiply_alpha
+    }
+
+    /// Treats each set of mirrored outputs as a single output in
+    /// [`DXGIManager::split`].
+    ///
+    /// In clone mode, outputs showing the same part of the desktop would
+    /// otherwise each get a capturer returning identical frames. With this
+    /// enabled, only the first output of each set, the one that
+    /// [`OutputInfo::mirror_of`] refers to, is duplicated. Capturers keep the
+    /// index of their output in [`DXGIManager::outputs`], so indices may have
+    /// gaps. Disabled by default.
+    pub fn set_skip_mirrored_outputs(&mut self, enabled: bool) {
+        self.skip_mirrored_outputs = enabled;
+    }
+
+    /// Returns whether mirrored outputs are left out of [`DXGIManager::split`].
+    ///
+    /// See [`DXGIManager::set_skip_mirrored_outputs`].
+    pub fn get_skip_mirrored_outputs(&self) -> bool {
+        self.skip_mirrored_outputs
+    }
+
+    /// Keeps each captured frame acquired until right before the next one is
+    /// acquired, instead of releasing it as soon as it is copied.
+    ///
+    /// This is the pattern Microsoft recommends for desktop duplication: while
+    /// a frame is acquired, DXGI accumulates further desktop updates into the
+    /// next frame instead of preparing frames that are never captured, and the
+    /// `ReleaseFrame` call moves off the path between acquiring a frame and
+    /// returning its pixels. Any gain depends on the driver and the capture
+    /// loop and has not been measured here; it can only show in tight loops
+    /// that acquire again right after processing a frame, so run the
+    /// `capture_frame_lazy_release` benchmark to compare both modes on the
+    /// target system before enabling it. Between captures the
+    /// held frame is of no use to anyone else, so leave this disabled when
+    /// captures are far apart or the output is also duplicated by another
+    /// process. Disabled by default.
+    ///
+    /// A held frame is always released before the next frame is acquired, and
+    /// when the duplication is dropped, so captures behave the same in both
+    /// modes. This applies to the capture methods that copy the frame into a
+    /// staging texture; [`DXGIManager::peek_metadata`],
+    /// [`DXGIManager::capture_latest_frame`] and scaled captures release right
+    /// away regardless.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::builder().lazy_release(true).build()?;
+    ///
+    /// for _ in 0..600 {
+    ///     if let Ok((pixels, _)) = manager.capture_frame() {
+    ///         // Encode; the frame is released by the next capture
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_lazy_release(&mut self, enabled: bool) {
+        self.lazy_release = enabled;
+        if let Some(output) = &mut self.duplicated_output {
+            output.lazy_release = enabled;
+        }
+    }
+
+    /// Returns whether frames are kept acquired until the next capture.
+    ///
+    /// See [`DXGIManager::set_lazy_release`].
+    pub fn get_lazy_release(&self) -> bool {
+        self.lazy_release
+    }
+
+    /// Returns captured frames bottom-up, with the last row of the image first.
+    ///
+    /// OpenGL and some bitmap formats expect images bottom-up. The flip is
+    /// applied after rotation correction, so the result is the upright frame
+    /// mirrored top to bottom, and it happens in the same pass that copies and
+    /// rotates the pixels rather than as a separate pass. The pointer is drawn
+    /// flipped as well.
+    ///
+    /// This applies to [`DXGIManager::capture_frame`] and the other methods
+    /// returning upright frames through the same copy: the component, RGBA,
+    /// metadata, hashed, timed, 16-bit and into-slice variants,
+    /// [`DXGIManager::capture_frame_shared`], [`FrameGuard::map`] and
+    /// [`DXGIManager::capture_window_region`]. Methods in the native
+    /// orientation of the display, region and scaled captures are not
+    /// flipped, and neither are [`DXGIManager::capture_frame_nv12`] and
+    /// [`DXGIManager::capture_frame_fast_rotated`], whatever path they take.
+    /// Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::builder().flip_vertical(true).build()?;
+    /// let (pixels, (width, height)) = manager.capture_frame()?;
+    /// // Upload straight into an OpenGL texture, whose first row is the bottom
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_flip_vertical(&mut self, enabled: bool) {
+        self.flip_vertical = enabled;
+    }
+
+    /// Returns whether captured frames are flipped bottom-up.
+    ///
+    /// See [`DXGIManager::set_flip_vertical`].
+    pub fn get_flip_vertical(&self) -> bool {
+        self.flip_vertical
+    }
+
+    /// Sets the fraction of the screen, between 0.0 and 1.0, that must change
+    /// for [`DXGIManager::capture_on_change`] and
+    /// [`DXGIManager::capture_frame_with_metadata`] to return a frame.
+    ///
+    /// The changed area is the summed area of the dirty and move rectangles,
+    /// computed as in [`DXGIManager::changed_area`], relative to the total
+    /// number of pixels. Frames at or below the threshold, including
+    /// pointer-only updates, are released without copying and the wait
+    /// continues, up to the timeout given to `capture_on_change` or the
+    /// configured timeout for `capture_frame_with_metadata`. A frame whose
+    /// rectangles were coalesced or are unavailable counts as fully changed.
+    /// Since DXGI accumulates updates into the next frame only while it is
+    /// not acquired, small changes that were released are not added up.
+    ///
+    /// Values are clamped to 0.0..=1.0, and NaN is treated as 0.0. At 0.0,
+    /// the default, `capture_frame_with_metadata` returns every frame and
+    /// `capture_on_change` every frame with changes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use std::time::Duration;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// // Only stream frames in which more than 5% of the screen changed
+    /// manager.set_min_change_fraction(0.05);
+    /// let (pixels, (width, height), metadata) =
+    ///     manager.capture_on_change(Duration::from_secs(10))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_min_change_fraction(&mut self, fraction: f32) {
+        self.min_change_fraction = clamp_change_fraction(fraction);
+    }
+
+    /// Returns the fraction of the screen that must change for a frame to be
+    /// returned.
+    ///
+    /// See [`DXGIManager::set_min_change_fraction`].
+    pub fn get_min_change_fraction(&self) -> f32 {
+        self.min_change_fraction
+    }
+
+    /// Sets the most dirty or move rectangles read from a frame, bounding the
+    /// memory used for frame metadata.
+    ///
+    /// DXGI can report thousands of rectangles during heavy UI activity. When
+    /// a frame has more dirty rectangles or more move rectangles than
+    /// `max_rects`, none of them are read. The frame is then reported as
+    /// fully dirty, with a single dirty rectangle covering the desktop image,
+    /// no move rectangles and [`FrameMetadata::rects_truncated`] set. The
+    /// limit applies to each list separately. The default is unlimited.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// manager.set_max_rects(256);
+    /// let (pixels, (width, height), metadata) = manager.capture_frame_with_metadata()?;
+    /// if metadata.rects_truncated {
+    ///     // Re-encode the whole frame
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_max_rects(&mut self, max_rects: usize) {
+        self.max_rects = max_rects;
+        if let Some(output) = &mut self.duplicated_output {
+            output.max_rects = max_rects;
+        }
+    }
+
+    /// Returns the most dirty or move rectangles read from a frame.
+    ///
+    /// See [`DXGIManager::set_max_rects`].
+    pub fn get_max_rects(&self) -> usize {
+        self.max_rects
+    }
+
+    /// Sets the byte order of pixels returned as bytes.
+    ///
+    /// With [`PixelOrder::Rgba`], [`DXGIManager::capture_frame_components`],
+    /// [`DXGIManager::capture_frame_components_into_slice`],
+    /// [`DXGIManager::capture_frame_components_with_metadata`],
+    /// [`DXGIManager::capture_frame_fast`] and
+    /// [`DXGIManager::capture_frame_fast_rotated`] return RGBA bytes. The
+    /// channels are swapped in the same in-place pass that converts outputs
+    /// with an RGBA desktop image, vectorized with the `simd` feature, so no
+    /// separate conversion is needed. Methods returning [`BGRA8`] pixels and
+    /// those exposing the mapped surface are not affected.
+    ///
+    /// Defaults to [`PixelOrder::Bgra`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, PixelOrder};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// manager.set_output_order(PixelOrder::Rgba);
+    ///
+    /// // Ready for image libraries that expect RGBA
+    /// let (rgba, (width, height)) = manager.capture_frame_components()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_output_order(&mut self, order: PixelOrder) {
+        self.output_order = order;
+    }
+
+    /// Returns the byte order of pixels returned as bytes.
+    ///
+    /// See [`DXGIManager::set_output_order`].
+    pub fn get_output_order(&self) -> PixelOrder {
+        self.output_order
+    }
+
+    /// Sets a callback receiving diagnostics about output duplication and
+    /// capture failures.
+    ///
+    /// The logger is called at each step of finding and duplicating the
+    /// capture source, e.g. when an adapter is skipped or `DuplicateOutput`
+    /// fails, with the failing HRESULT in the message, and when a capture fails
+    /// for a reason other than a timeout. This makes failures observable in the
+    /// field without patching the crate. Use [`DXGIManagerBuilder::logger`] to
+    /// also see diagnostics from the initial duplication. No logger is set by
+    /// default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, LogLevel};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// manager.set_logger(|level, message| {
+    ///     if level <= LogLevel::Warn {
+    ///         eprintln!("[dxgi-capture {level:?}] {message}");
+    ///     }
+    /// });
+    /// manager.acquire_output_duplication()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_logger(&mut self, logger: impl Fn(LogLevel, &str) + Send + Sync + 'static) {
+        self.logger = Some(Logger(Arc::new(logger)));
+    }
+
+    /// Removes the logger set with [`DXGIManager::set_logger`].
+    pub fn clear_logger(&mut self) {
+        self.logger = None;
+    }
+
+    /// Reinitializes the output duplication for the selected capture source.
+    ///
+    /// This method is automatically called when needed, but can be called manually
+    /// to recover from certain error conditions. It reinitializes the DXGI
+    /// Desktop Duplication system for the currently selected capture source.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or `Err(OutputDuplicationError)` if the
+    /// reinitialization fails.
+    ///
+    /// If the manager was built with [`DXGIManagerBuilder::acquire_retries`],
+    /// a failure to find or duplicate the output is retried that many times
+    /// before giving up.
+    ///
+    /// # Errors
+    ///
+    /// - [`OutputDuplicationError::NoOutput`] if no suitable display is found
+    /// - [`OutputDuplicationError::DeviceError`] if device creation fails, or
+    ///   with `DXGI_ERROR_SESSION_DISCONNECTED` while the session is
+    ///   disconnected, which is not retried
+    /// - [`OutputDuplicationError::UnsupportedSession`] if no adapter could
+    ///   duplicate the output in a remote desktop session
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, CaptureError};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// // Manually reinitialize if needed
+    /// match manager.acquire_output_duplication() {
+    ///     Ok(()) => println!("Successfully reinitialized"),
+    ///     Err(e) => println!("Failed to reinitialize: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn acquire_output_duplication(&mut self) -> Result<(), OutputDuplicationError> {
+        // Drop any existing output duplication first, releasing the COM
+        // resources before attempting to acquire new ones. This includes the
+        // per-output duplications held for virtual desktop and round-robin
+        // capture.
+        self.duplicated_output = None;
+        self.virtual_outputs.clear();
+        self.source_outputs.clear();
+
+        let mut retries = self.acquire_retries;
+        loop {
+            match self.try_acquire_output_duplication() {
+                // Duplication failures such as E_ACCESSDENIED or
+                // DXGI_ERROR_UNSUPPORTED surface as `NoOutput` and are often
+                // transient; device errors are not retried.
+                Err(OutputDuplicationError::NoOutput) if retries > 0 => {
+                    self.log(
+                        LogLevel::Warn,
+                        format_args!(
+                            "Capture source {} not duplicated, {retries} retries left",
+                            self.capture_source_index
+                        ),
+                    );
+                    retries -= 1;
+                    thread::sleep(self.acquire_retry_delay);
+                }
+                Err(e) => {
+                    self.log(
+                        LogLevel::Error,
+                        format_args!(
+                            "Failed to duplicate capture source {}: {e}",
+                            self.capture_source_index
+                        ),
+                    );
+                    return Err(e);
+                }
+                Ok(()) => break,
+            }
+        }
+
+        self.output_topology = output_topology(&self.factory)?;
+        Ok(())
+    }
+
+    /// Makes a single attempt at duplicating the selected capture source.
+    fn try_acquire_output_duplication(&mut self) -> Result<(), OutputDuplicationError> {
+        if let Some(device) = self.external_device.clone() {
+            return self.try_acquire_with_external_device(device);
+        }
+
+        // Only look up and duplicate the single output we actually need.
+        let (i, adapter, output) =
+            match get_output_at_index(&self.factory, self.capture_source_index) {
+                Ok(Some(found)) => found,
+                Ok(None) => {
+                    self.log(
+                        LogLevel::Debug,
+                        format_args!("There is no output {}", self.capture_source_index),
+                    );
+                    return Err(OutputDuplicationError::NoOutput);
+                }
+                Err(e) => {
+                    self.log(
+                        LogLevel::Error,
+                        format_args!("Enumerating outputs failed: {e}"),
+                    );
+                    return Err(e.into());
+                }
+            };
+
+        let duplicated_output =
+            self.adapter_device(&adapter)
+                .and_then(|(device, device_context)| {
+                    DuplicatedOutput::with_device(
+                        device,
+                        device_context,
+                        output,
+                        self.cpu_write_access,
+                        &self.duplication_formats,
+                    )
+                });
+        match duplicated_output {
+            Ok(mut duplicated_output) => {
+                self.log(
+                    LogLevel::Debug,
+                    format_args!(
+                        "Duplicated output {} on adapter {i}",
+                        self.capture_source_index
+                    ),
+                );
+                duplicated_output.lazy_release = self.lazy_release;
+                duplicated_output.max_rects = self.max_rects;
+                self.duplicated_output = Some(duplicated_output);
+                Ok(())
+            }
+            Err(e) => {
+                self.log(
+                    LogLevel::Warn,
+                    format_args!(
+                        "Duplicating output {} on adapter {i} failed: {e}",
+                        self.capture_source_index
+                    ),
+                );
+                // Keep the code so it surfaces as `SessionDisconnected`, and
+                // do not retry, as the session will not return in time
+                if e.code() == DXGI_ERROR_SESSION_DISCONNECTED {
+                    return Err(OutputDuplicationError::DeviceError(e));
+                }
+                if is_remote_session() {
+                    return Err(OutputDuplicationError::UnsupportedSession);
+                }
+                Err(OutputDuplicationError::NoOutput)
+            }
+        }
+    }
+
+    /// Duplicates the selected output using `device` and its immediate
+    /// context, provided the output is attached to the adapter `device`
+    /// belongs to.
+    fn try_acquire_with_external_device(
+        &mut self,
+        device: ID3D11Device,
+    ) -> Result<(), OutputDuplicationError> {
+        if unsafe { device.GetCreationFlags() } & D3D11_CREATE_DEVICE_BGRA_SUPPORT.0 == 0 {
+            return Err(OutputDuplicationError::DeviceError(
+                windows::core::Error::new(
+                    E_INVALIDARG,
+                    "The device was not created with D3D11_CREATE_DEVICE_BGRA_SUPPORT",
+                ),
+            ));
+        }
+
+        let adapter: IDXGIAdapter1 =
+            unsafe { device.cast::<IDXGIDevice>()?.GetAdapter()? }.cast()?;
+        let luid = unsafe { adapter.GetDesc1()? }.AdapterLuid;
+        let output = match get_output_at_index(&self.factory, self.capture_source_index)? {
+            Some((_, output_adapter, output))
+                if unsafe { output_adapter.GetDesc1()? }.AdapterLuid == luid =>
+            {
+                output
+            }
+            _ => {
+                self.log(
+                    LogLevel::Error,
+                    format_args!(
+                        "Output {} is not attached to the adapter of the supplied device",
+                        self.capture_source_index
+                    ),
+                );
+                return Err(OutputDuplicationError::NoOutput);
+            }
+        };
+        let device_context = unsafe { device.GetImmediateContext()? };
+
+        let mut duplicated_output = DuplicatedOutput::with_device(
+            device,
+            device_context,
+            output,
+            self.cpu_write_access,
+            &self.duplication_formats,
+        )?;
+        self.log(
+            LogLevel::Debug,
+            format_args!(
+                "Duplicated output {} with the supplied device",
+                self.capture_source_index
+            ),
+        );
+        duplicated_output.lazy_release = self.lazy_release;
+        duplicated_output.max_rects = self.max_rects;
+        self.duplicated_output = Some(duplicated_output);
+        Ok(())
+    }
+
+    /// Passes a diagnostic to the logger, if one is set, only formatting it
+    /// when needed.
+    fn log(&self, level: LogLevel, message: fmt::Arguments<'_>) {
+        if let Some(Logger(logger)) = &self.logger {
+            logger(level, &message.to_string());
+        }
+    }
+
+    /// Returns the D3D11 device for `adapter`, reusing the cached one if it
+    /// belongs to the same adapter and has not been removed, and creating and
+    /// caching a new one otherwise.
+    fn adapter_device(
+        &mut self,
+        adapter: &IDXGIAdapter1,
+    ) -> WindowsResult<(ID3D11Device, ID3D11DeviceContext)> {
+        let luid = unsafe { adapter.GetDesc1()? }.AdapterLuid;
+        if let Some((cached_luid, device, device_context)) = &self.adapter_device
+            && *cached_luid == luid
+            && unsafe { device.GetDeviceRemovedReason() }.is_ok()
+        {
+            return Ok((device.clone(), device_context.clone()));
+        }
+
+        self.adapter_device = None;
+        let (device, device_context) = d3d11_create_device(Some(&adapter.cast()?))?;
+        self.adapter_device = Some((luid, device.clone(), device_context.clone()));
+        Ok((device, device_context))
+    }
+
+    /// Duplicates every desktop-attached output on every adapter for virtual
+    /// desktop capture, releasing the single-source duplication first.
+    fn acquire_virtual_outputs(&mut self) -> Result<(), OutputDuplicationError> {
+        self.duplicated_output = None;
+        self.virtual_outputs.clear();
+        self.source_outputs.clear();
+
+        for i in 0.. {
+            let adapter = match unsafe { self.factory.EnumAdapters1(i) } {
+                Ok(adapter) => adapter,
+                Err(e) if e.code() == DXGI_ERROR_NOT_FOUND => break,
+                Err(e) => return Err(e.into()),
+            };
+
+            for output in get_adapter_outputs(&adapter)? {
+                let desktop_rect = unsafe { output.GetDesc()? }.DesktopCoordinates;
+                // A mirrored output would only paint the same area again
+                if self
+                    .virtual_outputs
+                    .iter()
+                    .any(|virtual_output| virtual_output.desktop_rect == desktop_rect)
+                {
+                    continue;
+                }
+                let duplicated_output = match DuplicatedOutput::new(&adapter, output, false, &[]) {
+                    Ok(duplicated_output) => duplicated_output,
+                    Err(e) => {
+                        self.virtual_outputs.clear();
+                        return Err(e.into());
+                    }
+                };
+                self.virtual_outputs.push(VirtualOutput {
+                    duplicated_output,
+                    desktop_rect,
+                    pixels: Vec::new(),
+                    dimensions: (0, 0),
+                });
+            }
+        }
+
+        if self.virtual_outputs.is_empty() {
+            Err(OutputDuplicationError::NoOutput)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Duplicates every desktop-attached output on every adapter for
+    /// round-robin capture, releasing the other duplications first. Indices
+    /// and mirrored outputs are handled as in [`DXGIManager::split`].
+    fn acquire_source_outputs(&mut self) -> Result<(), OutputDuplicationError> {
+        self.duplicated_output = None;
+        self.virtual_outputs.clear();
+        self.source_outputs.clear();
+
+        let mut desktop_rects = Vec::new();
+        for i in 0.. {
+            let adapter = match unsafe { self.factory.EnumAdapters1(i) } {
+                Ok(adapter) => adapter,
+                Err(e) if e.code() == DXGI_ERROR_NOT_FOUND => break,
+                Err(e) => return Err(e.into()),
+            };
+
+            for output in get_adapter_outputs(&adapter)? {
+                let desktop_rect = unsafe { output.GetDesc()? }.DesktopCoordinates;
+                let index = desktop_rects.len();
+                let mirrored = desktop_rects.contains(&desktop_rect);
+                desktop_rects.push(desktop_rect);
+                if mirrored && self.skip_mirrored_outputs {
+                    continue;
+                }
+
+                match DuplicatedOutput::new(&adapter, output, false, &[]) {
+                    Ok(duplicated_output) => {
+                        self.source_outputs.push((index, duplicated_output));
+                    }
+                    Err(e) => {
+                        self.source_outputs.clear();
+                        return Err(e.into());
+                    }
+                }
+            }
+        }
+
+        if self.source_outputs.is_empty() {
+            Err(OutputDuplicationError::NoOutput)
+        } else {
+            Ok(())
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Internal capture helpers
+    // -----------------------------------------------------------------------
+
+    /// Runs `f` against the current duplicated output, re-acquiring it first
+    /// if needed.  On recoverable DXGI errors the internal `duplicated_output`
+    /// is reset so the next capture attempt will re-acquire.  With auto-recover
+    /// enabled, `f` is retried once after access is lost or denied.
+    fn with_duplicated_output<R>(
+        &mut self,
+        mut f: impl FnMut(&mut DuplicatedOutput, u32) -> WindowsResult<R>,
+    ) -> Result<R, CaptureError> {
+        let mut recovered = false;
+        let result = loop {
+            if self.duplicated_output.is_none()
+                && let Err(e) = self.acquire_output_duplication()
+            {
+                break Err(map_refresh_error(e));
+            }
+
+            let timeout_ms = self.timeout_ms;
+            let dup = self.duplicated_output.as_mut().unwrap();
+
+            match f(dup, timeout_ms) {
+                Ok(result) => break Ok(result),
+                Err(e) => {
+                    let err = map_capture_error(e);
+                    // On non-timeout errors, drop the output so it is re-acquired.
+                    if !matches!(err, CaptureError::Timeout) {
+                        self.duplicated_output = None;
+                    }
+                    // A removed device must not be reused for the new duplication
+                    if matches!(err, CaptureError::DeviceLost) {
+                        self.adapter_device = None;
+                    }
+                    if self.auto_recover
+                        && !recovered
+                        && matches!(
+                            err,
+                            CaptureError::AccessLost
+                                | CaptureError::DeviceLost
+                                | CaptureError::AccessDenied
+                        )
+                    {
+                        recovered = true;
+                        continue;
+                    }
+                    break Err(err);
+                }
+            }
+        };
+        self.track_access_lost(&result);
+        if let Err(e) = &result
+            && !matches!(e, CaptureError::Timeout)
+        {
+            self.log(LogLevel::Warn, format_args!("Capture failed: {e}"));
+        }
+        result
+    }
+
+    /// Updates the streak behind [`DXGIManager::is_output_in_fullscreen_exclusive`]
+    /// with the outcome of a capture.
+    fn track_access_lost<R>(&mut self, result: &Result<R, CaptureError>) {
+        match result {
+            Ok(_) | Err(CaptureError::Timeout) => self.access_lost_streak = 0,
+            Err(CaptureError::AccessLost) => {
+                self.access_lost_streak = self.access_lost_streak.saturating_add(1)
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Acquires a frame surface, optionally with the metadata selected by
+    /// `metadata`.
+    fn acquire_surface(
+        &mut self,
+        metadata: Option<MetadataFlags>,
+    ) -> Result<(IDXGISurface1, Option<FrameMetadata>), CaptureError> {
+        let (surface, metadata) = self.with_duplicated_output(|dup, timeout_ms| {
+            dup.capture_frame_to_surface(timeout_ms, metadata)
+        })?;
+        if let Some(metadata) = &metadata
+            && metadata.rects_coalesced
+        {
+            self.log(
+                LogLevel::Debug,
+                format_args!(
+                    "DXGI coalesced the rectangles of a frame with {} accumulated updates",
+                    metadata.accumulated_frames
+                ),
+            );
+        }
+        check_surface_size(&surface, self.max_frame_pixels)?;
+        Ok((surface, metadata))
+    }
+
+    /// Like [`DXGIManager::acquire_surface`], but waits for at most
+    /// `timeout_ms` instead of the configured timeout.
+    fn acquire_surface_with_timeout(
+        &mut self,
+        metadata: Option<MetadataFlags>,
+        timeout_ms: u32,
+    ) -> Result<(IDXGISurface1, Option<FrameMetadata>), CaptureError> {
+        let (surface, metadata) = self
+            .with_duplicated_output(|dup, _| dup.capture_frame_to_surface(timeout_ms, metadata))?;
+        check_surface_size(&surface, self.max_frame_pixels)?;
+        Ok((surface, metadata))
+    }
+
+    /// Returns the rotation of the current output, acquiring the output
+    /// duplication first if needed.
+    fn output_rotation(&mut self) -> Result<DXGI_MODE_ROTATION, CaptureError> {
+        if self.duplicated_output.is_none() {
+            self.acquire_output_duplication()
+                .map_err(map_refresh_error)?;
+        }
+        let rotation = self
+            .duplicated_output
+            .as_ref()
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?
+            .rotation()?;
+        Ok(rotation)
+    }
+
+    /// Returns the desktop image format of the current output, acquiring the
+    /// output duplication first if needed.
+    fn output_format(&mut self) -> Result<DXGI_FORMAT, CaptureError> {
+        if self.duplicated_output.is_none() {
+            self.acquire_output_duplication()
+                .map_err(map_refresh_error)?;
+        }
+        self.current_format()
+    }
+
+    /// Reads pixel data from a mapped surface sized to the current output,
+    /// handling rotation and returning the channels in `order`. `T` is either
+    /// [`BGRA8`], always with [`PixelOrder::Bgra`], or `u8`.
+    fn copy_surface_data<T: Copy + Send + Sync + Sized>(
+        &self,
+        surface: &IDXGISurface1,
+        order: PixelOrder,
+    ) -> Result<(Vec<T>, (usize, usize)), CaptureError> {
+        let rotation = self
+            .duplicated_output
+            .as_ref()
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?
+            .rotation()?;
+        // The surface is in the native orientation of the display, while the
+        // desktop coordinates are rotated, so the surface gives the dimensions
+        // of the mapped data
+        let surface_desc = unsafe { surface.GetDesc()? };
+        let width = surface_desc.Width as usize;
+        let height = surface_desc.Height as usize;
+
+        let (mut data, dimensions) = Self::copy_mapped_surface::<T>(
+            surface,
+            width,
+            height,
+            rotation,
+            self.flip_vertical,
+            order,
+        )?;
+
+        if self.composite_cursor
+            && let Some(output) = &self.duplicated_output
+        {
+            let frame = unsafe {
+                slice::from_raw_parts_mut(
+                    data.as_mut_ptr() as *mut u8,
+                    data.len() * mem::size_of::<T>(),
+                )
+            };
+            output.composite_pointer(frame, dimensions.0, dimensions.1, order, self.flip_vertical);
+        }
+        apply_alpha(&mut data, self.force_opaque_alpha, self.premultiply_alpha);
+
+        Ok((data, dimensions))
+    }
+
+    /// Maps `surface` and copies its `width` x `height` pixels, applying
+    /// `rotation`, then a vertical flip if `flip` is set, and swapping
+    /// channels as needed to return them in `order`. `T` is [`BGRA8`],
+    /// `[u8; 4]` or `u8`.
+    fn copy_mapped_surface<T: Copy + Send + Sync + Sized>(
+        surface: &IDXGISurface1,
+        width: usize,
+        height: usize,
+        rotation: DXGI_MODE_ROTATION,
+        flip: bool,
+        order: PixelOrder,
+    ) -> Result<(Vec<T>, (usize, usize)), CaptureError> {
+        let format = unsafe { surface.GetDesc()? }.Format;
+        check_8bit_format(format)?;
+
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let (mut data, dimensions) =
+            unsafe { Self::copy_mapped_rect::<T>(&rect, width, height, rotation, flip) };
+
+        unsafe { surface.Unmap()? };
+
+        convert_pixel_order(&mut data, format, order);
+        Ok((data, dimensions))
+    }
+
+    /// Copies `width` x `height` pixels out of an already mapped surface into a
+    /// new vector, applying `rotation` and an optional vertical flip. `T` is
+    /// either [`BGRA8`] or `u8`.
+    ///
+    /// # Safety
+    ///
+    /// `rect` must describe a currently mapped surface of at least `height`
+    /// rows of `width` pixels.
+    unsafe fn copy_mapped_rect<T: Copy + Send + Sync + Sized>(
+        rect: &DXGI_MAPPED_RECT,
+        width: usize,
+        height: usize,
+        rotation: DXGI_MODE_ROTATION,
+        flip: bool,
+    ) -> (Vec<T>, (usize, usize)) {
+        let bytes_per_pixel = mem::size_of::<BGRA8>() / mem::size_of::<T>();
+        // Both pixel types are plain bytes, for which all zeroes is valid
+        let mut data_vec: Vec<T> = vec![unsafe { mem::zeroed() }; width * height * bytes_per_pixel];
+        let dimensions = unsafe {
+            Self::copy_mapped_rect_into(rect, width, height, rotation, flip, &mut data_vec)
+        };
+        (data_vec, dimensions)
+    }
+
+    /// Copies `width` x `height` pixels out of an already mapped surface into
+    /// `out`, applying `rotation` and then flipping vertically if `flip` is
+    /// set, and returns the rotated dimensions. `T` is either [`BGRA8`] or
+    /// `u8`.
+    ///
+    /// # Safety
+    ///
+    /// `rect` must describe a currently mapped surface of at least `height`
+    /// rows of `width` pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` holds fewer than `width * height` pixels.
+    unsafe fn copy_mapped_rect_into<T: Copy + Send + Sync + Sized>(
+        rect: &DXGI_MAPPED_RECT,
+        width: usize,
+        height: usize,
+        rotation: DXGI_MODE_ROTATION,
+        flip: bool,
+        out: &mut [T],
+    ) -> (usize, usize) {
+        let pitch = rect.Pitch as usize / mem::size_of::<T>();
+        let values_per_pixel = mem::size_of::<BGRA8>() / mem::size_of::<T>();
+        // Only borrow up to the end of the last pixel; the padding after the
+        // last row is not guaranteed to be part of the mapping
+        let len = match height {
+            0 => 0,
+            _ => pitch * (height - 1) + width * values_per_pixel,
+        };
+        let source = unsafe { slice::from_raw_parts(rect.pBits as *const T, len) };
+
+        rotate_pixels(
+            source,
+            pitch,
+            width,
+            height,
+            values_per_pixel,
+            Rotation::from_dxgi(rotation),
+            flip,
+            out,
+        )
+    }
+
+    // -----------------------------------------------------------------------
+    // Public capture methods
+    // -----------------------------------------------------------------------
+
+    /// Captures a single frame and returns it as a `Vec<BGRA8>`.
+    ///
+    /// This method captures the current screen content and returns it as a vector
+    /// of [`BGRA8`] pixels along with the frame dimensions. The method waits for
+    /// a new frame to become available, up to the configured timeout.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (width, height)))` where:
+    /// - `pixels` is a `Vec<BGRA8>` containing the pixel data
+    /// - `width` and `height` are the frame dimensions in pixels
+    /// - Pixels are stored in row-major order (left-to-right, top-to-bottom)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, CaptureError};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.capture_frame() {
+    ///     Ok((pixels, (width, height))) => {
+    ///         println!("Captured {}x{} frame with {} pixels", width, height, pixels.len());
+    ///     }
+    ///     Err(CaptureError::Timeout) => {
+    ///         // No new frame available within timeout
+    ///     }
+    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame(&mut self) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        self.copy_surface_data(&surface, PixelOrder::Bgra)
+    }
+
+    /// Captures a single frame like [`DXGIManager::capture_frame`], after
+    /// waiting for the next vertical blank of the display.
+    ///
+    /// The wait uses `IDXGIOutput::WaitForVBlank`, so the frame is acquired
+    /// right after the display finished scanning out, which reduces the chance
+    /// of capturing a partially updated frame on some drivers. This adds up to
+    /// one refresh interval of latency before the usual wait for a new frame,
+    /// which is why it is a separate method.
+    ///
+    /// `WaitForVBlank` only works while the output is not in a fullscreen
+    /// swap chain owned by another process, such as an exclusive fullscreen
+    /// game. If the wait fails, the frame is captured without it, and the
+    /// failure is logged at [`LogLevel::Debug`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let (pixels, (width, height)) = manager.capture_frame_vsync()?;
+    /// println!("Captured {}x{} frame after vblank", width, height);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_vsync(&mut self) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        if self.duplicated_output.is_none() {
+            self.acquire_output_duplication()
+                .map_err(map_refresh_error)?;
+        }
+        if let Some(output) = &self.duplicated_output
+            && let Err(e) = unsafe { output.output.WaitForVBlank() }
+        {
+            self.log(
+                LogLevel::Debug,
+                format_args!("WaitForVBlank failed, capturing without it: {e}"),
+            );
+        }
+        self.capture_frame()
+    }
+
+    /// Captures a frame like [`DXGIManager::capture_frame`] and reports how
+    /// long each stage took.
+    ///
+    /// The returned [`CaptureTimings`] separate waiting for the frame from
+    /// mapping the staging texture and copying its pixels, which tells whether
+    /// a slow pipeline is bound by the desktop, the GPU or the CPU copy. Use
+    /// [`DXGIManager::set_capture_timing`] to turn the measurements off.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (pixels, (width, height), timings) = manager.capture_frame_timed()?;
+    /// println!(
+    ///     "{width}x{height}: acquire {:?}, map {:?}, copy {:?}",
+    ///     timings.acquire, timings.map, timings.copy
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_timed(&mut self) -> CaptureFrameTimedResult {
+        let mut stopwatch = Stopwatch::new(self.capture_timing);
+        let (surface, _) = self.acquire_surface(None)?;
+        let acquire = stopwatch.lap();
+
+        let rotation = self.output_rotation()?;
+        let desc = unsafe { surface.GetDesc()? };
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+        let map = stopwatch.lap();
+
+        let (mut pixels, dimensions) = unsafe {
+            Self::copy_mapped_rect::<BGRA8>(
+                &rect,
+                desc.Width as usize,
+                desc.Height as usize,
+                rotation,
+                self.flip_vertical,
+            )
+        };
+        unsafe { surface.Unmap()? };
+        convert_pixel_order(&mut pixels, desc.Format, PixelOrder::Bgra);
+
+        if self.composite_cursor
+            && let Some(output) = &self.duplicated_output
+        {
+            let frame = unsafe {
+                slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, pixels.len() * 4)
+            };
+            output.composite_pointer(
+                frame,
+                dimensions.0,
+                dimensions.1,
+                PixelOrder::Bgra,
+                self.flip_vertical,
+            );
+        }
+        apply_alpha(&mut pixels, self.force_opaque_alpha, self.premultiply_alpha);
+        let copy = stopwatch.lap();
+
+        Ok((pixels, dimensions, CaptureTimings { acquire, map, copy }))
+    }
+
+    /// Captures a frame like [`DXGIManager::capture_frame`] and reports when
+    /// it was presented and how long acquiring it took.
+    ///
+    /// This is lighter than [`DXGIManager::capture_frame_with_metadata`], as
+    /// no rectangles or pointer state are queried. The present time is taken
+    /// from `LastPresentTime` and converted into an [`Instant`] using the
+    /// cached performance counter frequency, so encoders can measure how far
+    /// behind the display they run with `presented_at.elapsed()` and align
+    /// their schedule with the monitor's refresh cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (pixels, (width, height), timing) = manager.capture_frame_with_present_time()?;
+    /// if let Some(presented_at) = timing.presented_at {
+    ///     println!(
+    ///         "{width}x{height} presented {:?} ago, acquired in {:?}",
+    ///         presented_at.elapsed(),
+    ///         timing.acquire_latency
+    ///     );
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_with_present_time(&mut self) -> CaptureFramePresentTimeResult {
+        let start = Instant::now();
+        let (surface, metadata) = self.acquire_surface(Some(MetadataFlags::NONE))?;
+        let acquire_latency = start.elapsed();
+
+        let present_qpc = metadata.map_or(0, |metadata| metadata.last_present_time);
+        let timing = PresentTiming {
+            present_qpc,
+            presented_at: qpc_to_instant(present_qpc),
+            acquire_latency,
+        };
+
+        let (pixels, dimensions) = self.copy_surface_data(&surface, PixelOrder::Bgra)?;
+        Ok((pixels, dimensions, timing))
+    }
+
+    /// Captures whatever is currently on screen, even if nothing changed.
+    ///
+    /// [`DXGIManager::capture_frame`] waits for a new frame, so on a static
+    /// screen it times out no matter how often it is called. This first tries
+    /// to take a pending frame without waiting. If there is none, it duplicates
+    /// the output again: the first frame of a fresh duplication is the current
+    /// desktop image, delivered right away. That is retried a few times with
+    /// increasing timeouts, 400 ms in total, to ride out a busy driver. The
+    /// configured timeout is not used.
+    ///
+    /// Suited to a "take screenshot now" button; for continuous capture,
+    /// prefer [`DXGIManager::capture_frame`], which avoids re-duplicating.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Timeout`] if no image arrived after all retries,
+    /// [`CaptureError::RefreshFailure`] if the output cannot be duplicated
+    /// again, and the other [`CaptureError`] variants on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// // Returns immediately, even if the screen has not changed in hours
+    /// let (pixels, (width, height)) = manager.snapshot()?;
+    /// println!("Screenshot of {}x{}", width, height);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn snapshot(&mut self) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        const RETRY_TIMEOUTS_MS: [u32; 3] = [50, 100, 250];
+
+        match self.acquire_surface_with_timeout(None, 0) {
+            Err(CaptureError::Timeout) => {}
+            result => return self.copy_surface_data(&result?.0, PixelOrder::Bgra),
+        }
+        for timeout_ms in RETRY_TIMEOUTS_MS {
+            self.acquire_output_duplication()
+                .map_err(map_refresh_error)?;
+            match self.acquire_surface_with_timeout(None, timeout_ms) {
+                Err(CaptureError::Timeout) => continue,
+                result => return self.copy_surface_data(&result?.0, PixelOrder::Bgra),
+            }
+        }
+        Err(CaptureError::Timeout)
+    }
+
+    /// Captures a single frame and returns it as a `Vec<u8>`.
+    ///
+    /// This method captures the current screen content and returns it as a vector
+    /// of raw bytes representing the pixel components. Each pixel is represented
+    /// by 4 consecutive bytes in BGRA order, or in RGBA order if configured with
+    /// [`DXGIManager::set_output_order`].
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((components, (width, height)))` where:
+    /// - `components` is a `Vec<u8>` containing the raw pixel component data
+    /// - `width` and `height` are the frame dimensions in pixels
+    /// - Components are stored as [B, G, R, A, B, G, R, A, ...] in row-major order
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.capture_frame_components() {
+    ///     Ok((components, (width, height))) => {
+    ///         println!("Captured {}x{} frame with {} bytes", width, height, components.len());
+    ///     }
+    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_components(&mut self) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
+        self.capture_frame_components_in(self.output_order)
+    }
+
+    /// Captures a single frame and returns it as a `Vec<[u8; 4]>`.
+    ///
+    /// This returns the same bytes as [`DXGIManager::capture_frame_components`],
+    /// grouped into one array per pixel, for SIMD and array-based APIs that
+    /// take `&[[u8; 4]]` without a transmute on the caller's side. Like the
+    /// other component methods, each pixel is in BGRA order, or RGBA if
+    /// configured with [`DXGIManager::set_output_order`], and rotation is
+    /// applied.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let (quads, (width, height)) = manager.capture_frame_quads()?;
+    /// let opaque = quads.iter().filter(|[_, _, _, a]| *a == 255).count();
+    /// println!("{} of {} pixels are opaque", opaque, width * height);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_quads(&mut self) -> CaptureFrameQuadsResult {
+        let (surface, _) = self.acquire_surface(None)?;
+        self.copy_surface_data(&surface, self.output_order)
+    }
+
+    /// Captures a single frame with 16 bits per channel.
+    ///
+    /// Image processing such as blurring or scaling loses precision when done
+    /// in 8 bits. This returns one `[u16; 4]` per pixel, in BGRA order or RGBA
+    /// if configured with [`DXGIManager::set_output_order`], with rotation
+    /// applied. How channels are widened depends on the desktop format:
+    ///
+    /// - 8-bit formats are scaled to the full range, 255 becoming 65535.
+    /// - `DXGI_FORMAT_R10G10B10A2_UNORM` keeps all 10 bits of each channel.
+    /// - `DXGI_FORMAT_R16G16B16A16_FLOAT` holds linear scRGB values. They stay
+    ///   linear and saturate to `0.0..=1.0`, clipping highlights brighter than
+    ///   SDR white.
+    ///
+    /// Formats other than 8-bit BGRA are only used when requested with
+    /// [`DXGIManagerBuilder::duplication_formats`]. The pointer is only
+    /// composited into 8-bit frames.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Fail`] with `DXGI_ERROR_UNSUPPORTED` if the
+    /// desktop is in any other format.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let (pixels, (width, height)) = manager.capture_frame_u16()?;
+    /// let sum: u64 = pixels.iter().map(|[b, g, r, _]| (*b as u64 + *g as u64 + *r as u64) / 3).sum();
+    /// println!("Average level: {}", sum / (width * height) as u64);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_u16(&mut self) -> CaptureFrameU16Result {
+        let (surface, _) = self.acquire_surface(None)?;
+        let desc = unsafe { surface.GetDesc()? };
+        let (width, height) = (desc.Width as usize, desc.Height as usize);
+
+        let (mut pixels, dimensions): (Vec<[u16; 4]>, _) = match desc.Format {
+            DXGI_FORMAT_B8G8R8A8_TYPELESS
+            | DXGI_FORMAT_B8G8R8A8_UNORM
+            | DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+            | DXGI_FORMAT_R8G8B8A8_TYPELESS
+            | DXGI_FORMAT_R8G8B8A8_UNORM
+            | DXGI_FORMAT_R8G8B8A8_UNORM_SRGB => {
+                let (data, dimensions) =
+                    self.copy_surface_data::<[u8; 4]>(&surface, self.output_order)?;
+                let pixels = data.into_iter().map(widen::from_8bit).collect();
+                return Ok((pixels, dimensions));
+            }
+            DXGI_FORMAT_R10G10B10A2_TYPELESS | DXGI_FORMAT_R10G10B10A2_UNORM => {
+                let rotation = self.output_rotation()?;
+                let mut rect = DXGI_MAPPED_RECT::default();
+                unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+                // Copied as is, so the packed bits stay intact
+                let (data, dimensions) = unsafe {
+                    Self::copy_mapped_rect::<[u8; 4]>(
+                        &rect,
+                        width,
+                        height,
+                        rotation,
+                        self.flip_vertical,
+                    )
+                };
+
+                unsafe { surface.Unmap()? };
+                (
+                    data.into_iter().map(widen::from_10bit).collect(),
+                    dimensions,
+                )
+            }
+            DXGI_FORMAT_R16G16B16A16_FLOAT => {
+                let rotation = Rotation::from_dxgi(self.output_rotation()?);
+                let mut rect = DXGI_MAPPED_RECT::default();
+                unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+                let pitch = rect.Pitch as usize / mem::size_of::<[u16; 4]>();
+                let len = match height {
+                    0 => 0,
+                    _ => pitch * (height - 1) + width,
+                };
+                let source = unsafe { slice::from_raw_parts(rect.pBits as *const [u16; 4], len) };
+                let mut data = vec![[0u16; 4]; width * height];
+                let dimensions = rotate_pixels(
+                    source,
+                    pitch,
+                    width,
+                    height,
+                    1,
+                    rotation,
+                    self.flip_vertical,
+                    &mut data,
+                );
+
+                unsafe { surface.Unmap()? };
+                (data.into_iter().map(widen::from_fp16).collect(), dimensions)
+            }
+            _ => {
+                return Err(CaptureError::Fail(windows::core::Error::from(
+                    DXGI_ERROR_UNSUPPORTED,
+                )));
+            }
+        };
+
+        // The unpacked 10-bit and floating-point pixels are RGBA
+        if self.output_order == PixelOrder::Bgra {
+            pixels.iter_mut().for_each(|pixel| pixel.swap(0, 2));
+        }
+        if self.force_opaque_alpha {
+            pixels.iter_mut().for_each(|pixel| pixel[3] = u16::MAX);
+        } else if self.premultiply_alpha {
+            pixels.iter_mut().for_each(widen::premultiply);
+        }
+        Ok((pixels, dimensions))
+    }
+
+    /// Captures a single frame as bytes in `order`, regardless of the
+    /// configured output order.
+    fn capture_frame_components_in(
+        &mut self,
+        order: PixelOrder,
+    ) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        self.copy_surface_data(&surface, order)
+    }
+
+    /// Captures a single frame as bytes in `order` with rotation applied but
+    /// never flipped or with the pointer drawn, for the fallbacks of methods
+    /// that otherwise return the mapped surface as is.
+    fn capture_frame_upright_in(
+        &mut self,
+        order: PixelOrder,
+    ) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
+        let flip = mem::replace(&mut self.flip_vertical, false);
+        let cursor = mem::replace(&mut self.composite_cursor, false);
+        let result = self.capture_frame_components_in(order);
+        self.flip_vertical = flip;
+        self.composite_cursor = cursor;
+        result
+    }
+
+    /// Captures a single frame into a caller-provided buffer of BGRA bytes.
+    ///
+    /// This writes the same data as [`DXGIManager::capture_frame_components`],
+    /// including rotation correction, but never allocates, so frames can be
+    /// captured into preallocated slots such as those of a ring buffer. Only
+    /// the first `width * height * 4` bytes of `out` are written.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((width, height))`, the dimensions of the frame
+    /// written into `out`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Fail`] with `E_INVALIDARG` if `out` is smaller
+    /// than the frame, in which case nothing is written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (width, height) = manager.geometry();
+    /// let mut slots = vec![vec![0u8; width * height * 4]; 4];
+    ///
+    /// for slot in slots.iter_mut() {
+    ///     let (w, h) = manager.capture_frame_components_into_slice(slot)?;
+    ///     println!("Captured {}x{} frame", w, h);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_components_into_slice(
+        &mut self,
+        out: &mut [u8],
+    ) -> Result<(usize, usize), CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+
+        let Some(out) = out.get_mut(..width * height * 4) else {
+            return Err(CaptureError::Fail(windows::core::Error::from(E_INVALIDARG)));
+        };
+        self.copy_surface_data_into(&surface, out, self.output_order)
+    }
+
+    /// Captures a single frame into raw memory, such as a view of a named
+    /// shared memory section read by another process.
+    ///
+    /// This is [`DXGIManager::capture_frame_components_into_slice`] for memory
+    /// that cannot be borrowed as a slice: the frame is written as bytes in the
+    /// configured output order, starting at `dst`, without an intermediate
+    /// copy. Only the first `width * height * 4` bytes are written.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((width, height))`, the dimensions of the frame
+    /// written to `dst`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Fail`] with `E_INVALIDARG` if `dst` is null or
+    /// `capacity` is smaller than the frame, in which case nothing is written.
+    ///
+    /// # Safety
+    ///
+    /// - `dst` must be null or valid for writes of `capacity` bytes, all
+    ///   within a single allocation or mapped view, and `capacity` must not
+    ///   exceed `isize::MAX`.
+    /// - Nothing else may read or write that memory for the duration of the
+    ///   call, including other processes sharing the mapping. Hand frames over
+    ///   with a synchronization primitive such as a named event or mutex.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// # fn map_shared_view() -> (*mut u8, usize) { unimplemented!() }
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// // A view of a named section, from `OpenFileMappingW` and `MapViewOfFile`
+    /// let (view, size) = map_shared_view();
+    ///
+    /// // The reader process waits on an event before touching the view
+    /// let (width, height) = unsafe { manager.capture_frame_into_ptr(view, size)? };
+    /// println!("Shared a {}x{} frame", width, height);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub unsafe fn capture_frame_into_ptr(
+        &mut self,
+        dst: *mut u8,
+        capacity: usize,
+    ) -> Result<(usize, usize), CaptureError> {
+        if dst.is_null() {
+            return Err(CaptureError::Fail(windows::core::Error::from(E_INVALIDARG)));
+        }
+        let out = unsafe { slice::from_raw_parts_mut(dst, capacity) };
+        self.capture_frame_components_into_slice(out)
+    }
+
+    /// Captures a single frame and returns it as an `Arc<[BGRA8]>`.
+    ///
+    /// This returns the same pixels as [`DXGIManager::capture_frame`], but in a
+    /// shared allocation that can be handed to several consumers, e.g. over
+    /// channels, by cloning the `Arc` instead of the pixels. The frame is
+    /// copied straight into the `Arc`, so this costs no more than
+    /// [`DXGIManager::capture_frame`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use std::sync::mpsc;
+    /// use std::thread;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (encoder_tx, encoder_rx) = mpsc::channel();
+    /// let (preview_tx, preview_rx) = mpsc::channel();
+    /// thread::spawn(move || for (pixels, _) in encoder_rx { /* encode */ });
+    /// thread::spawn(move || for (pixels, _) in preview_rx { /* display */ });
+    ///
+    /// let (pixels, dimensions) = manager.capture_frame_shared()?;
+    /// encoder_tx.send((pixels.clone(), dimensions))?;
+    /// preview_tx.send((pixels, dimensions))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_shared(&mut self) -> CaptureFrameSharedResult {
+        let (surface, _) = self.acquire_surface(None)?;
+        let desc = unsafe { surface.GetDesc()? };
+        let len = desc.Width as usize * desc.Height as usize;
+
+        let black = BGRA8 {
+            b: 0,
+            g: 0,
+            r: 0,
+            a: 0,
+        };
+        // Collecting an exact-size iterator allocates the `Arc` only once
+        let mut pixels: Arc<[BGRA8]> = iter::repeat_n(black, len).collect();
+        let out = Arc::get_mut(&mut pixels).expect("A new Arc is unique");
+        let out = unsafe { slice::from_raw_parts_mut(out.as_mut_ptr() as *mut u8, len * 4) };
+        let dimensions = self.copy_surface_data_into(&surface, out, PixelOrder::Bgra)?;
+        Ok((pixels, dimensions))
+    }
+
+    /// Copies the pixels of `surface` into `out`, which must hold exactly
+    /// `width * height * 4` bytes, applying rotation, converting to `order`
+    /// and compositing the pointer if enabled.
+    fn copy_surface_data_into(
+        &mut self,
+        surface: &IDXGISurface1,
+        out: &mut [u8],
+        order: PixelOrder,
+    ) -> Result<(usize, usize), CaptureError> {
+        let rotation = self.output_rotation()?;
+        let desc = unsafe { surface.GetDesc()? };
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let dimensions = unsafe {
+            Self::copy_mapped_rect_into(
+                &rect,
+                desc.Width as usize,
+                desc.Height as usize,
+                rotation,
+                self.flip_vertical,
+                out,
+            )
+        };
+
+        unsafe { surface.Unmap()? };
+
+        convert_pixel_order(out, desc.Format, order);
+
+        if self.composite_cursor
+            && let Some(output) = &self.duplicated_output
+        {
+            output.composite_pointer(out, dimensions.0, dimensions.1, order, self.flip_vertical);
+        }
+        apply_alpha(out, self.force_opaque_alpha, self.premultiply_alpha);
+
+        Ok(dimensions)
+    }
+
+    /// Captures a single frame and returns it as a `Vec<BGRA8>` together with a
+    /// hash of its pixels.
+    ///
+    /// The hash is computed over the mapped surface in the same pass that reads
+    /// it, before the rotation-aware copy, so no extra pass over the returned
+    /// pixels is needed. Comparing it with the hash of the previous frame is a
+    /// cheap way to skip identical consecutive frames.
+    ///
+    /// The cursor is composited into the returned pixels as usual when
+    /// [`DXGIManager::set_composite_cursor`] is enabled, but only after hashing,
+    /// so the hash covers the desktop image alone and does not change when
+    /// just the pointer moves.
+    ///
+    /// The hash is fast but not cryptographic, and is only stable within the
+    /// same version of this crate, so it should not be persisted.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (width, height), hash))`, where `pixels`
+    /// and the dimensions are as returned by [`DXGIManager::capture_frame`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let mut last_hash = None;
+    ///
+    /// for _ in 0..10 {
+    ///     let (pixels, (width, height), hash) = manager.capture_frame_hashed()?;
+    ///     if last_hash == Some(hash) {
+    ///         continue; // Identical to the previous frame
+    ///     }
+    ///     last_hash = Some(hash);
+    ///     println!("New {}x{} frame", width, height);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_hashed(&mut self) -> CaptureFrameHashedResult {
+        let (surface, _) = self.acquire_surface(None)?;
+        let rotation = self.output_rotation()?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let pitch = rect.Pitch as usize;
+        let data = unsafe { slice::from_raw_parts(rect.pBits as *const u8, pitch * height) };
+        let hash = hash_rows(data, pitch, width * 4, height);
+        let (mut pixels, dimensions) = unsafe {
+            Self::copy_mapped_rect::<BGRA8>(&rect, width, height, rotation, self.flip_vertical)
+        };
+
+        unsafe { surface.Unmap()? };
+
+        convert_pixel_order(&mut pixels, desc.Format, PixelOrder::Bgra);
+
+        if self.composite_cursor
+            && let Some(output) = &self.duplicated_output
+        {
+            let frame = unsafe {
+                slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, pixels.len() * 4)
+            };
+            output.composite_pointer(
+                frame,
+                dimensions.0,
+                dimensions.1,
+                PixelOrder::Bgra,
+                self.flip_vertical,
+            );
+        }
+        apply_alpha(&mut pixels, self.force_opaque_alpha, self.premultiply_alpha);
+
+        Ok((pixels, dimensions, hash))
+    }
+
+    /// Captures the most recent frame, skipping any backlog, and returns it
+    /// with the number of frames skipped.
+    ///
+    /// The first frame is awaited with the configured timeout. After that,
+    /// frames are acquired without waiting for as long as new ones are
+    /// immediately available, up to `max_drain` of them. A frame's desktop
+    /// texture is only valid until it is released, which has to happen before
+    /// the next one can be acquired, so every drained frame is copied into the
+    /// staging texture on the GPU. Only the last one is read back to the CPU,
+    /// so a skipped frame costs a GPU copy but no CPU copy. This keeps latency
+    /// minimal for consumers that fall behind the display.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (width, height), skipped))`, where
+    /// `skipped` is the number of frames acquired and discarded in favor of the
+    /// returned one, at most `max_drain`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let (pixels, (width, height), skipped) = manager.capture_latest_frame(8)?;
+    /// if skipped > 0 {
+    ///     println!("Skipped {skipped} stale frames");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_latest_frame(&mut self, max_drain: usize) -> CaptureLatestFrameResult {
+        let (surface, skipped) = self.with_duplicated_output(|dup, timeout_ms| {
+            dup.drain_to_surface(timeout_ms, max_drain)
+        })?;
+        check_surface_size(&surface, self.max_frame_pixels)?;
+        let (pixels, dimensions) = self.copy_surface_data(&surface, PixelOrder::Bgra)?;
+        Ok((pixels, dimensions, skipped))
+    }
+
+    /// Captures a single frame and returns it as RGBA bytes in a `Vec<u8>`.
+    ///
+    /// This is equivalent to [`DXGIManager::capture_frame_components`] followed by
+    /// swapping the blue and red channels of every pixel, which is the layout most
+    /// image and GUI libraries expect. With the `simd` feature enabled, the swap
+    /// uses AVX2 or SSSE3 shuffles when the CPU supports them and falls back to a
+    /// scalar loop otherwise.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((components, (width, height)))` where:
+    /// - `components` is a `Vec<u8>` containing the raw pixel component data
+    /// - `width` and `height` are the frame dimensions in pixels
+    /// - Components are stored as [R, G, B, A, R, G, B, A, ...] in row-major order
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.capture_frame_rgba() {
+    ///     Ok((rgba, (width, height))) => {
+    ///         println!("Captured {}x{} RGBA frame with {} bytes", width, height, rgba.len());
+    ///     }
+    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_rgba(&mut self) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
+        self.capture_frame_components_in(PixelOrder::Rgba)
+    }
+
+    /// Captures the client area of a window from the current capture source.
+    ///
+    /// The window's client rectangle is located on screen with `GetClientRect`
+    /// and `ClientToScreen`, translated into the coordinate space of the
+    /// duplicated output, and cut out of a full-frame capture. Parts of the
+    /// client area that extend beyond the output are clipped. This captures
+    /// whatever is visible at that location, so overlapping windows appear in
+    /// the result rather than causing an error.
+    ///
+    /// The calling process should be per-monitor DPI aware; otherwise Windows
+    /// reports scaled window coordinates that do not match the captured pixels.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (width, height)))` with the pixels of the
+    /// visible client area in row-major order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::WindowNotOnOutput`] if the window is not mostly on
+    /// the display of the current capture source, and [`CaptureError::Fail`] if
+    /// the window handle is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let hwnd = unsafe { GetForegroundWindow() };
+    ///
+    /// let (pixels, (width, height)) = manager.capture_window_region(hwnd)?;
+    /// println!("Captured {}x{} window region", width, height);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_window_region(
+        &mut self,
+        hwnd: HWND,
+    ) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        let desc = self
+            .duplicated_output
+            .as_ref()
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?
+            .get_desc()?;
+        if unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONULL) } != desc.Monitor {
+            return Err(CaptureError::WindowNotOnOutput);
+        }
+
+        let mut client = RECT::default();
+        unsafe { GetClientRect(hwnd, &mut client)? };
+        let mut origin = POINT {
+            x: client.left,
+            y: client.top,
+        };
+        unsafe { ClientToScreen(hwnd, &mut origin).ok()? };
+
+        let (pixels, (width, height)) = self.capture_frame()?;
+
+        // Translate into output coordinates and clip to the captured frame
+        let desktop = desc.DesktopCoordinates;
+        let clip = |value: i32, max: usize| value.clamp(0, max as i32) as usize;
+        let left = clip(origin.x - desktop.left, width);
+        let top = clip(origin.y - desktop.top, height);
+        let right = clip(origin.x - desktop.left + client.right - client.left, width);
+        let bottom = clip(origin.y - desktop.top + client.bottom - client.top, height);
+
+        // A flipped frame holds the window's rows bottom-up at the other end
+        let rows = if self.flip_vertical {
+            height - bottom..height - top
+        } else {
+            top..bottom
+        };
+        let mut region = Vec::with_capacity((right - left) * (bottom - top));
+        for row in rows {
+            region.extend_from_slice(&pixels[row * width + left..row * width + right]);
+        }
+
+        Ok((region, (right - left, bottom - top)))
+    }
+
+    /// Captures a `side` x `side` square from the center of the display.
+    ///
+    /// Models for image classification and detection usually take a fixed
+    /// square input. Only the rows and columns of the square are copied out of
+    /// the mapped surface, so this is cheaper than cropping a full capture.
+    /// The square is centered on the frame as returned by
+    /// [`DXGIManager::capture_frame`], i.e. with rotation applied, rounding
+    /// its position towards the top left. `side` is clamped to the smaller
+    /// dimension of the frame.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (side, side)))` with the clamped side.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let (pixels, (side, _)) = manager.capture_center_crop(224)?;
+    /// let input: Vec<f32> = pixels
+    ///     .iter()
+    ///     .flat_map(|p| [p.r, p.g, p.b].map(|c| c as f32 / 255.0))
+    ///     .collect();
+    /// println!("{side}x{side} model input with {} values", input.len());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_center_crop(
+        &mut self,
+        side: usize,
+    ) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        let rotation = Rotation::from_dxgi(self.output_rotation()?);
+        let desc = unsafe { surface.GetDesc()? };
+        let (width, height) = (desc.Width as usize, desc.Height as usize);
+
+        // Crop origin in the rotated frame, and the same square in the surface
+        let (frame_width, frame_height) = match rotation {
+            Rotation::Rotate90 | Rotation::Rotate270 => (height, width),
+            Rotation::Identity | Rotation::Rotate180 | Rotation::Unspecified => (width, height),
+        };
+        let side = side.min(frame_width).min(frame_height);
+        let (x, y) = ((frame_width - side) / 2, (frame_height - side) / 2);
+        let (left, top) = match rotation {
+            Rotation::Identity | Rotation::Unspecified => (x, y),
+            Rotation::Rotate90 => (y, height - x - side),
+            Rotation::Rotate180 => (width - x - side, height - y - side),
+            Rotation::Rotate270 => (width - y - side, x),
+        };
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let pitch = rect.Pitch as usize / mem::size_of::<BGRA8>();
+        let len = match side {
+            0 => 0,
+            _ => pitch * (side - 1) + side,
+        };
+        let source = unsafe {
+            slice::from_raw_parts((rect.pBits as *const BGRA8).add(top * pitch + left), len)
+        };
+        let black = BGRA8 {
+            b: 0,
+            g: 0,
+            r: 0,
+            a: 0,
+        };
+        let mut pixels = vec![black; side * side];
+        rotate_pixels(source, pitch, side, side, 1, rotation, false, &mut pixels);
+
+        unsafe { surface.Unmap()? };
+
+        convert_pixel_order(&mut pixels, desc.Format, PixelOrder::Bgra);
+        if self.composite_cursor
+            && let Some(output) = &self.duplicated_output
+        {
+            let frame = unsafe {
+                slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, side * side * 4)
+            };
+            let origin = (x as i32, y as i32);
+            output.composite_pointer_at(frame, side, side, PixelOrder::Bgra, origin);
+        }
+        apply_alpha(&mut pixels, self.force_opaque_alpha, self.premultiply_alpha);
+
+        Ok((pixels, (side, side)))
+    }
+
+    /// Captures a single frame and returns its luminance as one byte per pixel.
+    ///
+    /// Each pixel is converted with the BT.601 weights `0.299 R + 0.587 G + 0.114 B`
+    /// (in 8-bit fixed point) directly from the mapped surface, so no full BGRA
+    /// copy is made. This needs a quarter of the memory of an RGBA capture, which
+    /// suits OCR and other grayscale pipelines. Rotation is applied as in
+    /// [`DXGIManager::capture_frame`].
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((luma, (width, height)))` where:
+    /// - `luma` is a `Vec<u8>` with `width * height` luminance values
+    /// - `width` and `height` are the frame dimensions in pixels
+    /// - Values are stored in row-major order (left-to-right, top-to-bottom)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.capture_frame_luma() {
+    ///     Ok((luma, (width, height))) => {
+    ///         let mean = luma.iter().map(|&v| v as u64).sum::<u64>() / luma.len() as u64;
+    ///         println!("Captured {}x{} grayscale frame, mean {}", width, height, mean);
+    ///     }
+    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_luma(&mut self) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
+        let (red, blue) = if is_rgba_format(self.output_format()?) {
+            (0, 2)
+        } else {
+            (2, 0)
+        };
+        let (luma, (width, height)) = self.capture_frame_with(|data, pitch, width, height| {
+            let mut luma = Vec::with_capacity(width * height);
+            for row in data.chunks(pitch).take(height) {
+                luma.extend(row[..width * 4].chunks_exact(4).map(|p| {
+                    let (b, g, r) = (p[blue] as u32, p[1] as u32, p[red] as u32);
+                    ((77 * r + 150 * g + 29 * b + 128) >> 8) as u8
+                }));
+            }
+            (luma, (width, height))
+        })?;
+        let rotation = self
+            .duplicated_output
+            .as_ref()
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?
+            .rotation()?;
+
+        let mut rotated = vec![0; luma.len()];
+        let dimensions = rotate_pixels(
+            &luma,
+            width,
+            width,
+            height,
+            1,
+            Rotation::from_dxgi(rotation),
+            false,
+            &mut rotated,
+        );
+        Ok((rotated, dimensions))
+    }
+
+    /// Captures a single frame and converts it to NV12 for video encoders.
+    ///
+    /// The conversion runs on the CPU, directly from the mapped surface when the
+    /// display is not rotated. It serves as a correct reference implementation
+    /// that needs no extra BGRA copy in the common case. Rotation is applied as
+    /// in [`DXGIManager::capture_frame`]. See [`Nv12Frame`] for the plane layout
+    /// and color space; both strides are 16-byte aligned for encoder consumption.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let frame = manager.capture_frame_nv12()?;
+    /// println!(
+    ///     "{}x{} NV12 frame, Y stride {}, UV stride {}",
+    ///     frame.width, frame.height, frame.y_stride, frame.uv_stride
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "nv12")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nv12")))]
+    pub fn capture_frame_nv12(&mut self) -> Result<Nv12Frame, CaptureError> {
+        if is_rgba_format(self.output_format()?) {
+            let (components, (width, height)) = self.capture_frame_upright_in(PixelOrder::Bgra)?;
+            return Ok(nv12::bgra_to_nv12(&components, width * 4, width, height));
+        }
+        match self.output_rotation()? {
+            DXGI_MODE_ROTATION_IDENTITY | DXGI_MODE_ROTATION_UNSPECIFIED => {
+                self.capture_frame_with(nv12::bgra_to_nv12)
+            }
+            _ => {
+                let (components, (width, height)) =
+                    self.capture_frame_upright_in(PixelOrder::Bgra)?;
+                Ok(nv12::bgra_to_nv12(&components, width * 4, width, height))
+            }
+        }
+    }
+
+    /// Captures a single frame with minimal overhead for performance-critical applications.
+    ///
+    /// This method provides the fastest possible screen capture by minimizing memory
+    /// allocations and copying. Returns raw pixel data without rotation handling.
+    ///
+    /// # Rotation
+    ///
+    /// **This method does not apply display rotation.** The pixels are returned in
+    /// the native orientation of the desktop image, which on a display rotated by
+    /// 90 or 270 degrees appears sideways, with `width` and `height` swapped
+    /// relative to [`DXGIManager::capture_frame`]. Use
+    /// [`DXGIManager::capture_frame_fast_rotated`] to get upright images on any
+    /// display, or check [`DXGIManager::current_mode`] to handle rotation yourself.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (width, height)))` where:
+    /// - `pixels` is a `Vec<u8>` containing raw BGRA pixel data
+    /// - `width` and `height` are the dimensions of the native desktop image in pixels
+    /// - Data is in the native orientation (no rotation correction)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(100)?;
+    ///
+    /// match manager.capture_frame_fast() {
+    ///     Ok((pixels, (width, height))) => {
+    ///         println!("Fast captured {}x{} frame", width, height);
+    ///     }
+    ///     Err(e) => eprintln!("Fast capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_fast(&mut self) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
+        let mut data = Vec::new();
+        let dimensions = self.capture_frame_fast_into(&mut data)?;
+        Ok((data, dimensions))
+    }
+
+    /// Captures a single frame with minimal overhead into a reusable buffer.
+    ///
+    /// This is the allocation-free counterpart of
+    /// [`DXGIManager::capture_frame_fast`] for tight capture loops: `buf` is
+    /// cleared and filled with the frame, reusing its capacity, so it only
+    /// reallocates when a frame is larger than any before. Afterwards `buf`
+    /// holds exactly `width * height * 4` bytes, also after a resolution
+    /// change. When the rows of the mapped surface are contiguous, the frame
+    /// is copied in a single pass. As with `capture_frame_fast`, display
+    /// rotation is not applied.
+    ///
+    /// If no frame is captured, e.g. on [`CaptureError::Timeout`], `buf` keeps
+    /// its previous contents. An error after the frame was mapped, such as a
+    /// failure to unmap it, may leave `buf` holding the new frame.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((width, height))`, the dimensions of the native
+    /// desktop image written into `buf`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(100)?;
+    /// let mut buf = Vec::new();
+    ///
+    /// for _ in 0..60 {
+    ///     let (width, height) = manager.capture_frame_fast_into(&mut buf)?;
+    ///     // Process width * height BGRA pixels in `buf`
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_fast_into(
+        &mut self,
+        buf: &mut Vec<u8>,
+    ) -> Result<(usize, usize), CaptureError> {
+        let format = self.output_format()?;
+        let order = self.output_order;
+        let (opaque, premultiplied) = (self.force_opaque_alpha, self.premultiply_alpha);
+        self.capture_frame_with(|source, pitch, width, height| {
+            let bytes_per_row = width * 4;
+            buf.clear();
+            if pitch == bytes_per_row {
+                buf.extend_from_slice(&source[..bytes_per_row * height]);
+            } else {
+                buf.reserve(bytes_per_row * height);
+                for row in source.chunks(pitch).take(height) {
+                    buf.extend_from_slice(&row[..bytes_per_row]);
+                }
+            }
+            convert_pixel_order(buf, format, order);
+            apply_alpha(buf, opaque, premultiplied);
+            (width, height)
+        })
+    }
+
+    /// Captures a single frame with minimal overhead, applying display rotation.
+    ///
+    /// On displays without rotation this is exactly [`DXGIManager::capture_frame_fast`].
+    /// On rotated displays it falls back to the rotation-aware copy of
+    /// [`DXGIManager::capture_frame_components`], so the result is always upright
+    /// and its dimensions match [`DXGIManager::capture_frame`].
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (width, height)))` where:
+    /// - `pixels` is a `Vec<u8>` containing raw BGRA pixel data
+    /// - `width` and `height` are the frame dimensions in pixels after rotation
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(100)?;
+    ///
+    /// let (pixels, (width, height)) = manager.capture_frame_fast_rotated()?;
+    /// println!("Fast captured upright {}x{} frame", width, height);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_fast_rotated(
+        &mut self,
+    ) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
+        match self.output_rotation()? {
+            DXGI_MODE_ROTATION_IDENTITY | DXGI_MODE_ROTATION_UNSPECIFIED => {
+                self.capture_frame_fast()
+            }
+            _ => self.capture_frame_upright_in(self.output_order),
+        }
+    }
+
+    /// Captures a single frame and returns it with its original row pitch.
+    ///
+    /// Unlike [`DXGIManager::capture_frame_fast`], which strips the padding at
+    /// the end of each row, this copies the mapped surface as is, so stride-aware
+    /// routines can process it directly. Consumers that want tightly packed data
+    /// can ignore the padding using [`RawFrame::pitch`]. Data is in the native
+    /// orientation of the display (no rotation correction).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let frame = manager.capture_frame_raw()?;
+    /// for row in frame.data.chunks(frame.pitch) {
+    ///     let pixels = &row[..frame.width * 4];
+    ///     // Process one row of BGRA pixels
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_raw(&mut self) -> Result<RawFrame, CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let pitch = rect.Pitch as usize;
+        let data = unsafe { slice::from_raw_parts(rect.pBits as *const u8, pitch * height) };
+        let data = data.to_vec();
+
+        unsafe { surface.Unmap()? };
+
+        Ok(RawFrame {
+            data,
+            pitch,
+            width,
+            height,
+            format: desc.Format,
+        })
+    }
+
+    /// Captures a single frame together with a [`FrameDescriptor`] of
+    /// everything an encoder needs to interpret it.
+    ///
+    /// Piecing this together from [`DXGIManager::geometry`],
+    /// [`DXGIManager::current_format`] and [`DXGIManager::color_space`] can
+    /// race with display mode changes between the calls. Here the dimensions,
+    /// pitch and format come from the description of the captured surface
+    /// itself, and the rotation and color space are read for the same output
+    /// right after the frame was acquired.
+    ///
+    /// The pixels are the mapped surface copied as is, like
+    /// [`DXGIManager::capture_frame_raw`]: `height` rows of `pitch` bytes in
+    /// the native orientation of the display and in `format`, without
+    /// rotation correction, pixel order conversion or pointer compositing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, Rotation, copy_rotated};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let (data, desc) = manager.capture_frame_described()?;
+    /// println!("{:?} frame in {:?}", desc.format, desc.color_space);
+    /// if desc.rotation != Rotation::Identity {
+    ///     let mut upright = Vec::new();
+    ///     copy_rotated(&data, desc.pitch, desc.width, desc.height, desc.rotation, &mut upright);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_described(&mut self) -> Result<(Vec<u8>, FrameDescriptor), CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        let rotation = Rotation::from_dxgi(self.output_rotation()?);
+        let color_space = self.color_space()?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let pitch = rect.Pitch as usize;
+        let data = unsafe { slice::from_raw_parts(rect.pBits as *const u8, pitch * height) };
+        let data = data.to_vec();
+
+        unsafe { surface.Unmap()? };
+
+        let descriptor = FrameDescriptor {
+            width,
+            height,
+            pitch,
+            format: desc.Format,
+            rotation,
+            color_space,
+        };
+        Ok((data, descriptor))
+    }
+
+    /// Captures a single frame and returns its surface together with the frame
+    /// info exactly as reported by `AcquireNextFrame`.
+    ///
+    /// This is an escape hatch for fields of [`DXGI_OUTDUPL_FRAME_INFO`] that the
+    /// crate does not map, such as `TotalMetadataBufferSize` or the raw
+    /// `PointerPosition`. The surface holds the desktop image in the native
+    /// orientation of the display and format of [`DXGIManager::current_format`],
+    /// ready to be mapped with `IDXGISurface::Map`. It stays valid until the
+    /// next capture. If the desktop texture can be mapped directly, the
+    /// surface is that texture and the frame stays acquired until the next
+    /// capture. Otherwise the surface is a staging copy, and the frame has
+    /// already been released unless [`DXGIManager::set_lazy_release`] is
+    /// enabled. The dirty and move rectangles are not returned either way; use
+    /// [`DXGIManager::capture_frame_with_metadata`] for those.
+    ///
+    /// Requires the `raw-dxgi` feature, as both returned types come from the
+    /// `windows` crate.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let (surface, frame_info) = manager.capture_frame_raw_info()?;
+    /// println!(
+    ///     "{} frames accumulated, {} bytes of metadata",
+    ///     frame_info.AccumulatedFrames, frame_info.TotalMetadataBufferSize
+    /// );
+    /// let desc = unsafe { surface.GetDesc()? };
+    /// println!("Surface is {}x{}", desc.Width, desc.Height);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "raw-dxgi")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "raw-dxgi")))]
+    pub fn capture_frame_raw_info(
+        &mut self,
+    ) -> Result<(IDXGISurface1, DXGI_OUTDUPL_FRAME_INFO), CaptureError> {
+        let (surface, frame_info) = self.with_duplicated_output(|dup, timeout_ms| {
+            dup.capture_frame_info_to_surface(timeout_ms)
+        })?;
+        check_surface_size(&surface, self.max_frame_pixels)?;
+        Ok((surface, frame_info))
+    }
+
+    /// Captures a single frame into a new GPU texture and returns an NT handle
+    /// to it, for zero-copy sharing with another Direct3D device, including
+    /// one in another process.
+    ///
+    /// The texture holds the desktop image in the native orientation of the
+    /// display and format of [`DXGIManager::current_format`], without the
+    /// pointer. It is never read back to the CPU. Every call creates a new
+    /// texture, so frames handed out earlier are never overwritten.
+    ///
+    /// Access is synchronized with a keyed mutex (`IDXGIKeyedMutex`), which is
+    /// released with key 1 once the copy is queued. Consumers open the texture
+    /// with `ID3D11Device1::OpenSharedResource1`, call `AcquireSync(1, ...)`
+    /// before reading it, which also waits for the copy to finish on the GPU,
+    /// and `ReleaseSync(0)` when done.
+    ///
+    /// # Handle ownership
+    ///
+    /// The returned handle belongs to the caller, who must close it with
+    /// `CloseHandle` once it is no longer needed; otherwise the texture is
+    /// leaked. Closing it does not affect devices that already opened the
+    /// texture, which keep it alive until they release it. The handle is only
+    /// valid in the calling process: pass it to another process by duplicating
+    /// it into that process with `DuplicateHandle` and sending the duplicated
+    /// value, then close the original.
+    ///
+    /// Requires the `raw-dxgi` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use windows::Win32::Foundation::CloseHandle;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let handle = manager.capture_frame_shared_handle()?;
+    /// // Duplicate `handle` into the consumer process and send it the new value
+    /// unsafe { CloseHandle(handle)? };
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "raw-dxgi")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "raw-dxgi")))]
+    pub fn capture_frame_shared_handle(&mut self) -> Result<HANDLE, CaptureError> {
+        self.with_duplicated_output(|dup, timeout_ms| {
+            dup.capture_frame_to_shared_handle(timeout_ms)
+        })
+    }
+
+    /// Captures a single frame and passes the mapped pixel data to a closure
+    /// without copying it.
+    ///
+    /// The closure receives the mapped surface as a byte slice along with the
+    /// row pitch, width and height. Rows are `pitch` bytes apart, of which the
+    /// first `width * 4` bytes hold BGRA pixels, so the slice is `pitch * height`
+    /// bytes long. Like [`DXGIManager::capture_frame_fast`], data is in the
+    /// native orientation of the display (no rotation correction). The pixels
+    /// are not converted, so on the rare outputs with an RGBA desktop image
+    /// (see [`DXGIManager::current_format`]) red and blue are swapped.
+    ///
+    /// The slice is only valid inside the closure: the surface is unmapped as
+    /// soon as the closure returns. Copy out whatever needs to outlive it.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok(result)` with the value returned by the closure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use std::hash::Hasher;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let hash = manager.capture_frame_with(|data, pitch, width, height| {
+    ///     let mut hasher = DefaultHasher::new();
+    ///     for row in 0..height {
+    ///         hasher.write(&data[row * pitch..row * pitch + width * 4]);
+    ///     }
+    ///     hasher.finish()
+    /// })?;
+    /// println!("Frame hash: {:016x}", hash);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_with<R>(
+        &mut self,
+        f: impl FnOnce(&[u8], usize, usize, usize) -> R,
+    ) -> Result<R, CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let pitch = rect.Pitch as usize;
+        let data = unsafe { slice::from_raw_parts(rect.pBits as *const u8, pitch * height) };
+        let result = f(data, pitch, width, height);
+
+        unsafe { surface.Unmap()? };
+
+        Ok(result)
+    }
+
+    /// Captures a single frame and passes the mapped pixels to a closure as
+    /// [`BGRA8`] values without copying them.
+    ///
+    /// This is the typed counterpart of [`DXGIManager::capture_frame_with`].
+    /// The closure receives the pixels along with the row stride, width and
+    /// height. Rows are `stride` pixels apart, of which the first `width` are
+    /// part of the image, so the slice is always `stride * height` pixels long,
+    /// where `stride` is the row pitch divided by 4. Data is in the native
+    /// orientation of the display (no rotation correction).
+    ///
+    /// On the rare outputs with an RGBA desktop image (see
+    /// [`DXGIManager::current_format`]) the pixels are converted to BGRA in a
+    /// copy with the same stride, so the channels are always correct.
+    ///
+    /// The slice is only valid inside the closure: the surface is unmapped as
+    /// soon as the closure returns.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let brightest = manager.capture_frame_view(|pixels, stride, width, height| {
+    ///     (0..height)
+    ///         .flat_map(|row| &pixels[row * stride..row * stride + width])
+    ///         .map(|pixel| pixel.r as u32 + pixel.g as u32 + pixel.b as u32)
+    ///         .max()
+    /// })?;
+    /// println!("Brightest pixel sum: {:?}", brightest);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_view<R>(
+        &mut self,
+        f: impl FnOnce(&[BGRA8], usize, usize, usize) -> R,
+    ) -> Result<R, CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        // The pitch of a 32-bit format is always a whole number of pixels
+        let stride = rect.Pitch as usize / mem::size_of::<BGRA8>();
+        let pixels = unsafe { slice::from_raw_parts(rect.pBits as *const BGRA8, stride * height) };
+        let result = if is_rgba_format(desc.Format) {
+            let mut converted = pixels.to_vec();
+            convert_pixel_order(&mut converted, desc.Format, PixelOrder::Bgra);
+            f(&converted, stride, width, height)
+        } else {
+            f(pixels, stride, width, height)
+        };
+
+        unsafe { surface.Unmap()? };
+
+        Ok(result)
+    }
+
+    /// Captures a rectangular region of a frame and passes the mapped pixel
+    /// data to a closure without copying it on the CPU.
+    ///
+    /// Neither `IDXGISurface1::Map` nor `ID3D11DeviceContext::Map` can map
+    /// part of a surface, on any GPU: a map always covers the whole texture.
+    /// What makes full-frame reads expensive on large displays, however, is
+    /// the GPU-to-CPU transfer of every pixel. This copies only `region` on
+    /// the GPU with `CopySubresourceRegion` into a staging texture of that
+    /// size and maps that, so only the region crosses the bus, which is much
+    /// faster when just a strip of a big display is needed.
+    ///
+    /// `region` is in the native orientation of the display, like the data
+    /// passed to [`DXGIManager::capture_frame_with`], and is clipped to the
+    /// desktop image. The closure receives the mapped bytes with the row pitch
+    /// and the size of the clipped region, exactly as the driver returned them
+    /// in `DXGI_MAPPED_RECT`: rows are `pitch` bytes apart, of which the first
+    /// `width * 4` bytes hold pixels in the format of
+    /// [`DXGIManager::current_format`], and the slice is `pitch * height`
+    /// bytes long. The pointer is not drawn.
+    ///
+    /// The slice is only valid inside the closure: the surface is unmapped as
+    /// soon as the closure returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Fail`] with `E_INVALIDARG` if `region` lies
+    /// entirely outside the desktop image, and the usual [`CaptureError`]
+    /// variants otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, Rect};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (width, _) = manager.output_geometry();
+    ///
+    /// // Read only the top 64 rows, e.g. a title bar strip
+    /// let region = Rect::new(0, 0, width as i32, 64);
+    /// let strip = manager.capture_region_with(region, |data, pitch, width, height| {
+    ///     (0..height)
+    ///         .flat_map(|row| &data[row * pitch..row * pitch + width * 4])
+    ///         .copied()
+    ///         .collect::<Vec<u8>>()
+    /// })?;
+    /// println!("Read {} bytes", strip.len());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_region_with<R>(
+        &mut self,
+        region: Rect,
+        f: impl FnOnce(&[u8], usize, usize, usize) -> R,
+    ) -> Result<R, CaptureError> {
+        if self.duplicated_output.is_none() {
+            self.acquire_output_duplication()
+                .map_err(map_refresh_error)?;
+        }
+        // Reject regions outside the image before a frame is acquired
+        let (width, height) = self.output_geometry();
+        let bounds = Rect::new(0, 0, width as i32, height as i32);
+        let region = region
+            .intersect(&bounds)
+            .ok_or(CaptureError::Fail(windows::core::Error::from(E_INVALIDARG)))?;
+
+        let surface = self.with_duplicated_output(|dup, timeout_ms| {
+            dup.capture_region_to_surface(timeout_ms, region)
+        })?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let pitch = rect.Pitch as usize;
+        let data = unsafe { slice::from_raw_parts(rect.pBits as *const u8, pitch * height) };
+        let result = f(data, pitch, width, height);
+
+        unsafe { surface.Unmap()? };
+
+        Ok(result)
+    }
+
+    /// Captures a single frame and passes the mapped pixel data to a closure for
+    /// reading and writing.
+    ///
+    /// This works like [`DXGIManager::capture_frame_with`], but the surface is
+    /// mapped for both reading and writing, so the closure can edit the frame
+    /// in place, e.g. to redact a region before reading it back. Writes only
+    /// affect the CPU-accessible staging copy of the frame, never the real
+    /// desktop, and are overwritten by the next capture.
+    ///
+    /// The slice is only valid inside the closure: the surface is unmapped as
+    /// soon as the closure returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Fail`] with `E_INVALIDARG` unless the manager was
+    /// built with [`DXGIManagerBuilder::cpu_write_access`] enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::builder().cpu_write_access(true).build()?;
+    ///
+    /// // Black out the top 100 rows, then copy the redacted frame
+    /// let redacted = manager.capture_frame_with_mut(|data, pitch, width, height| {
+    ///     for row in 0..height.min(100) {
+    ///         data[row * pitch..row * pitch + width * 4].fill(0);
+    ///     }
+    ///     data.to_vec()
+    /// })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_with_mut<R>(
+        &mut self,
+        f: impl FnOnce(&mut [u8], usize, usize, usize) -> R,
+    ) -> Result<R, CaptureError> {
+        if !self.cpu_write_access {
+            return Err(CaptureError::Fail(windows::core::Error::from(E_INVALIDARG)));
+        }
+
+        let (surface, _) = self.acquire_surface(None)?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ | DXGI_MAP_WRITE)? };
+
+        let pitch = rect.Pitch as usize;
+        let data = unsafe { slice::from_raw_parts_mut(rect.pBits, pitch * height) };
+        let result = f(data, pitch, width, height);
+
+        unsafe { surface.Unmap()? };
+
+        Ok(result)
+    }
+
+    /// Captures a single frame and returns it as a [`MappedFrame`], avoiding a
+    /// copy when the mapped surface is contiguous.
+    ///
+    /// If the surface pitch equals `width * 4`, the returned frame borrows the
+    /// mapped memory directly and keeps the surface mapped until it is dropped.
+    /// Otherwise the rows are copied into a buffer owned by the frame, exactly
+    /// like [`DXGIManager::capture_frame_fast`]. Either way the frame
+    /// dereferences to `width * height * 4` BGRA bytes in the native
+    /// orientation of the display (no rotation correction).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use std::hash::Hasher;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let frame = manager.capture_frame_borrowed()?;
+    /// let (width, height) = frame.dimensions();
+    /// let mut hasher = DefaultHasher::new();
+    /// hasher.write(&frame);
+    /// println!("{}x{} frame hash: {:016x}", width, height, hasher.finish());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_borrowed(&mut self) -> Result<MappedFrame<'_>, CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let pitch = rect.Pitch as usize;
+        let bytes_per_row = width * 4;
+        let mut frame = MappedFrame {
+            surface,
+            mapped: Some((rect.pBits as *const u8, bytes_per_row * height)),
+            buffer: Vec::new(),
+            dimensions: (width, height),
+            _manager: PhantomData,
+        };
+
+        // RGBA surfaces are copied too, so the frame can be converted to BGRA
+        if pitch != bytes_per_row || is_rgba_format(desc.Format) {
+            let source = unsafe { slice::from_raw_parts(rect.pBits as *const u8, pitch * height) };
+            frame.buffer.reserve_exact(bytes_per_row * height);
+            for row in source.chunks(pitch).take(height) {
+                frame.buffer.extend_from_slice(&row[..bytes_per_row]);
+            }
+            convert_pixel_order(&mut frame.buffer, desc.Format, PixelOrder::Bgra);
+            frame.mapped = None;
+            unsafe { frame.surface.Unmap()? };
+        }
+
+        Ok(frame)
+    }
+
+    /// Captures a single frame and returns an iterator over its rows, read
+    /// directly from the mapped surface.
+    ///
+    /// Each row is a `&[BGRA8]` of `width` pixels, skipping the padding at
+    /// the end of the surface rows, so scanline consumers such as JPEG
+    /// encoders can process the frame without it ever being collected into a
+    /// `Vec`. Rows are in the native scan order of the display (no rotation
+    /// correction); on rotated displays, use [`DXGIManager::capture_frame`]
+    /// for upright pixels. On the rare outputs with an RGBA desktop image (see
+    /// [`DXGIManager::current_format`]) the frame is converted to BGRA in an
+    /// internal buffer first.
+    ///
+    /// The rows borrow the manager, so they stay valid after the iterator is
+    /// dropped, until the manager is used again. The surface is unmapped when
+    /// the next frame is acquired.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let (rows, (width, height)) = manager.capture_frame_rows()?;
+    /// let mut scanline = Vec::with_capacity(width * 3);
+    /// for row in rows {
+    ///     scanline.clear();
+    ///     scanline.extend(row.iter().flat_map(|pixel| [pixel.r, pixel.g, pixel.b]));
+    ///     // Feed `scanline` to a JPEG encoder
+    /// }
+    /// println!("Streamed {width}x{height} frame");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_rows(&mut self) -> Result<(RowIter<'_>, (usize, usize)), CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        let desc = unsafe { surface.GetDesc()? };
+        let width = desc.Width as usize;
+        let height = desc.Height as usize;
+        let output = self
+            .duplicated_output
+            .as_mut()
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?;
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        // The pitch of a 32-bit format is always a whole number of pixels
+        let stride = rect.Pitch as usize / mem::size_of::<BGRA8>();
+        let data = if is_rgba_format(desc.Format) {
+            let pixels =
+                unsafe { slice::from_raw_parts(rect.pBits as *const BGRA8, stride * height) };
+            output.rows_buffer.clear();
+            output.rows_buffer.extend_from_slice(pixels);
+            convert_pixel_order(&mut output.rows_buffer, desc.Format, PixelOrder::Bgra);
+            unsafe { surface.Unmap()? };
+            output.rows_buffer.as_ptr()
+        } else {
+            // Unmapping waits for the next acquire, as rows may outlive the iterator
+            output.rows_surface = Some(surface);
+            rect.pBits as *const BGRA8
+        };
+
+        let rows = RowIter {
+            data,
+            stride,
+            width,
+            rows: 0..height,
+            _manager: PhantomData,
+        };
+        Ok((rows, (width, height)))
+    }
+
+    /// Captures a single frame and feeds it to `sink` row by row.
+    ///
+    /// This is the push-based counterpart of [`DXGIManager::capture_frame_rows`]
+    /// for libraries that take callbacks. Unlike the row iterator, rows are
+    /// upright: rotation correction and [`DXGIManager::set_flip_vertical`]
+    /// are applied as in [`DXGIManager::capture_frame_components`], and so are
+    /// the configured pixel order, alpha handling and pointer compositing.
+    /// Each row is assembled from the mapped surface into a single reused
+    /// buffer, so no full-frame buffer is ever allocated.
+    ///
+    /// The frame stays mapped while `sink` runs, so slow sinks delay the next
+    /// capture.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((width, height))`, the dimensions of the frame
+    /// fed to `sink`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, FrameSink};
+    ///
+    /// struct Luma(Vec<u8>);
+    ///
+    /// impl FrameSink for Luma {
+    ///     fn row(&mut self, _y: usize, row: &[u8]) {
+    ///         self.0.extend(row.chunks_exact(4).map(|pixel| pixel[1]));
+    ///     }
+    /// }
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let mut sink = Luma(Vec::new());
+    /// let (width, height) = manager.capture_frame_to_sink(&mut sink)?;
+    ///
+    /// // Closures work as sinks too
+    /// let mut rows = 0;
+    /// manager.capture_frame_to_sink(&mut |_y: usize, _row: &[u8]| rows += 1)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_to_sink(
+        &mut self,
+        sink: &mut impl FrameSink,
+    ) -> Result<(usize, usize), CaptureError> {
+        let (surface, _) = self.acquire_surface(None)?;
+        let rotation = Rotation::from_dxgi(self.output_rotation()?);
+        let desc = unsafe { surface.GetDesc()? };
+        let (width, height) = (desc.Width as usize, desc.Height as usize);
+        let (rotated_width, rotated_height) = match rotation {
+            Rotation::Rotate90 | Rotation::Rotate270 => (height, width),
+            Rotation::Identity | Rotation::Rotate180 | Rotation::Unspecified => (width, height),
+        };
+
+        check_8bit_format(desc.Format)?;
+        let mut rect = DXGI_MAPPED_RECT::default();
+        unsafe { surface.Map(&mut rect, DXGI_MAP_READ)? };
+
+        let pitch = rect.Pitch as usize;
+        // Only borrow up to the end of the last pixel, as the padding after
+        // the last row is not guaranteed to be part of the mapping
+        let len = match height {
+            0 => 0,
+            _ => pitch * (height - 1) + width * 4,
+        };
+        let source = unsafe { slice::from_raw_parts(rect.pBits as *const u8, len) };
+        let output = self.duplicated_output.as_ref();
+        let mut buffer = vec![0u8; rotated_width * 4];
+
+        for y in 0..rotated_height {
+            rotated_row(
+                source,
+                pitch,
+                (width, height),
+                rotation,
+                self.flip_vertical,
+                y,
+                &mut buffer,
+            );
+            convert_pixel_order(&mut buffer, desc.Format, self.output_order);
+            if self.composite_cursor
+                && let Some(output) = output
+            {
+                // The row sits at this height of the unflipped frame
+                let row = if self.flip_vertical {
+                    rotated_height - 1 - y
+                } else {
+                    y
+                };
+                output.composite_pointer_at(
+                    &mut buffer,
+                    rotated_width,
+                    1,
+                    self.output_order,
+                    (0, row as i32),
+                );
+            }
+            apply_alpha(&mut buffer, self.force_opaque_alpha, self.premultiply_alpha);
+            sink.row(y, &buffer);
+        }
+
+        unsafe { surface.Unmap()? };
+
+        Ok((rotated_width, rotated_height))
+    }
+
+    /// Captures a single frame and writes it to `w` row by row.
+    ///
+    /// The frame is written as tightly packed rows of the configured pixel
+    /// order, with rotation correction applied, exactly the bytes
+    /// [`DXGIManager::capture_frame_components`] would return, but streamed
+    /// through [`DXGIManager::capture_frame_to_sink`] without building a
+    /// `Vec`. This suits raw video pipes such as the stdin of an `ffmpeg`
+    /// process reading `-f rawvideo`. `w` receives one `write_all` per row and
+    /// is not flushed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Io`] if writing fails. Rows after the failed
+    /// write are not written, and the frame is still released.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use std::process::{Command, Stdio};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// let (width, height) = manager.geometry();
+    ///
+    /// let mut ffmpeg = Command::new("ffmpeg")
+    ///     .args(["-f", "rawvideo", "-pix_fmt", "bgra"])
+    ///     .args(["-s", &format!("{width}x{height}"), "-r", "30", "-i", "-"])
+    ///     .arg("desktop.mp4")
+    ///     .stdin(Stdio::piped())
+    ///     .spawn()?;
+    /// let mut stdin = ffmpeg.stdin.take().expect("stdin is piped");
+    ///
+    /// for _ in 0..300 {
+    ///     manager.capture_frame_write(&mut stdin)?;
+    /// }
+    /// drop(stdin);
+    /// ffmpeg.wait()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_write(
+        &mut self,
+        w: &mut impl io::Write,
+    ) -> Result<(usize, usize), CaptureError> {
+        let mut written = Ok(());
+        let dimensions = self.capture_frame_to_sink(&mut |_y: usize, row: &[u8]| {
+            if written.is_ok() {
+                written = w.write_all(row);
+            }
+        })?;
+        written.map_err(CaptureError::Io)?;
+        Ok(dimensions)
+    }
+
+    /// Acquires the next frame and holds it until the returned guard is dropped.
+    ///
+    /// The other capture methods acquire, copy and release a frame in one call.
+    /// This splits that lifecycle so lower-level consumers can inspect the
+    /// frame's metadata first and only pay for copying its pixels when needed.
+    /// The frame is released with `ReleaseFrame` when the [`FrameGuard`] is
+    /// dropped, so it cannot be forgotten.
+    ///
+    /// The guard borrows the manager mutably, so only one guard can exist at a
+    /// time and no other capture can happen while it is alive. Drop it
+    /// promptly: holding a frame stops DXGI from updating the desktop image.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`DXGIManager::capture_frame`]. With
+    /// [`DXGIManager::set_auto_recover`] enabled, the output duplication is
+    /// re-acquired on the next call rather than retried immediately.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let mut frame = manager.acquire_frame()?;
+    /// if frame.metadata()?.has_updates() {
+    ///     let (pixels, (width, height)) = frame.map()?;
+    ///     println!("Captured {}x{} frame", width, height);
+    /// }
+    /// // The frame is released here
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn acquire_frame(&mut self) -> Result<FrameGuard<'_>, CaptureError> {
+        if self.duplicated_output.is_none() {
+            self.acquire_output_duplication()
+                .map_err(map_refresh_error)?;
+        }
+
+        let timeout_ms = self.timeout_ms;
+        let result = self
+            .duplicated_output
+            .as_mut()
+            .unwrap()
+            .acquire_next_frame(timeout_ms);
+        let (texture, frame_info) = match result {
+            Ok(frame) => frame,
+            Err(e) => {
+                let err = map_capture_error(e);
+                // On non-timeout errors, drop the output so it is re-acquired.
+                if !matches!(err, CaptureError::Timeout) {
+                    self.duplicated_output = None;
+                }
+                let err = Err(err);
+                self.track_access_lost(&err);
+                return err;
+            }
+        };
+        self.access_lost_streak = 0;
+
+        Ok(FrameGuard {
+            output: self.duplicated_output.as_mut().unwrap(),
+            texture,
+            frame_info,
+            composite_cursor: self.composite_cursor,
+            output_order: self.output_order,
+            max_frame_pixels: self.max_frame_pixels,
+            force_opaque_alpha: self.force_opaque_alpha,
+            premultiply_alpha: self.premultiply_alpha,
+            flip_vertical: self.flip_vertical,
+        })
+    }
+
+    /// Captures a single frame downscaled on the GPU and returns it as a `Vec<BGRA8>`.
+    ///
+    /// The frame is reduced by generating a mip chain on the GPU, so only the
+    /// downscaled pixels are copied back to the CPU. This is considerably cheaper
+    /// than capturing at full resolution and resizing afterwards, especially for
+    /// thumbnails of high-resolution displays.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - The downscale factor in the range `(0, 1]`. It is snapped to the
+    ///   nearest power of two, so `0.5` yields half and `0.25` quarter resolution,
+    ///   while `0.3` also yields quarter resolution.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (width, height)))` where `width` and
+    /// `height` are the scaled dimensions. Each dimension is the full-resolution
+    /// size divided by the snapped factor and rounded down, but never less than 1.
+    /// Rotation is applied as in [`DXGIManager::capture_frame`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Fail`] with `E_INVALIDARG` if `scale` is not in
+    /// `(0, 1]`, and [`CaptureError::Fail`] if the device cannot generate mips for
+    /// the desktop format.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.capture_frame_scaled(0.25) {
+    ///     Ok((pixels, (width, height))) => {
+    ///         println!("Captured {}x{} thumbnail", width, height);
+    ///     }
+    ///     Err(e) => eprintln!("Scaled capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_scaled(
+        &mut self,
+        scale: f32,
+    ) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        let mip_level = mip_level_for_scale(scale)
+            .ok_or_else(|| CaptureError::Fail(windows::core::Error::from(E_INVALIDARG)))?;
+        if mip_level == 0 {
+            return self.capture_frame();
+        }
+
+        let (surface, (width, height)) = self.with_duplicated_output(|dup, timeout_ms| {
+            dup.capture_scaled_frame_to_surface(timeout_ms, mip_level)
+        })?;
+        let rotation = self
+            .duplicated_output
+            .as_ref()
+            .ok_or(CaptureError::RefreshFailure(
+                OutputDuplicationError::NoOutput,
+            ))?
+            .rotation()?;
+
+        let (mut pixels, dimensions) =
+            Self::copy_mapped_surface(&surface, width, height, rotation, false, PixelOrder::Bgra)?;
+        apply_alpha(&mut pixels, self.force_opaque_alpha, self.premultiply_alpha);
+        Ok((pixels, dimensions))
+    }
+
+    /// Captures a single frame and returns it as `Vec<BGRA8>` along with frame metadata.
+    ///
+    /// This method captures the current screen content and returns it as a vector
+    /// of [`BGRA8`] pixels along with comprehensive metadata about the frame, including
+    /// dirty rectangles, moved rectangles, and timing information.
+    ///
+    /// A frame is also returned when only the mouse cursor moved on an otherwise
+    /// static screen. In that case [`FrameMetadata::is_mouse_only_update`] is true,
+    /// the pixels are unchanged from the previous frame, and
+    /// [`FrameMetadata::pointer_position`] carries the new cursor position.
+    ///
+    /// When [`DXGIManager::set_min_change_fraction`] is set, frames with
+    /// smaller changes, including pointer-only updates, are released and the
+    /// wait continues until a frame exceeds the threshold or the configured
+    /// timeout passes.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (width, height), metadata))` where:
+    /// - `pixels` is a `Vec<BGRA8>` containing the pixel data
+    /// - `width` and `height` are the frame dimensions in pixels
+    /// - `metadata` is a [`FrameMetadata`] struct containing detailed frame information
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, CaptureError};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.capture_frame_with_metadata() {
+    ///     Ok((pixels, (width, height), metadata)) => {
+    ///         println!("Captured {}x{} frame with {} dirty rects and {} move rects",
+    ///                  width, height, metadata.dirty_rects.len(), metadata.move_rects.len());
+    ///     }
+    ///     Err(CaptureError::Timeout) => {
+    ///         // No new frame available within timeout
+    ///     }
+    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_with_metadata(&mut self) -> CaptureFrameWithMetadataResult {
+        if self.min_change_fraction <= 0.0 {
+            return self.capture_frame_with_metadata_opts(MetadataFlags::ALL);
+        }
+        let min_fraction = self.min_change_fraction;
+        let (surface, metadata) = self.with_duplicated_output(|dup, timeout_ms| {
+            let deadline = Instant::now() + Duration::from_millis(timeout_ms as u64);
+            dup.capture_changed_to_surface(deadline, min_fraction)
+        })?;
+        check_surface_size(&surface, self.max_frame_pixels)?;
+        let (data, dims) = self.copy_surface_data::<BGRA8>(&surface, PixelOrder::Bgra)?;
+        Ok((data, dims, metadata))
+    }
+
+    /// Captures a single frame with only the parts of its metadata selected by
+    /// `flags`.
+    ///
+    /// This behaves like [`DXGIManager::capture_frame_with_metadata`], but skips
+    /// the COM calls for the dirty or move rectangles unless they are
+    /// requested, which reduces the per-frame overhead when, for example, only
+    /// the pointer is of interest. See [`MetadataFlags`] for which fields are
+    /// always filled in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, MetadataFlags};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let (pixels, (width, height), metadata) =
+    ///     manager.capture_frame_with_metadata_opts(MetadataFlags::POINTER)?;
+    /// if let Some((x, y)) = metadata.pointer_position {
+    ///     println!("Pointer at {}, {} on a {}x{} frame", x, y, width, height);
+    /// }
+    /// assert!(metadata.dirty_rects.is_empty());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_with_metadata_opts(
+        &mut self,
+        flags: MetadataFlags,
+    ) -> CaptureFrameWithMetadataResult {
+        let (surface, metadata) = self.acquire_surface(Some(flags))?;
+        let (data, dims) = self.copy_surface_data::<BGRA8>(&surface, PixelOrder::Bgra)?;
+        Ok((data, dims, metadata.unwrap()))
+    }
+
+    /// Waits for a frame whose content changed and captures it with its metadata.
+    ///
+    /// Frames without dirty or move rectangles, such as pointer-only updates,
+    /// are released without copying and the wait continues, so this only
+    /// returns once [`FrameMetadata::has_updates`] is true. With
+    /// [`DXGIManager::set_min_change_fraction`], frames must also change more
+    /// than that fraction of the screen. No frame is held between iterations.
+    /// `timeout` is an overall deadline for the whole wait, not per frame; the
+    /// configured timeout is left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Timeout`] if no changed frame arrived within
+    /// `timeout`, and the other [`CaptureError`] variants on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{CaptureError, DXGIManager};
+    /// use std::time::Duration;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.capture_on_change(Duration::from_secs(10)) {
+    ///     Ok((pixels, (width, height), metadata)) => {
+    ///         let regions = metadata.total_change_count();
+    ///         println!("{} regions changed in a {}x{} frame", regions, width, height);
+    ///     }
+    ///     Err(CaptureError::Timeout) => println!("Nothing changed for 10 seconds"),
+    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_on_change(&mut self, timeout: Duration) -> CaptureFrameWithMetadataResult {
+        let deadline = Instant::now() + timeout.min(Duration::from_millis(u32::MAX as u64));
+        let min_fraction = self.min_change_fraction;
+        let (surface, metadata) = self.with_duplicated_output(|dup, _| {
+            dup.capture_changed_to_surface(deadline, min_fraction)
+        })?;
+        check_surface_size(&surface, self.max_frame_pixels)?;
+        let (data, dims) = self.copy_surface_data::<BGRA8>(&surface, PixelOrder::Bgra)?;
+        Ok((data, dims, metadata))
+    }
+
+    /// Captures the next frame as separate desktop and pointer layers.
+    ///
+    /// DXGI reports desktop and mouse updates independently, and frames are
+    /// delivered for either. This returns the desktop image only when it was
+    /// updated, so a mouse movement over a static desktop costs no pixel copy,
+    /// while [`Layers::cursor`] always carries the latest pointer state. A
+    /// streamer can thereby draw the pointer at full frame rate and encode the
+    /// desktop only when it changes.
+    ///
+    /// When the desktop and the pointer update in the same frame, both layers
+    /// are current: `desktop` holds the new image and `cursor` the new pointer
+    /// state. The pointer is never drawn into the desktop layer, regardless of
+    /// [`DXGIManager::set_composite_cursor`]. Waits up to the configured
+    /// timeout for the next frame.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{CaptureError, DXGIManager};
+    ///
+    /// let mut manager = DXGIManager::new(16)?;
+    ///
+    /// loop {
+    ///     match manager.capture_layers() {
+    ///         Ok(layers) => {
+    ///             if let Some((pixels, (width, height))) = layers.desktop {
+    ///                 // Upload the new desktop texture
+    ///             }
+    ///             if let Some((shape, (x, y))) = layers.cursor {
+    ///                 // Draw the pointer on top at (x, y)
+    ///             }
+    ///         }
+    ///         Err(CaptureError::Timeout) => continue,
+    ///         Err(e) => return Err(e.into()),
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_layers(&mut self) -> Result<Layers, CaptureError> {
+        let surface = self.with_duplicated_output(|dup, timeout_ms| {
+            dup.capture_desktop_layer_to_surface(timeout_ms)
+        })?;
+
+        let desktop = match surface {
+            Some(surface) => {
+                check_surface_size(&surface, self.max_frame_pixels)?;
+                let rotation = self.output_rotation()?;
+                let desc = unsafe { surface.GetDesc()? };
+                let (mut pixels, dimensions) = Self::copy_mapped_surface::<BGRA8>(
+                    &surface,
+                    desc.Width as usize,
+                    desc.Height as usize,
+                    rotation,
+                    false,
+                    PixelOrder::Bgra,
+                )?;
+                apply_alpha(&mut pixels, self.force_opaque_alpha, self.premultiply_alpha);
+                Some((pixels, dimensions))
+            }
+            None => None,
+        };
+        let cursor = self
+            .duplicated_output
+            .as_ref()
+            .and_then(DuplicatedOutput::pointer_layer);
+
+        Ok(Layers { desktop, cursor })
+    }
+
+    /// Acquires the next frame and returns only its metadata, without copying pixels.
+    ///
+    /// The frame is released before returning, so this is a cheap way to decide
+    /// whether a full capture is worthwhile, e.g. by checking
+    /// [`FrameMetadata::has_updates`]. Note that the peeked frame is consumed: a
+    /// following capture waits for the next frame rather than returning this one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{DXGIManager, CaptureError};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.peek_metadata() {
+    ///     Ok(metadata) if metadata.has_updates() => {
+    ///         println!("{} regions changed", metadata.total_change_count());
+    ///     }
+    ///     Ok(_) => println!("Nothing changed"),
+    ///     Err(CaptureError::Timeout) => println!("No new frame"),
+    ///     Err(e) => eprintln!("Peek failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn peek_metadata(&mut self) -> Result<FrameMetadata, CaptureError> {
+        self.with_duplicated_output(|dup, timeout_ms| {
+            dup.peek_frame_metadata(timeout_ms, MetadataFlags::ALL)
+        })
+    }
+
+    /// Blocks until the next frame arrives and reports whether the screen changed.
+    ///
+    /// The frame is acquired, inspected for dirty and move rectangles, and
+    /// released without copying any pixels. Unlike [`DXGIManager::peek_metadata`],
+    /// this waits for up to `timeout` instead of the configured timeout, which is
+    /// left untouched. Timeouts longer than `u32::MAX` milliseconds are clamped.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(FrameStatus::Changed(metadata))` if the frame reported changed regions
+    /// - `Ok(FrameStatus::Unchanged)` if a frame arrived without changed regions,
+    ///   e.g. because only the mouse pointer moved
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Timeout`] if DXGI delivered no frame at all within
+    /// `timeout`, and the other [`CaptureError`] variants on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{CaptureError, DXGIManager, FrameStatus};
+    /// use std::time::Duration;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.wait_for_frame(Duration::from_secs(5)) {
+    ///     Ok(FrameStatus::Changed(metadata)) => {
+    ///         println!("{} regions changed", metadata.total_change_count());
+    ///     }
+    ///     Ok(FrameStatus::Unchanged) => println!("Frame without changes"),
+    ///     Err(CaptureError::Timeout) => println!("No frame within 5 seconds"),
+    ///     Err(e) => eprintln!("Wait failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn wait_for_frame(&mut self, timeout: Duration) -> Result<FrameStatus, CaptureError> {
+        let timeout_ms = duration_to_timeout_ms(timeout);
+        let metadata = self.with_duplicated_output(|dup, _| {
+            dup.peek_frame_metadata(timeout_ms, MetadataFlags::ALL)
+        })?;
+
+        if metadata.has_updates() {
+            Ok(FrameStatus::Changed(metadata))
+        } else {
+            Ok(FrameStatus::Unchanged)
+        }
+    }
+
+    /// Returns the number of pixels that changed in the next frame, without
+    /// copying any pixels.
+    ///
+    /// The frame is acquired, its dirty and move rectangles are clipped to the
+    /// desktop image and their areas summed, and the frame is released. This is
+    /// a cheap activity signal, e.g. for idle detection. Overlapping rectangles
+    /// are counted once per rectangle, so the result is an upper bound capped
+    /// at the total number of pixels. When DXGI coalesced the rectangles or
+    /// provided none for an updated desktop, the total number of pixels is
+    /// returned, and frames with only a pointer update count as 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Timeout`] if no frame arrived within the
+    /// configured timeout, which also means nothing changed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{CaptureError, DXGIManager};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let changed = match manager.changed_area() {
+    ///     Ok(pixels) => pixels,
+    ///     Err(CaptureError::Timeout) => 0,
+    ///     Err(e) => return Err(e.into()),
+    /// };
+    /// if changed == 0 {
+    ///     println!("Screen is idle");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn changed_area(&mut self) -> Result<u64, CaptureError> {
+        self.with_duplicated_output(|dup, timeout_ms| {
+            let metadata = dup.peek_frame_metadata(timeout_ms, MetadataFlags::RECTS)?;
+            Ok(dup.changed_area(&metadata).0)
+        })
+    }
+
+    /// Captures a single frame and returns it as `Vec<u8>` along with frame metadata.
+    ///
+    /// This method captures the current screen content and returns it as a vector
+    /// of raw bytes representing the pixel components along with comprehensive
+    /// metadata about the frame.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((components, (width, height), metadata))` where:
+    /// - `components` is a `Vec<u8>` containing the raw pixel component data
+    /// - `width` and `height` are the frame dimensions in pixels
+    /// - `metadata` is a [`FrameMetadata`] struct containing detailed frame information
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// match manager.capture_frame_components_with_metadata() {
+    ///     Ok((components, (width, height), metadata)) => {
+    ///         println!("Captured {}x{} frame with {} bytes", width, height, components.len());
+    ///         if metadata.has_updates() {
+    ///             println!("Frame has {} total changes", metadata.total_change_count());
+    ///         }
+    ///     }
+    ///     Err(e) => eprintln!("Capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_frame_components_with_metadata(
+        &mut self,
+    ) -> CaptureFrameComponentsWithMetadataResult {
+        let (surface, metadata) = self.acquire_surface(Some(MetadataFlags::ALL))?;
+        let (data, dims) = self.copy_surface_data::<u8>(&surface, self.output_order)?;
+        Ok((data, dims, metadata.unwrap()))
+    }
+
+    // -----------------------------------------------------------------------
+    // Virtual desktop capture
+    // -----------------------------------------------------------------------
+
+    /// Sets the color used by [`DXGIManager::capture_virtual_desktop`] for areas
+    /// of the virtual desktop not covered by any display.
+    ///
+    /// Defaults to fully transparent black.
+    pub fn set_clear_color(&mut self, color: BGRA8) {
+        self.clear_color = color
+    }
+
+    /// Gets the color used for areas of the virtual desktop not covered by any display.
+    pub fn get_clear_color(&self) -> BGRA8 {
+        self.clear_color
+    }
+
+    /// Captures all displays stitched together into one virtual desktop image.
+    ///
+    /// Every desktop-attached output is duplicated and composited into a single
+    /// buffer covering the union of their desktop coordinates. Each display's
+    /// image is rotated to match its orientation before being placed. Areas not
+    /// covered by any display (e.g. in L-shaped layouts) are filled with the
+    /// color set by [`DXGIManager::set_clear_color`].
+    ///
+    /// The first call switches the manager into virtual desktop mode, releasing
+    /// the single-source duplication; the per-output duplications are kept for
+    /// subsequent calls. Any single-source capture or
+    /// [`DXGIManager::acquire_output_duplication`] switches back.
+    ///
+    /// Displays without a new frame reuse their last captured image. The
+    /// configured timeout is shared across all displays, and
+    /// [`CaptureError::Timeout`] is returned only if no display produced a new
+    /// frame.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns `Ok((pixels, (width, height)))` where `width` and
+    /// `height` span the whole virtual desktop, and the first pixel corresponds
+    /// to its top-left corner (which may have negative desktop coordinates).
+    ///
+    /// # Errors
+    ///
+    /// - [`CaptureError::RefreshFailure`] if the outputs cannot be duplicated
+    /// - [`CaptureError::AccessLost`] or other errors if capture fails on any
+    ///   display; the per-output duplications are then released
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::{BGRA8, DXGIManager};
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    /// manager.set_clear_color(BGRA8 { b: 0, g: 0, r: 0, a: 255 });
+    ///
+    /// match manager.capture_virtual_desktop() {
+    ///     Ok((pixels, (width, height))) => {
+    ///         println!("Captured {}x{} virtual desktop", width, height);
+    ///     }
+    ///     Err(e) => eprintln!("Virtual desktop capture failed: {:?}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_virtual_desktop(
+        &mut self,
+    ) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        if self.virtual_outputs.is_empty() {
+            self.acquire_virtual_outputs().map_err(map_refresh_error)?;
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(self.timeout_ms as u64);
+        let mut updated = false;
+        for virtual_output in &mut self.virtual_outputs {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match virtual_output.update(remaining.as_millis() as u32) {
+                Ok(true) => updated = true,
+                Ok(false) => {}
+                Err(e) => {
+                    self.virtual_outputs.clear();
+                    return Err(e);
+                }
+            }
+        }
+        if !updated {
+            return Err(CaptureError::Timeout);
+        }
+
+        let union = self.virtual_outputs.iter().fold(
+            self.virtual_outputs[0].desktop_rect,
+            |acc, output| RECT {
+                left: acc.left.min(output.desktop_rect.left),
+                top: acc.top.min(output.desktop_rect.top),
+                right: acc.right.max(output.desktop_rect.right),
+                bottom: acc.bottom.max(output.desktop_rect.bottom),
+            },
+        );
+        let width = (union.right - union.left) as usize;
+        let height = (union.bottom - union.top) as usize;
+        check_frame_size(width, height, self.max_frame_pixels)?;
+
+        let mut pixels = vec![self.clear_color; width * height];
+        for output in &self.virtual_outputs {
+            let (output_width, output_height) = output.dimensions;
+            let x = (output.desktop_rect.left - union.left) as usize;
+            let y = (output.desktop_rect.top - union.top) as usize;
+            let copy_width = output_width.min(width - x);
+            let copy_height = output_height.min(height - y);
+
+            for row in 0..copy_height {
+                let src = &output.pixels[row * output_width..row * output_width + copy_width];
+                let dst_start = (y + row) * width + x;
+                pixels[dst_start..dst_start + copy_width].copy_from_slice(src);
+            }
+        }
+
+        apply_alpha(&mut pixels, self.force_opaque_alpha, self.premultiply_alpha);
+        Ok((pixels, (width, height)))
+    }
+
+    /// Captures one frame from each desktop-attached output in turn.
+    ///
+    /// This suits dashboards showing every display as a tile without the
+    /// threads of [`DXGIManager::split`]. Outputs are captured in the order
+    /// and with the indices of [`DXGIManager::outputs`], leaving out mirrored
+    /// outputs if [`DXGIManager::set_skip_mirrored_outputs`] is enabled. Each
+    /// frame is rotation-corrected like [`DXGIManager::capture_frame`], and
+    /// the pointer is drawn into the frame of the output it is on if
+    /// [`DXGIManager::set_composite_cursor`] is enabled.
+    ///
+    /// The first call switches the manager into round-robin mode, releasing
+    /// the single-source duplication, since an output can only be duplicated
+    /// once per process. The per-output duplications are kept for subsequent
+    /// calls, so they are not re-created each round. Any single-source capture,
+    /// [`DXGIManager::capture_virtual_desktop`] or
+    /// [`DXGIManager::acquire_output_duplication`] switches back.
+    ///
+    /// Each round first takes the frames already pending on any output without
+    /// waiting, so a static output cannot use up the wait of the others. The
+    /// outputs that had none are then waited on in turn, sharing the
+    /// configured timeout, so a round takes at most that long; outputs without
+    /// a new frame in time report [`CaptureError::Timeout`]. If any output
+    /// fails otherwise, e.g. with [`CaptureError::AccessLost`] after a mode
+    /// change, all duplications are released and re-created on the next call.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::RefreshFailure`] if the outputs cannot be
+    /// duplicated. Errors of single outputs are reported in their entries.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// let mut manager = DXGIManager::new(100)?;
+    ///
+    /// for (index, result) in manager.capture_all_sources()? {
+    ///     match result {
+    ///         Ok((pixels, (width, height))) => println!("Output {index}: {width}x{height}"),
+    ///         Err(e) => println!("Output {index}: {e}"),
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_all_sources(&mut self) -> Result<Vec<SourceFrame>, CaptureError> {
+        if self.source_outputs.is_empty() {
+            self.acquire_source_outputs().map_err(map_refresh_error)?;
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(self.timeout_ms as u64);
+        let max_frame_pixels = self.max_frame_pixels;
+        let composite_cursor = self.composite_cursor;
+        let mut frames: Vec<SourceFrame> = self
+            .source_outputs
+            .iter_mut()
+            .map(|(index, duplicated_output)| {
+                let result = Self::capture_source_frame(
+                    duplicated_output,
+                    0,
+                    max_frame_pixels,
+                    composite_cursor,
+                );
+                (*index, result)
+            })
+            .collect();
+        for ((_, result), (_, duplicated_output)) in frames.iter_mut().zip(&mut self.source_outputs)
+        {
+            if matches!(result, Err(CaptureError::Timeout)) {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                *result = Self::capture_source_frame(
+                    duplicated_output,
+                    duration_to_timeout_ms(remaining),
+                    max_frame_pixels,
+                    composite_cursor,
+                );
+            }
+        }
+
+        let mut failed = false;
+        for (_, result) in &mut frames {
+            match result {
+                Ok((pixels, _)) => {
+                    apply_alpha(pixels, self.force_opaque_alpha, self.premultiply_alpha)
+                }
+                Err(CaptureError::Timeout) => {}
+                Err(_) => failed = true,
+            }
+        }
+        if failed {
+            self.source_outputs.clear();
+        }
+        Ok(frames)
+    }
+
+    /// Captures a rotation-corrected frame from one output of
+    /// [`DXGIManager::capture_all_sources`], drawing the pointer into it if
+    /// `composite_cursor` is set.
+    fn capture_source_frame(
+        duplicated_output: &mut DuplicatedOutput,
+        timeout_ms: u32,
+        max_frame_pixels: usize,
+        composite_cursor: bool,
+    ) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        let (surface, _) = duplicated_output
+            .capture_frame_to_surface(timeout_ms, None)
+            .map_err(map_capture_error)?;
+        check_surface_size(&surface, max_frame_pixels)?;
+
+        let surface_desc = unsafe { surface.GetDesc()? };
+        let rotation = duplicated_output.rotation()?;
+        let (mut pixels, dimensions) = Self::copy_mapped_surface(
+            &surface,
+            surface_desc.Width as usize,
+            surface_desc.Height as usize,
+            rotation,
+            false,
+            PixelOrder::Bgra,
+        )?;
+
+        if composite_cursor {
+            let frame = unsafe {
+                slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, pixels.len() * 4)
+            };
+            duplicated_output.composite_pointer(
+                frame,
+                dimensions.0,
+                dimensions.1,
+                PixelOrder::Bgra,
+                false,
+            );
+        }
+        Ok((pixels, dimensions))
+    }
+
+    // -----------------------------------------------------------------------
+    // Per-output capture
+    // -----------------------------------------------------------------------
+
+    /// Splits the manager into one independent [`OutputCapturer`] per output.
+    ///
+    /// Every desktop-attached output on every adapter is duplicated with its
+    /// own D3D11 device, in adapter order and then output order, which also
+    /// determines [`OutputCapturer::index`] and matches
+    /// [`DXGIManager::outputs`]. Mirrored outputs are left out if
+    /// [`DXGIManager::set_skip_mirrored_outputs`] is enabled. The capturers
+    /// inherit the configured timeout and frame size limit. Unlike
+    /// [`DXGIManager::set_capture_source_index`], no re-acquisition is needed
+    /// to switch between displays, and the capturers can be moved to separate
+    /// threads to capture concurrently.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutputDuplicationError::NoOutput`] if no output is attached to
+    /// the desktop, or [`OutputDuplicationError::DeviceError`] if any output
+    /// cannot be duplicated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use std::thread;
+    ///
+    /// let manager = DXGIManager::new(1000)?;
+    ///
+    /// let threads: Vec<_> = manager
+    ///     .split()?
+    ///     .into_iter()
+    ///     .map(|mut capturer| {
+    ///         thread::spawn(move || {
+    ///             if let Ok((_, (width, height))) = capturer.capture_frame() {
+    ///                 println!("Output {}: {}x{}", capturer.index(), width, height);
+    ///             }
+    ///         })
+    ///     })
+    ///     .collect();
+    ///
+    /// for thread in threads {
+    ///     thread.join().unwrap();
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn split(mut self) -> Result<Vec<OutputCapturer>, OutputDuplicationError> {
+        // An output can only be duplicated once per process, so release ours first
+        self.duplicated_output = None;
+        self.virtual_outputs.clear();
+        self.source_outputs.clear();
+
+        let mut capturers = Vec::new();
+        let mut desktop_rects = Vec::new();
+        for i in 0.. {
+            let adapter = match unsafe { self.factory.EnumAdapters1(i) } {
+                Ok(adapter) => adapter,
+                Err(e) if e.code() == DXGI_ERROR_NOT_FOUND => break,
+                Err(e) => return Err(e.into()),
+            };
+
+            for output in get_adapter_outputs(&adapter)? {
+                let desktop_rect = unsafe { output.GetDesc()? }.DesktopCoordinates;
+                let index = desktop_rects.len();
+                let mirrored = desktop_rects.contains(&desktop_rect);
+                desktop_rects.push(desktop_rect);
+                if mirrored && self.skip_mirrored_outputs {
+                    continue;
+                }
+
+                let duplicated_output =
+                    DuplicatedOutput::new(&adapter, output.clone(), false, &[])?;
+                capturers.push(OutputCapturer {
+                    adapter: adapter.clone(),
+                    output,
+                    duplicated_output: Some(duplicated_output),
+                    index,
+                    timeout_ms: self.timeout_ms,
+                    max_frame_pixels: self.max_frame_pixels,
+                });
+            }
+        }
+
+        if capturers.is_empty() {
+            Err(OutputDuplicationError::NoOutput)
+        } else {
+            Ok(capturers)
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Capture loop
+    // -----------------------------------------------------------------------
+
+    /// Runs a capture loop on a dedicated thread, invoking `on_frame` for each
+    /// captured frame.
+    ///
+    /// The manager is moved onto the new thread, which repeatedly captures frames
+    /// with metadata. Timeouts are skipped silently. The loop ends when
+    /// `on_frame` returns [`ControlFlow::Break`], when [`CaptureLoop::stop`] is
+    /// called, or when a capture fails with any error other than
+    /// [`CaptureError::Timeout`].
+    ///
+    /// The stop signal is checked between captures, so stopping takes at most
+    /// the configured timeout to be observed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use std::ops::ControlFlow;
+    ///
+    /// let manager = DXGIManager::new(100)?;
+    /// let mut frames = 0;
+    ///
+    /// let capture = manager.run(move |pixels, (width, height), metadata| {
+    ///     frames += 1;
+    ///     println!("Frame {}: {}x{}, {} changes", frames, width, height, metadata.total_change_count());
+    ///     if frames == 60 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    /// });
+    ///
+    /// // Stop early from another thread if needed
+    /// // capture.stop();
+    /// capture.join().expect("capture thread panicked")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn run<F>(mut self, mut on_frame: F) -> CaptureLoop
+    where
+        F: FnMut(&[BGRA8], (usize, usize), &FrameMetadata) -> ControlFlow<()> + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let thread = thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                match self.capture_frame_with_metadata() {
+                    Ok((pixels, dimensions, metadata)) => {
+                        if on_frame(&pixels, dimensions, &metadata).is_break() {
+                            break;
+                        }
+                    }
+                    Err(CaptureError::Timeout) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        });
+
+        CaptureLoop { stop, thread }
+    }
+
+    // -----------------------------------------------------------------------
+    // Recording
+    // -----------------------------------------------------------------------
+
+    /// Records the capture source to a YUV4MPEG2 (`.y4m`) video file at `path`
+    /// for `duration`, at a constant `fps` frames per second.
+    ///
+    /// The manager owns the capture loop and its timing: the first frame is
+    /// captured with the configured timeout, then one frame slot is scheduled
+    /// every `1 / fps` seconds from that point. Each slot polls for a new
+    /// desktop image without waiting; when the desktop has not changed, the
+    /// previous image is written again. Slots that come due while the
+    /// recorder is still behind schedule are dropped and also filled with the
+    /// previous image, so the file always plays back for `duration`.
+    ///
+    /// Frames are converted to 4:2:0 YUV with BT.709 coefficients in limited
+    /// range, as by [`DXGIManager::capture_frame_nv12`]. The file is
+    /// uncompressed, so it grows by `width * height * 3 / 2` bytes per frame.
+    /// At least one frame is always written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written, if `fps` is 0, or if
+    /// capturing fails with anything but a timeout, converted as described
+    /// for `From<CaptureError> for io::Error`. A change of the display
+    /// resolution during recording fails with [`io::ErrorKind::InvalidData`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    /// use std::time::Duration;
+    ///
+    /// let mut manager = DXGIManager::new(1000)?;
+    ///
+    /// let stats = manager.record_to("desktop.y4m", 30, Duration::from_secs(10))?;
+    /// println!(
+    ///     "Wrote {} frames, {} unchanged, {} dropped",
+    ///     stats.frames_written, stats.frames_repeated, stats.frames_dropped
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "record")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "record")))]
+    pub fn record_to(
+        &mut self,
+        path: impl AsRef<Path>,
+        fps: u32,
+        duration: Duration,
+    ) -> io::Result<RecordStats> {
+        if fps == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fps must be greater than zero",
+            ));
+        }
+        let slots = ((duration.as_secs_f64() * fps as f64).round() as u64).max(1);
+
+        let mut frame = self.capture_frame_nv12()?;
+        let file = BufWriter::new(File::create(path)?);
+        let mut writer = record::Y4mWriter::new(file, frame.width, frame.height, fps)?;
+        let start = Instant::now();
+        writer.write_frame(&frame)?;
+
+        let mut stats = RecordStats {
+            frames_written: 1,
+            ..RecordStats::default()
+        };
+
+        // Later slots only poll for a new image, so zero the timeout for now
+        let timeout_ms = mem::replace(&mut self.timeout_ms, 0);
+        let mut result = Ok(());
+        for slot in 1..slots {
+            let deadline = start + Duration::from_secs_f64(slot as f64 / fps as f64);
+            let next_deadline = start + Duration::from_secs_f64((slot + 1) as f64 / fps as f64);
+
+            let now = Instant::now();
+            if now >= next_deadline {
+                stats.frames_dropped += 1;
+            } else {
+                thread::sleep(deadline.saturating_duration_since(now));
+                match self.capture_frame_nv12() {
+                    Ok(next) => frame = next,
+                    Err(CaptureError::Timeout) => stats.frames_repeated += 1,
+                    Err(e) => {
+                        result = Err(e.into());
+                        break;
+                    }
+                }
+            }
+
+            if let Err(e) = writer.write_frame(&frame) {
+                result = Err(e);
+                break;
+            }
+            stats.frames_written += 1;
+        }
+        self.timeout_ms = timeout_ms;
+
+        result?;
+        writer.finish()?;
+        Ok(stats)
+    }
+
+    // -----------------------------------------------------------------------
+    // Async capture
+    // -----------------------------------------------------------------------
+
+    /// Captures a single frame without blocking the async executor.
+    ///
+    /// DXGI has no asynchronous frame notification, so the returned future
+    /// polls for a frame with a zero timeout and, while none is available,
+    /// schedules itself to be polled again about every millisecond on a shared
+    /// background timer thread. No poll ever blocks, so the future is safe to
+    /// await on any executor, including single-threaded ones. It resolves to
+    /// [`CaptureError::Timeout`] once the configured timeout has elapsed
+    /// without a new frame, matching [`DXGIManager::capture_frame`].
+    ///
+    /// # Thread Affinity
+    ///
+    /// The D3D11 device context used for capturing is not thread-safe, which is
+    /// why the future borrows the manager mutably. The manager is `Send`, so
+    /// the future can be polled from any executor thread, but it cannot be
+    /// shared: to capture concurrently, use one manager per display. Dropping
+    /// the future before it completes is safe, as every poll releases the frame
+    /// it acquired.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dxgi_capture_rs::DXGIManager;
+    ///
+    /// async fn capture(manager: &mut DXGIManager) -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (pixels, (width, height)) = manager.capture_frame_async().await?;
+    ///     println!("Captured {}x{} frame", width, height);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub fn capture_frame_async(
+        &mut self,
+    ) -> impl Future<Output = Result<(Vec<BGRA8>, (usize, usize)), CaptureError>> + Send + '_ {
+        const POLL_INTERVAL: Duration = Duration::from_millis(1);
+        let deadline = Instant::now() + Duration::from_millis(self.timeout_ms as u64);
+
+        std::future::poll_fn(move |cx| match self.acquire_surface_with_timeout(None, 0) {
+            Ok((surface, _)) => Poll::Ready(self.copy_surface_data(&surface, PixelOrder::Bgra)),
+            Err(CaptureError::Timeout) if Instant::now() < deadline => {
+                timer::wake_at(Instant::now() + POLL_INTERVAL, cx.waker().clone());
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        })
+    }
+}
+
+/// A handle to a capture loop started with [`DXGIManager::run`].
+///
+/// Dropping the handle detaches the loop; it keeps running until the callback
+/// breaks or a capture error occurs.
+pub struct CaptureLoop {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Result<(), CaptureError>>,
+}
+
+impl CaptureLoop {
+    /// Signals the capture loop to stop after the current capture completes
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the capture thread has finished
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the capture loop to finish and returns its result.
+    ///
+    /// The outer `Err` is returned if the callback panicked; the inner result
+    /// carries the capture error that ended the loop, if any.
+    pub fn join(self) -> thread::Result<Result<(), CaptureError>> {
+        self.thread.join()
+    }
+
+    /// Returns the underlying thread's join handle, detaching the stop signal
+    pub fn into_join_handle(self) -> JoinHandle<Result<(), CaptureError>> {
+        self.thread
+    }
+}
+
+type RecoverFn = dyn FnMut(u32, &CaptureError) + Send;
+
+/// A resilient capture loop that recreates its [`DXGIManager`] when access to
+/// the output is lost.
+///
+/// [`CaptureSession::next_frame`] captures like [`DXGIManager::capture_frame`],
+/// but on [`CaptureError::AccessLost`], [`CaptureError::DeviceLost`],
+/// [`CaptureError::AccessDenied`] or [`CaptureError::RefreshFailure`] it
+/// builds a new manager from the builder the session was created with and
+/// tries again. Attempts are spaced with exponential backoff, starting at
+/// 100 ms and doubling up to 5 s, and give up after 10 attempts by default.
+/// Timeouts and other errors are returned as is.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use dxgi_capture_rs::{CaptureError, CaptureSession, DXGIManager};
+///
+/// let mut session = CaptureSession::new(DXGIManager::builder().timeout_ms(100))?;
+/// session.on_recover(|attempts, err| {
+///     eprintln!("Recovered from {err} after {attempts} attempts");
+/// });
+///
+/// loop {
+///     match session.next_frame() {
+///         Ok((pixels, (width, height))) => { /* encode */ }
+///         Err(CaptureError::Timeout) => continue,
+///         Err(e) => return Err(e.into()),
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct CaptureSession {
+    manager: DXGIManager,
+    builder: DXGIManagerBuilder,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_attempts: u32,
+    on_recover: Option<Box<RecoverFn>>,
+}
+
+impl CaptureSession {
+    /// Creates a session whose manager, and every replacement of it, is built
+    /// from `builder`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the initial manager cannot be built.
+    pub fn new(builder: DXGIManagerBuilder) -> Result<Self, OutputDuplicationError> {
+        Ok(Self {
+            manager: builder.clone().build()?,
+            builder,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            max_attempts: 10,
+            on_recover: None,
+        })
+    }
+
+    /// Sets the delay before the first recovery attempt and the cap it
+    /// doubles up to on each further attempt
+    pub fn set_backoff(&mut self, initial: Duration, max: Duration) {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+    }
+
+    /// Returns the initial and maximum recovery delays
+    pub fn get_backoff(&self) -> (Duration, Duration) {
+        (self.initial_backoff, self.max_backoff)
+    }
+
+    /// Sets how many recovery attempts a single call to
+    /// [`CaptureSession::next_frame`] makes before returning the error
+    pub fn set_max_attempts(&mut self, attempts: u32) {
+        self.max_attempts = attempts;
+    }
+
+    /// Returns how many recovery attempts are made per capture
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Sets a callback invoked after the manager was recreated, with the
+    /// number of attempts it took and the error that triggered the recovery.
+    ///
+    /// Display changes such as a new resolution or a monitor being unplugged
+    /// cause recoveries, so this is the place to log them or to re-read the
+    /// geometry with [`CaptureSession::manager`].
+    pub fn on_recover(&mut self, callback: impl FnMut(u32, &CaptureError) + Send + 'static) {
+        self.on_recover = Some(Box::new(callback));
+    }
+
+    /// Returns the current manager
+    pub fn manager(&self) -> &DXGIManager {
+        &self.manager
+    }
+
+    /// Returns the current manager mutably, e.g. to use other capture methods.
+    /// Settings changed on it are lost when it is recreated; configure the
+    /// builder passed to [`CaptureSession::new`] instead.
+    pub fn manager_mut(&mut self) -> &mut DXGIManager {
+        &mut self.manager
+    }
+
+    /// Captures the next frame, recreating the manager with backoff if access
+    /// to the output was lost.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Timeout`] as usual, the last recoverable error
+    /// once all attempts are used up, and other errors right away.
+    pub fn next_frame(&mut self) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        let mut attempts = 0;
+        let mut delay = self.initial_backoff;
+        loop {
+            let err = match self.manager.capture_frame() {
+                Err(
+                    err @ (CaptureError::AccessLost
+                    | CaptureError::DeviceLost
+                    | CaptureError::AccessDenied
+                    | CaptureError::RefreshFailure(_)),
+                ) => err,
+                result => return result,
+            };
+
+            // Rebuild until a manager is created or the attempts run out
+            loop {
+                if attempts >= self.max_attempts {
+                    return Err(err);
+                }
+                attempts += 1;
+                thread::sleep(delay);
+                delay = (delay * 2).min(self.max_backoff);
+
+                if let Ok(manager) = self.builder.clone().build() {
+                    self.manager = manager;
+                    if let Some(callback) = &mut self.on_recover {
+                        callback(attempts, &err);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Statistics of a recording made with [`DXGIManager::record_to`].
+#[cfg(feature = "record")]
+#[cfg_attr(docsrs, doc(cfg(feature = "record")))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecordStats {
+    /// Total number of frames written to the file
+    pub frames_written: u64,
+    /// Frames that repeat the previous image because the desktop did not change
+    pub frames_repeated: u64,
+    /// Frame slots that were skipped because the recorder fell behind, and
+    /// were filled with the previous image instead
+    pub frames_dropped: u64,
+}
+
+/// An independent capturer for a single output, created by [`DXGIManager::split`].
+///
+/// Each capturer owns the duplication of its output along with its own D3D11
+/// device, so capturers for different displays can be moved to separate threads
+/// and capture concurrently without re-acquiring on every switch. After a
+/// non-timeout error the output is duplicated again on the next capture.
+///
+/// Rotation is applied as in [`DXGIManager::capture_frame`].
+pub struct OutputCapturer {
+    adapter: IDXGIAdapter1,
+    output: IDXGIOutput,
+    duplicated_output: Option<DuplicatedOutput>,
+    index: usize,
+    timeout_ms: u32,
+    max_frame_pixels: usize,
+}
+
+impl OutputCapturer {
+    /// Returns the index of the output, in the order used by [`DXGIManager::split`]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the dimensions `(width, height)` of the output on the desktop
+    pub fn geometry(&self) -> Result<(usize, usize), CaptureError> {
+        let RECT {
+            left,
+            top,
+            right,
+            bottom,
+        } = unsafe { self.output.GetDesc()? }.DesktopCoordinates;
+        Ok(((right - left) as usize, (bottom - top) as usize))
+    }
+
+    /// Sets the timeout in milliseconds for capture operations
+    pub fn set_timeout_ms(&mut self, timeout_ms: u32) {
+        self.timeout_ms = timeout_ms
+    }
+
+    /// Gets the timeout in milliseconds for capture operations
+    pub fn get_timeout_ms(&self) -> u32 {
+        self.timeout_ms
+    }
+
+    /// Captures a single frame of this output as a `Vec<BGRA8>`.
+    ///
+    /// See [`DXGIManager::capture_frame`].
+    pub fn capture_frame(&mut self) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        self.capture()
+    }
+
+    /// Captures a single frame of this output as raw BGRA bytes in a `Vec<u8>`.
+    ///
+    /// See [`DXGIManager::capture_frame_components`].
+    pub fn capture_frame_components(&mut self) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
+        self.capture()
+    }
+
+    /// Acquires a frame, duplicating the output first if needed, and copies it
+    /// with rotation applied. On non-timeout errors the duplication is dropped
+    /// so that the next capture duplicates the output again.
+    fn capture<T: Copy + Send + Sync + Sized>(
+        &mut self,
+    ) -> Result<(Vec<T>, (usize, usize)), CaptureError> {
+        if self.duplicated_output.is_none() {
+            let duplicated_output =
+                DuplicatedOutput::new(&self.adapter, self.output.clone(), false, &[])
+                    .map_err(|e| map_refresh_error(e.into()))?;
+            self.duplicated_output = Some(duplicated_output);
+        }
+        let dup = self.duplicated_output.as_mut().unwrap();
+
+        let surface = match dup.capture_frame_to_surface(self.timeout_ms, None) {
+            Ok((surface, _)) => surface,
+            Err(e) => {
+                let err = map_capture_error(e);
+                if !matches!(err, CaptureError::Timeout) {
+                    self.duplicated_output = None;
+                }
+                return Err(err);
+            }
+        };
+
+        check_surface_size(&surface, self.max_frame_pixels)?;
+        let surface_desc = unsafe { surface.GetDesc()? };
+        let rotation = dup.rotation()?;
+        DXGIManager::copy_mapped_surface(
+            &surface,
+            surface_desc.Width as usize,
+            surface_desc.Height as usize,
+            rotation,
+            false,
+            PixelOrder::Bgra,
+        )
+    }
+}
+
+/// A captured frame borrowed from the mapped staging surface, returned by
+/// [`DXGIManager::capture_frame_borrowed`].
+///
+/// Dereferences to the tightly packed BGRA bytes of the frame in the native
+/// orientation of the display. When the surface rows are already contiguous,
+/// the bytes are read straight from the mapped surface, which stays mapped
+/// until the frame is dropped. Otherwise the rows are copied into an internal
+/// buffer and the surface is unmapped immediately.
+///
+/// The frame borrows the manager mutably, so no other capture can happen while
+/// it is alive.
+pub struct MappedFrame<'a> {
+    surface: IDXGISurface1,
+    mapped: Option<(*const u8, usize)>,
+    buffer: Vec<u8>,
+    dimensions: (usize, usize),
+    _manager: PhantomData<&'a mut DXGIManager>,
+}
+
+impl MappedFrame<'_> {
+    /// Returns the frame dimensions `(width, height)` in pixels
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+
+    /// Returns true if the bytes are read directly from the mapped surface
+    /// rather than from a copy
+    pub fn is_borrowed(&self) -> bool {
+        self.mapped.is_some()
+    }
+}
+
+impl Deref for MappedFrame<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self.mapped {
+            // The surface stays mapped until `self` is dropped
+            Some((data, len)) => unsafe { slice::from_raw_parts(data, len) },
+            None => &self.buffer,
+        }
+    }
+}
+
+impl Drop for MappedFrame<'_> {
+    fn drop(&mut self) {
+        if self.mapped.is_some() {
+            let _ = unsafe { self.surface.Unmap() };
+        }
+    }
+}
+
+/// An iterator over the rows of a captured frame, returned by
+/// [`DXGIManager::capture_frame_rows`].
+///
+/// Yields `height` rows of `width` [`BGRA8`] pixels each, top to bottom in
+/// the native orientation of the display. The rows point into the mapped
+/// surface, which stays mapped while the manager is borrowed.
+pub struct RowIter<'a> {
+    data: *const BGRA8,
+    stride: usize,
+    width: usize,
+    rows: Range<usize>,
+    _manager: PhantomData<&'a mut DXGIManager>,
+}
+
+impl<'a> Iterator for RowIter<'a> {
+    type Item = &'a [BGRA8];
+
+    fn next(&mut self) -> Option<&'a [BGRA8]> {
+        let row = self.rows.next()?;
+        // The data covers `stride` pixels for each of the frame's rows
+        Some(unsafe { slice::from_raw_parts(self.data.add(row * self.stride), self.width) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl ExactSizeIterator for RowIter<'_> {}
+
+/// A consumer of frame rows, fed one row at a time by
+/// [`DXGIManager::capture_frame_to_sink`].
+///
+/// Implement this for encoders that process a frame row by row, such as PNG
+/// or video encoders, so frames reach them without being collected into a
+/// full-frame buffer first. Closures taking `(y, row)` implement it too.
+pub trait FrameSink {
+    /// Receives row `y` of the frame, counted from the top, as `width * 4`
+    /// bytes. Rows arrive in order, starting at 0. The slice is reused for the
+    /// next row, so copy out anything that must be kept.
+    fn row(&mut self, y: usize, row: &[u8]);
+}
+
+impl<F: FnMut(usize, &[u8])> FrameSink for F {
+    fn row(&mut self, y: usize, row: &[u8]) {
+        self(y, row)
+    }
+}
+
+/// An acquired frame that is released when dropped, returned by
+/// [`DXGIManager::acquire_frame`].
+///
+/// The frame's pixels stay on the GPU until [`FrameGuard::map`] or
+/// [`FrameGuard::map_components`] copies them out, so a frame can be inspected
+/// through [`FrameGuard::metadata`] and skipped cheaply.
+pub struct FrameGuard<'a> {
+    output: &'a mut DuplicatedOutput,
+    texture: ID3D11Texture2D,
+    frame_info: DXGI_OUTDUPL_FRAME_INFO,
+    composite_cursor: bool,
+    output_order: PixelOrder,
+    max_frame_pixels: usize,
+    force_opaque_alpha: bool,
+    premultiply_alpha: bool,
+    flip_vertical: bool,
+}
+
+impl FrameGuard<'_> {
+    /// Returns the metadata of the frame, including its dirty and move
+    /// rectangles.
+    pub fn metadata(&self) -> Result<FrameMetadata, CaptureError> {
+        // The output is only borrowed shared here, so its scratch buffers
+        // cannot be reused
+        Ok(self.output.read_frame_metadata(
+            &self.frame_info,
+            MetadataFlags::ALL,
+            &mut Vec::new(),
+            &mut Vec::new(),
+        )?)
+    }
+
+    /// Copies the frame's pixels out as a `Vec<BGRA8>`, as
+    /// [`DXGIManager::capture_frame`] would return them. Can be called more
+    /// than once.
+    pub fn map(&mut self) -> Result<(Vec<BGRA8>, (usize, usize)), CaptureError> {
+        self.copy_pixels(PixelOrder::Bgra)
+    }
+
+    /// Copies the frame's pixels out as bytes in the configured output order,
+    /// as [`DXGIManager::capture_frame_components`] would return them. Can be
+    /// called more than once.
+    pub fn map_components(&mut self) -> Result<(Vec<u8>, (usize, usize)), CaptureError> {
+        self.copy_pixels(self.output_order)
+    }
+
+    fn copy_pixels<T: Copy + Send + Sync + Sized>(
+        &mut self,
+        order: PixelOrder,
+    ) -> Result<(Vec<T>, (usize, usize)), CaptureError> {
+        let (surface, _) = self.output.mappable_surface(&self.texture)?;
+        let rotation = self.output.rotation()?;
+        let desc = unsafe { surface.GetDesc()? };
+        check_frame_size(
+            desc.Width as usize,
+            desc.Height as usize,
+            self.max_frame_pixels,
+        )?;
+
+        let (mut data, dimensions) = DXGIManager::copy_mapped_surface::<T>(
+            &surface,
+            desc.Width as usize,
+            desc.Height as usize,
+            rotation,
+            self.flip_vertical,
+            order,
+        )?;
+
+        if self.composite_cursor {
+            let frame = unsafe {
+                slice::from_raw_parts_mut(
+                    data.as_mut_ptr() as *mut u8,
+                    data.len() * mem::size_of::<T>(),
+                )
+            };
+            self.output.composite_pointer(
+                frame,
+                dimensions.0,
+                dimensions.1,
+                order,
+                self.flip_vertical,
+            );
+        }
+        apply_alpha(&mut data, self.force_opaque_alpha, self.premultiply_alpha);
+
+        Ok((data, dimensions))
+    }
+}
+
+impl Drop for FrameGuard<'_> {
+    fn drop(&mut self) {
+        let _ = unsafe { self.output.output_duplication.ReleaseFrame() };
+    }
+}
+
+pub type CaptureFrameWithMetadataResult =
+    Result<(Vec<BGRA8>, (usize, usize), FrameMetadata), CaptureError>;
+
+pub type CaptureFrameComponentsWithMetadataResult =
     Result<(Vec<u8>, (usize, usize), FrameMetadata), CaptureError>;
+
+pub type CaptureFrameHashedResult = Result<(Vec<BGRA8>, (usize, usize), u64), CaptureError>;
+
+pub type CaptureLatestFrameResult = Result<(Vec<BGRA8>, (usize, usize), usize), CaptureError>;
+
+pub type CaptureFrameQuadsResult = Result<(Vec<[u8; 4]>, (usize, usize)), CaptureError>;
+
+pub type CaptureFrameSharedResult = Result<(Arc<[BGRA8]>, (usize, usize)), CaptureError>;
+
+pub type CaptureFrameU16Result = Result<(Vec<[u16; 4]>, (usize, usize)), CaptureError>;
+
+pub type CaptureFrameTimedResult =
+    Result<(Vec<BGRA8>, (usize, usize), CaptureTimings), CaptureError>;
+
+pub type CaptureFramePresentTimeResult =
+    Result<(Vec<BGRA8>, (usize, usize), PresentTiming), CaptureError>;
+
+pub type SourceFrame = (usize, Result<(Vec<BGRA8>, (usize, usize)), CaptureError>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_disconnected_duplication_maps_to_session_disconnected() {
+        let err = OutputDuplicationError::DeviceError(DXGI_ERROR_SESSION_DISCONNECTED.into());
+        assert!(matches!(
+            map_refresh_error(err),
+            CaptureError::SessionDisconnected
+        ));
+    }
+
+    fn staging_textures_created(manager: &DXGIManager) -> usize {
+        manager
+            .duplicated_output
+            .as_ref()
+            .map_or(0, |output| output.staging_textures_created)
+    }
+
+    #[test]
+    fn staging_textures_are_reused_per_path() {
+        let mut manager = match DXGIManager::new(1000) {
+            Ok(m) => m,
+            Err(_) => {
+                println!("DXGI not available - skipping staging texture test");
+                return;
+            }
+        };
+        assert_eq!(staging_textures_created(&manager), 0);
+
+        // Alternating between paths must not recreate their textures, so the
+        // count grows at most once per path (not at all when the desktop
+        // texture is mapped directly)
+        let mut used = [false; 3];
+        for _ in 0..3 {
+            if manager.capture_frame().is_ok() {
+                used[StagingSlot::Full as usize] = true;
+            }
+            assert!(staging_textures_created(&manager) <= used.iter().filter(|&&u| u).count());
+            if manager
+                .capture_region_with(Rect::new(0, 0, 16, 16), |_, _, _, _| ())
+                .is_ok()
+            {
+                used[StagingSlot::Region as usize] = true;
+            }
+            assert!(staging_textures_created(&manager) <= used.iter().filter(|&&u| u).count());
+            if manager.capture_frame_scaled(0.5).is_ok() {
+                used[StagingSlot::Scaled as usize] = true;
+            }
+            assert!(staging_textures_created(&manager) <= used.iter().filter(|&&u| u).count());
+        }
+
+        // A region of another size changes the desc, so its texture is recreated
+        let created = staging_textures_created(&manager);
+        if manager
+            .capture_region_with(Rect::new(0, 0, 32, 16), |_, _, _, _| ())
+            .is_ok()
+        {
+            assert_eq!(staging_textures_created(&manager), created + 1);
+        }
+
+        // Re-acquiring, as happens after a geometry change, starts a fresh cache
+        assert!(manager.acquire_output_duplication().is_ok());
+        assert_eq!(staging_textures_created(&manager), 0);
+    }
+}
@@ -0,0 +1,58 @@
+//! CPU reference conversion from BGRA to NV12 (BT.709, limited range).
+
+use crate::Nv12Frame;
+
+/// Converts a `width` x `height` BGRA image whose rows are `pitch` bytes apart
+/// into NV12 with 16-byte aligned plane strides.
+///
+/// Chroma is averaged over each 2x2 block; at odd right and bottom edges the
+/// block is smaller and only the available pixels are averaged.
+pub(crate) fn bgra_to_nv12(data: &[u8], pitch: usize, width: usize, height: usize) -> Nv12Frame {
+    let stride = width.next_multiple_of(16);
+    let chroma_height = height.div_ceil(2);
+
+    let mut y_plane = vec![0u8; stride * height];
+    let mut uv_plane = vec![128u8; stride * chroma_height];
+
+    for row in 0..height {
+        let source = &data[row * pitch..row * pitch + width * 4];
+        let dest = &mut y_plane[row * stride..row * stride + width];
+        for (y, pixel) in dest.iter_mut().zip(source.chunks_exact(4)) {
+            let (b, g, r) = (pixel[0] as i32, pixel[1] as i32, pixel[2] as i32);
+            *y = (((47 * r + 157 * g + 16 * b + 128) >> 8) + 16) as u8;
+        }
+    }
+
+    for chroma_row in 0..chroma_height {
+        let rows = (chroma_row * 2)..(chroma_row * 2 + 2).min(height);
+        for chroma_col in 0..width.div_ceil(2) {
+            let cols = (chroma_col * 2)..(chroma_col * 2 + 2).min(width);
+
+            let (mut b, mut g, mut r, mut count) = (0i32, 0i32, 0i32, 0i32);
+            for row in rows.clone() {
+                for col in cols.clone() {
+                    let index = row * pitch + col * 4;
+                    b += data[index] as i32;
+                    g += data[index + 1] as i32;
+                    r += data[index + 2] as i32;
+                    count += 1;
+                }
+            }
+            let average = |sum: i32| (sum + count / 2) / count;
+            let (b, g, r) = (average(b), average(g), average(r));
+
+            let index = chroma_row * stride + chroma_col * 2;
+            uv_plane[index] = (((-26 * r - 86 * g + 112 * b + 128) >> 8) + 128) as u8;
+            uv_plane[index + 1] = (((112 * r - 102 * g - 10 * b + 128) >> 8) + 128) as u8;
+        }
+    }
+
+    Nv12Frame {
+        y_plane,
+        uv_plane,
+        width,
+        height,
+        y_stride: stride,
+        uv_stride: stride,
+    }
+}
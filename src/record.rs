@@ -0,0 +1,66 @@
+//! Writing NV12 frames to a YUV4MPEG2 (`.y4m`) stream, the container used by
+//! `DXGIManager::record_to`.
+
+use crate::Nv12Frame;
+use std::io::{self, Write};
+
+/// Writes frames of a fixed size as 4:2:0 planar YUV4MPEG2.
+///
+/// The stream header is written on creation, followed by one `FRAME` chunk per
+/// frame holding the Y, U and V planes without any row padding.
+pub(crate) struct Y4mWriter<W: Write> {
+    out: W,
+    width: usize,
+    height: usize,
+    chroma_row: Vec<u8>,
+}
+
+impl<W: Write> Y4mWriter<W> {
+    pub(crate) fn new(mut out: W, width: usize, height: usize, fps: u32) -> io::Result<Self> {
+        // Chroma is averaged over each 2x2 block, so it is centered as in JPEG
+        writeln!(
+            out,
+            "YUV4MPEG2 W{width} H{height} F{fps}:1 Ip A1:1 C420jpeg XCOLORRANGE=LIMITED"
+        )?;
+        Ok(Self {
+            out,
+            width,
+            height,
+            chroma_row: Vec::with_capacity(width.div_ceil(2)),
+        })
+    }
+
+    pub(crate) fn write_frame(&mut self, frame: &Nv12Frame) -> io::Result<()> {
+        if (frame.width, frame.height) != (self.width, self.height) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "frame size changed during recording",
+            ));
+        }
+
+        self.out.write_all(b"FRAME\n")?;
+        for row in frame.y_plane.chunks(frame.y_stride).take(self.height) {
+            self.out.write_all(&row[..self.width])?;
+        }
+
+        // De-interleave the UV plane into separate U and V planes
+        let chroma_width = self.width.div_ceil(2);
+        for component in 0..2 {
+            for row in frame
+                .uv_plane
+                .chunks(frame.uv_stride)
+                .take(self.height.div_ceil(2))
+            {
+                self.chroma_row.clear();
+                self.chroma_row
+                    .extend(row[component..chroma_width * 2].iter().step_by(2));
+                self.out.write_all(&self.chroma_row)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -1,27 +1,31 @@
-use std::arch::x86_64::*;
+//! BGRA to RGBA channel swapping, vectorized with SSSE3/AVX2 when the `simd`
+//! feature is enabled and the CPU supports it.
 
 // Adapted from https://github.com/mcy/vb64/blob/main/src/simd.rs
 
-pub fn bgra_to_rgba(s: &mut [u8]) {
-    if s.len() < 16 {
-        return bgra_to_rgba_scalar(s);
-    }
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+use std::arch::x86_64::*;
 
-    #[cfg(target_arch = "x86_64")]
+/// Swaps the B and R channels of every 4-byte pixel in `buf` in place.
+pub(crate) fn bgra_to_rgba(buf: &mut [u8]) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
     {
-        if is_x86_feature_detected!("avx2") {
-            return unsafe { bgra_to_rgba_simd_avx2(s) };
-        }
-        if is_x86_feature_detected!("ssse3") {
-            return unsafe { bgra_to_rgba_simd_sse2(s) };
+        if buf.len() >= 16 {
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { bgra_to_rgba_avx2(buf) };
+            }
+            if is_x86_feature_detected!("ssse3") {
+                return unsafe { bgra_to_rgba_ssse3(buf) };
+            }
         }
     }
 
-    bgra_to_rgba_scalar(s)
+    bgra_to_rgba_scalar(buf)
 }
 
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
 #[target_feature(enable = "ssse3")]
-unsafe fn bgra_to_rgba_simd_sse2(buf: &mut [u8]) {
+unsafe fn bgra_to_rgba_ssse3(buf: &mut [u8]) {
     unsafe {
         let mut chunks = buf.chunks_exact_mut(16);
         for chunk in &mut chunks {
@@ -36,8 +40,9 @@ unsafe fn bgra_to_rgba_simd_sse2(buf: &mut [u8]) {
     }
 }
 
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
 #[target_feature(enable = "avx2")]
-unsafe fn bgra_to_rgba_simd_avx2(buf: &mut [u8]) {
+unsafe fn bgra_to_rgba_avx2(buf: &mut [u8]) {
     unsafe {
         let mut chunks = buf.chunks_exact_mut(32);
         for chunk in &mut chunks {
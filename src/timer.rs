@@ -0,0 +1,48 @@
+//! A single background thread that wakes futures at a given instant, used by
+//! the `async` capture API to retry without blocking the executor.
+
+use std::sync::OnceLock;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::task::Waker;
+use std::thread;
+use std::time::Instant;
+
+/// Schedules `waker` to be woken at `deadline`.
+pub(crate) fn wake_at(deadline: Instant, waker: Waker) {
+    static TIMER: OnceLock<Sender<(Instant, Waker)>> = OnceLock::new();
+    let sender = TIMER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("dxgi-capture-timer".into())
+            .spawn(move || run(receiver))
+            .expect("Failed to spawn timer thread");
+        sender
+    });
+    // The timer thread never exits, so sending cannot fail
+    let _ = sender.send((deadline, waker));
+}
+
+fn run(receiver: Receiver<(Instant, Waker)>) {
+    let mut pending: Vec<(Instant, Waker)> = Vec::new();
+    loop {
+        let now = Instant::now();
+        pending.retain(|(deadline, waker)| {
+            if *deadline <= now {
+                waker.wake_by_ref();
+                false
+            } else {
+                true
+            }
+        });
+
+        let next = match pending.iter().map(|(deadline, _)| *deadline).min() {
+            Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(now)),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match next {
+            Ok(entry) => pending.push(entry),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
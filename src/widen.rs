@@ -0,0 +1,56 @@
+//! Widening of desktop pixels to 16 bits per channel, keeping the precision
+//! of 10-bit and floating-point surfaces.
+
+/// Widens 8-bit channels to 16 bits, mapping 255 to 65535.
+pub(crate) fn from_8bit(pixel: [u8; 4]) -> [u16; 4] {
+    pixel.map(|channel| channel as u16 * 257)
+}
+
+/// Unpacks a little-endian `DXGI_FORMAT_R10G10B10A2_UNORM` pixel into RGBA.
+/// The high bits of each channel are repeated in its low bits, so the full
+/// range maps to the full 16-bit range.
+pub(crate) fn from_10bit(pixel: [u8; 4]) -> [u16; 4] {
+    let packed = u32::from_le_bytes(pixel);
+    let channel = |shift: u32| {
+        let value = ((packed >> shift) & 0x3ff) as u16;
+        (value << 6) | (value >> 4)
+    };
+    let alpha = (packed >> 30) as u16 * 0x5555;
+    [channel(0), channel(10), channel(20), alpha]
+}
+
+/// Converts the channels of a `DXGI_FORMAT_R16G16B16A16_FLOAT` pixel to 16-bit
+/// unsigned normalized values, keeping RGBA order.
+pub(crate) fn from_fp16(pixel: [u16; 4]) -> [u16; 4] {
+    pixel.map(half_to_unorm16)
+}
+
+/// Multiplies the color channels of a 16-bit pixel by its alpha, the last
+/// channel, as [`crate::DXGIManager::set_premultiply_alpha`] does for 8-bit
+/// pixels.
+pub(crate) fn premultiply(pixel: &mut [u16; 4]) {
+    let alpha = pixel[3] as u32;
+    for channel in &mut pixel[..3] {
+        *channel = (*channel as u32 * alpha / 65535) as u16;
+    }
+}
+
+/// Converts a half-precision float to a 16-bit unsigned normalized value,
+/// saturating values outside `0.0..=1.0`. NaN becomes 0.
+fn half_to_unorm16(half: u16) -> u16 {
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    if half & 0x8000 != 0 {
+        return 0;
+    }
+    if exponent == 0x1f {
+        return if mantissa == 0.0 { u16::MAX } else { 0 };
+    }
+
+    let value = if exponent == 0 {
+        mantissa * 2f32.powi(-24)
+    } else {
+        (1024.0 + mantissa) * 2f32.powi(exponent - 25)
+    };
+    (value.min(1.0) * 65535.0).round() as u16
+}
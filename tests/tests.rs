@@ -1,6 +1,68 @@
 //! Tests for dxgi-capture-rs library functionality.
 
-use dxgi_capture_rs::{BGRA8, CaptureError, DXGIManager};
+use dxgi_capture_rs::{
+    BGRA8, CaptureError, DXGIManager, DXGIManagerBuilder, FrameStatus, Rect, Rotation,
+    compute_dirty_rects, copy_rotated, copy_rotated_flipped, premultiply_alpha, qpc_to_duration,
+};
+
+/// Re-acquires the duplication and captures a reference frame with
+/// [`DXGIManager::capture_frame`]. DXGI delivers the current desktop image
+/// right after a duplication is created, so the duplication is re-acquired
+/// once more afterwards to give the capture under test a fresh full frame too.
+fn reference_frame(manager: &mut DXGIManager) -> Option<(Vec<BGRA8>, (usize, usize))> {
+    manager.acquire_output_duplication().ok()?;
+    let frame = manager.capture_frame().ok()?;
+    manager.acquire_output_duplication().ok()?;
+    Some(frame)
+}
+
+/// Whether the capture source is unrotated, so native-orientation captures
+/// can be compared with [`DXGIManager::capture_frame`].
+fn is_unrotated(manager: &DXGIManager) -> bool {
+    manager
+        .current_mode()
+        .is_ok_and(|mode| mode.rotation == Rotation::Identity)
+}
+
+fn bgra_quads(pixels: &[BGRA8]) -> Vec<[u8; 4]> {
+    pixels.iter().map(|p| [p.b, p.g, p.r, p.a]).collect()
+}
+
+/// Groups mapped rows of `width` pixels, `pitch` bytes apart, into one array
+/// per pixel, dropping the row padding.
+fn mapped_quads(data: &[u8], pitch: usize, width: usize, height: usize) -> Vec<[u8; 4]> {
+    (0..height)
+        .flat_map(|row| data[row * pitch..row * pitch + width * 4].chunks_exact(4))
+        .map(|p| [p[0], p[1], p[2], p[3]])
+        .collect()
+}
+
+fn crop<T: Copy>(pixels: &[T], width: usize, x: usize, y: usize, w: usize, h: usize) -> Vec<T> {
+    (y..y + h)
+        .flat_map(|row| pixels[row * width + x..row * width + x + w].iter().copied())
+        .collect()
+}
+
+/// Asserts that `matches` holds for at least 90% of the element pairs. Two
+/// captures are taken moments apart, so a clock or an animation may change a
+/// few pixels in between.
+fn assert_mostly<T>(actual: &[T], expected: &[T], matches: impl Fn(&T, &T) -> bool, what: &str) {
+    assert_eq!(actual.len(), expected.len(), "{what}: length differs");
+    let same = actual
+        .iter()
+        .zip(expected)
+        .filter(|(a, b)| matches(a, b))
+        .count();
+    assert!(
+        same * 10 >= actual.len() * 9,
+        "{what}: only {same} of {} elements match the reference",
+        actual.len()
+    );
+}
+
+fn assert_mostly_equal<T: PartialEq>(actual: &[T], expected: &[T], what: &str) {
+    assert_mostly(actual, expected, |a, b| a == b, what);
+}
 
 #[test]
 fn test_dxgi_manager_creation() {
@@ -56,10 +118,17 @@ fn test_capture_source_index() {
     let initial_index = manager.get_capture_source_index();
     assert_eq!(initial_index, 0);
 
-    manager.set_capture_source_index(0);
+    manager
+        .set_capture_source_index(0)
+        .expect("The primary display should be selectable");
     assert_eq!(manager.get_capture_source_index(), 0);
 
-    manager.set_capture_source_index(1);
+    // Without a secondary display, the primary one stays selected
+    let expected = match manager.set_capture_source_index(1) {
+        Ok(()) => 1,
+        Err(_) => 0,
+    };
+    assert_eq!(manager.get_capture_source_index(), expected);
 }
 
 #[test]
@@ -104,6 +173,37 @@ fn test_geometry() {
     );
 }
 
+#[test]
+fn test_frame_and_output_geometry() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping frame geometry test");
+            return;
+        }
+    };
+
+    assert_eq!(manager.frame_geometry(), manager.geometry());
+    let (frame_width, frame_height) = manager.frame_geometry();
+    let (output_width, output_height) = manager.output_geometry();
+    assert!(
+        (output_width, output_height) == (frame_width, frame_height)
+            || (output_width, output_height) == (frame_height, frame_width)
+    );
+
+    // Holds on rotated displays too, where the two geometries differ
+    match manager.capture_frame() {
+        Ok((_, dimensions)) => assert_eq!(dimensions, manager.frame_geometry()),
+        Err(CaptureError::Timeout) => println!("Capture timed out - acceptable"),
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+    match manager.capture_frame_fast() {
+        Ok((_, dimensions)) => assert_eq!(dimensions, manager.output_geometry()),
+        Err(CaptureError::Timeout) => println!("Fast capture timed out - acceptable"),
+        Err(e) => println!("Fast capture failed: {e:?}"),
+    }
+}
+
 #[test]
 fn test_frame_capture() {
     let mut manager = match DXGIManager::new(1000) {
@@ -140,6 +240,36 @@ fn test_frame_capture() {
     }
 }
 
+#[test]
+fn test_snapshot() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping snapshot test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping snapshot test");
+        return;
+    };
+
+    // Consume pending frames so the screen looks static to the snapshot
+    for _ in 0..100 {
+        if manager.wait_for_frame(std::time::Duration::ZERO).is_err() {
+            break;
+        }
+    }
+
+    match manager.snapshot() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!((width, height), manager.frame_geometry());
+            assert_mostly_equal(&pixels, &reference, "snapshot");
+        }
+        Err(e) => println!("Snapshot failed: {e:?}"),
+    }
+}
+
 #[test]
 fn test_frame_components_capture() {
     let mut manager = match DXGIManager::new(300) {
@@ -167,6 +297,276 @@ fn test_frame_components_capture() {
     }
 }
 
+#[test]
+fn test_frame_shared_capture() {
+    let mut manager = match DXGIManager::new(300) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping test");
+        return;
+    };
+
+    match manager.capture_frame_shared() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!((width, height), manager.frame_geometry());
+            assert_mostly_equal(&pixels, &reference, "shared frame");
+            let shared = pixels.clone();
+            assert!(std::ptr::eq(shared.as_ptr(), pixels.as_ptr()));
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Shared frame capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Shared frame capture failed with error: {e:?}"),
+    }
+}
+
+#[test]
+fn test_frame_timed_capture() {
+    use dxgi_capture_rs::CaptureTimings;
+    use std::time::Duration;
+
+    let timings = CaptureTimings {
+        acquire: Duration::from_millis(3),
+        map: Duration::from_millis(2),
+        copy: Duration::from_millis(1),
+    };
+    assert_eq!(timings.total(), Duration::from_millis(6));
+    assert_eq!(CaptureTimings::default().total(), Duration::ZERO);
+
+    let mut manager = match DXGIManager::new(300) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+    assert!(manager.get_capture_timing());
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping test");
+        return;
+    };
+
+    match manager.capture_frame_timed() {
+        Ok((pixels, _, timings)) => {
+            assert_mostly_equal(&pixels, &reference, "timed frame");
+            println!("Capture timings: {timings:?}");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Timed frame capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Timed frame capture failed with error: {e:?}"),
+    }
+
+    manager.set_capture_timing(false);
+    assert!(!manager.get_capture_timing());
+    match manager.capture_frame_timed() {
+        Ok((_, _, timings)) => assert_eq!(timings, CaptureTimings::default()),
+        Err(CaptureError::Timeout) => {
+            println!("Timed frame capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Timed frame capture failed with error: {e:?}"),
+    }
+}
+
+#[test]
+fn test_frame_with_present_time() {
+    use std::time::Instant;
+
+    let mut manager = match DXGIManager::new(300) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping test");
+        return;
+    };
+
+    match manager.capture_frame_with_present_time() {
+        Ok((pixels, _, timing)) => {
+            assert_mostly_equal(&pixels, &reference, "present time frame");
+            assert_eq!(timing.presented_at.is_some(), timing.present_qpc > 0);
+            if let Some(presented_at) = timing.presented_at {
+                assert!(presented_at <= Instant::now());
+            }
+            println!("Present timing: {timing:?}");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Present time capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Present time capture failed with error: {e:?}"),
+    }
+}
+
+#[test]
+fn test_force_opaque_alpha() {
+    let mut manager = match DXGIManager::builder()
+        .timeout_ms(300)
+        .force_opaque_alpha(true)
+        .build()
+    {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+    assert!(manager.get_force_opaque_alpha());
+
+    match manager.capture_frame() {
+        Ok((pixels, _)) => assert!(pixels.iter().all(|pixel| pixel.a == 255)),
+        Err(CaptureError::Timeout) => {
+            println!("Opaque frame capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Opaque frame capture failed with error: {e:?}"),
+    }
+
+    match manager.capture_frame_components() {
+        Ok((bytes, _)) => assert!(bytes.chunks_exact(4).all(|pixel| pixel[3] == 255)),
+        Err(CaptureError::Timeout) => {
+            println!("Opaque component capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Opaque component capture failed with error: {e:?}"),
+    }
+
+    manager.set_force_opaque_alpha(false);
+    assert!(!manager.get_force_opaque_alpha());
+}
+
+#[test]
+fn test_frame_u16_capture() {
+    use windows::Win32::Graphics::Dxgi::Common::{
+        DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_R10G10B10A2_UNORM,
+    };
+    use windows::Win32::Graphics::Dxgi::DXGI_ERROR_UNSUPPORTED;
+
+    let mut manager = match DXGIManager::builder()
+        .timeout_ms(300)
+        .duplication_formats(&[DXGI_FORMAT_R10G10B10A2_UNORM, DXGI_FORMAT_B8G8R8A8_UNORM])
+        .force_opaque_alpha(true)
+        .build()
+    {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+    let format = manager.current_format().ok();
+    // 8-bit desktops are widened by repeating each byte
+    let reference = if format == Some(DXGI_FORMAT_B8G8R8A8_UNORM) {
+        reference_frame(&mut manager).map(|(pixels, _)| {
+            pixels
+                .iter()
+                .map(|p| [p.b, p.g, p.r, p.a].map(|c| u16::from(c) * 257))
+                .collect::<Vec<_>>()
+        })
+    } else {
+        None
+    };
+
+    match manager.capture_frame_u16() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!(pixels.len(), width * height);
+            assert_eq!((width, height), manager.frame_geometry());
+            assert!(pixels.iter().all(|pixel| pixel[3] == u16::MAX));
+            if let Some(reference) = reference {
+                assert_mostly_equal(&pixels, &reference, "16-bit frame");
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("16-bit frame capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("16-bit frame capture failed with error: {e:?}"),
+    }
+
+    // 4-byte captures cannot read a 10-bit desktop
+    if format == Some(DXGI_FORMAT_R10G10B10A2_UNORM) {
+        match manager.capture_frame() {
+            Err(CaptureError::Fail(e)) => assert_eq!(e.code(), DXGI_ERROR_UNSUPPORTED),
+            Err(CaptureError::Timeout) => {}
+            other => panic!("Expected DXGI_ERROR_UNSUPPORTED, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_capture_center_crop() {
+    let mut manager = match DXGIManager::new(300) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+    let (width, height) = manager.frame_geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping test");
+        return;
+    };
+
+    match manager.capture_center_crop(64) {
+        Ok((pixels, (w, h))) => {
+            assert_eq!(
+                (w, h),
+                (64.min(width).min(height), 64.min(width).min(height))
+            );
+            let (x, y) = ((width - w) / 2, (height - h) / 2);
+            let expected = crop(&reference, width, x, y, w, h);
+            assert_mostly_equal(&pixels, &expected, "center crop");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Center crop capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Center crop capture failed with error: {e:?}"),
+    }
+
+    // Oversized squares are clamped to the smaller frame dimension
+    match manager.capture_center_crop(usize::MAX) {
+        Ok((pixels, (w, h))) => {
+            assert_eq!((w, h), (width.min(height), width.min(height)));
+            assert_eq!(pixels.len(), w * h);
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Center crop capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Center crop capture failed with error: {e:?}"),
+    }
+}
+
+#[test]
+fn test_frame_quads_capture() {
+    let mut manager = match DXGIManager::new(300) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping test");
+        return;
+    };
+
+    match manager.capture_frame_quads() {
+        Ok((quads, (width, height))) => {
+            assert_eq!((width, height), manager.frame_geometry());
+            assert_mostly_equal(&quads, &bgra_quads(&reference), "frame quads");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Frame quads capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Frame quads capture failed with error: {e:?}"),
+    }
+}
+
 #[test]
 fn test_frame_capture_consistency() {
     let mut manager = match DXGIManager::new(500) {
@@ -273,14 +673,54 @@ fn test_hresult_failure_detection() {
     assert!(HRESULT(-2147467259).is_err());
 }
 
+#[test]
+fn test_device_lost_error_mapping() {
+    use windows::Win32::Foundation::E_FAIL;
+    use windows::Win32::Graphics::Dxgi::{DXGI_ERROR_DEVICE_REMOVED, DXGI_ERROR_DEVICE_RESET};
+
+    for code in [DXGI_ERROR_DEVICE_REMOVED, DXGI_ERROR_DEVICE_RESET] {
+        assert!(matches!(
+            CaptureError::from(windows::core::Error::from(code)),
+            CaptureError::DeviceLost
+        ));
+    }
+    assert!(matches!(
+        CaptureError::from(windows::core::Error::from(E_FAIL)),
+        CaptureError::Fail(_)
+    ));
+}
+
+#[test]
+fn test_session_disconnected_error_mapping() {
+    use windows::Win32::Graphics::Dxgi::DXGI_ERROR_SESSION_DISCONNECTED;
+
+    let err = CaptureError::from(windows::core::Error::from(DXGI_ERROR_SESSION_DISCONNECTED));
+    assert!(matches!(err, CaptureError::SessionDisconnected));
+    assert_eq!(err.to_string(), "The session was disconnected");
+}
+
 #[test]
 fn test_capture_error_variants() {
+    use dxgi_capture_rs::OutputDuplicationError;
     use windows::Win32::Foundation::E_FAIL;
     let errors = [
         CaptureError::AccessDenied,
         CaptureError::AccessLost,
-        CaptureError::RefreshFailure,
+        CaptureError::DeviceLost,
+        CaptureError::RefreshFailure(OutputDuplicationError::NoOutput),
+        CaptureError::RefreshFailure(OutputDuplicationError::UnsupportedSession),
+        CaptureError::RefreshFailure(OutputDuplicationError::DeviceError(
+            windows::core::Error::from(E_FAIL),
+        )),
         CaptureError::Timeout,
+        CaptureError::WindowNotOnOutput,
+        CaptureError::DesktopSwitch,
+        CaptureError::SessionDisconnected,
+        CaptureError::FrameTooLarge {
+            width: 100_000,
+            height: 100_000,
+        },
+        CaptureError::Io(std::io::Error::from(std::io::ErrorKind::BrokenPipe)),
         CaptureError::Fail(windows::core::Error::from(E_FAIL)),
     ];
 
@@ -290,6 +730,40 @@ fn test_capture_error_variants() {
     }
 }
 
+#[test]
+fn test_capture_error_into_io_error() {
+    use std::io;
+    use windows::Win32::Foundation::E_FAIL;
+
+    let cases = [
+        (CaptureError::Timeout, io::ErrorKind::WouldBlock),
+        (CaptureError::AccessDenied, io::ErrorKind::PermissionDenied),
+        (CaptureError::AccessLost, io::ErrorKind::Other),
+        (CaptureError::DeviceLost, io::ErrorKind::Other),
+        (CaptureError::WindowNotOnOutput, io::ErrorKind::Other),
+        (CaptureError::DesktopSwitch, io::ErrorKind::PermissionDenied),
+        (
+            CaptureError::SessionDisconnected,
+            io::ErrorKind::NotConnected,
+        ),
+        (
+            CaptureError::Fail(windows::core::Error::from(E_FAIL)),
+            io::ErrorKind::Other,
+        ),
+    ];
+
+    for (error, kind) in cases {
+        let message = error.to_string();
+        let io_error = io::Error::from(error);
+        assert_eq!(io_error.kind(), kind);
+        assert_eq!(io_error.to_string(), message);
+    }
+
+    // IO errors come back unwrapped
+    let io_error = io::Error::from(CaptureError::Io(io::Error::from(io::ErrorKind::BrokenPipe)));
+    assert_eq!(io_error.kind(), io::ErrorKind::BrokenPipe);
+}
+
 #[test]
 fn test_hresult_failure_detection_comprehensive() {
     use windows::Win32::Foundation::{E_FAIL, S_OK};
@@ -377,24 +851,23 @@ fn test_capture_source_index_validation() {
         }
     };
 
-    // Setting a very high index should not cause a panic
-    // It should result in an error or timeout on next capture
-    manager.set_capture_source_index(99);
+    use dxgi_capture_rs::OutputDuplicationError;
 
-    // The capture should fail gracefully, likely with a timeout or access lost
-    let result = manager.capture_frame();
+    let geometry = manager.geometry();
+
+    // An invalid index is reported immediately
+    let result = manager.set_capture_source_index(99);
     assert!(
-        result.is_err(),
-        "Capture should fail with an invalid source index"
+        matches!(result, Err(OutputDuplicationError::NoOutput)),
+        "Selecting an invalid source index should fail with NoOutput, got {result:?}"
     );
 
-    // The specific error can vary, but it shouldn't be a success
-    if let Err(e) = result {
-        println!("Capture with invalid index failed as expected: {e:?}");
-        assert!(matches!(
-            e,
-            CaptureError::AccessLost | CaptureError::Timeout | CaptureError::RefreshFailure
-        ));
+    // The previous source stays selected and keeps working
+    assert_eq!(manager.get_capture_source_index(), 0);
+    assert_eq!(manager.geometry(), geometry);
+    match manager.capture_frame() {
+        Ok(_) | Err(CaptureError::Timeout) => {}
+        Err(e) => println!("Capture after an invalid source index failed: {e:?}"),
     }
 }
 
@@ -445,8 +918,7 @@ fn test_capture_workflow() {
         "Pixel buffer size should be consistent"
     );
 
-    manager.set_capture_source_index(1);
-    let switched = manager.acquire_output_duplication().is_ok() && manager.capture_frame().is_ok();
+    let switched = manager.set_capture_source_index(1).is_ok() && manager.capture_frame().is_ok();
 
     if switched {
         println!("Successfully switched to a secondary display.");
@@ -459,9 +931,8 @@ fn test_capture_workflow() {
         println!("Could not switch to secondary display (may not exist).");
     }
 
-    manager.set_capture_source_index(0);
     assert!(
-        manager.acquire_output_duplication().is_ok(),
+        manager.set_capture_source_index(0).is_ok(),
         "Should successfully re-acquire the primary display"
     );
     let (reverted_width, reverted_height) = manager.geometry();
@@ -487,8 +958,10 @@ fn test_capture_source_switching() {
         }
     };
 
-    manager.set_capture_source_index(1);
-    let res1 = manager.capture_frame();
+    let res1 = manager
+        .set_capture_source_index(1)
+        .map_err(CaptureError::RefreshFailure)
+        .and_then(|()| manager.capture_frame());
 
     if res1.is_ok() {
         println!("Secondary display found and captured.");
@@ -498,7 +971,9 @@ fn test_capture_source_switching() {
         println!("Secondary display not found or capture failed (expected if single monitor).");
     }
 
-    manager.set_capture_source_index(0);
+    manager
+        .set_capture_source_index(0)
+        .expect("Should re-acquire the primary display");
     let (w_revert, h_revert) = manager.geometry();
     assert!(
         w_revert > 0 && h_revert > 0,
@@ -615,9 +1090,11 @@ fn test_error_handling_robustness() {
     // Force a situation that might lead to AccessLost or other errors
     // by repeatedly changing capture source.
     for i in 0..3 {
-        manager.set_capture_source_index(i);
+        let _ = manager.set_capture_source_index(i); // Indices past the last display fail
         let _ = manager.capture_frame(); // Ignore result, just stress the system
-        manager.set_capture_source_index(0);
+        if let Err(e) = manager.set_capture_source_index(0) {
+            println!("Re-selecting the primary display failed: {e:?}");
+        }
         let _ = manager.capture_frame();
     }
 
@@ -629,7 +1106,7 @@ fn test_error_handling_robustness() {
 }
 
 #[test]
-fn test_capture_method_consistency() {
+fn test_rapid_source_switching_same_adapter() {
     let mut manager = match DXGIManager::new(1000) {
         Ok(m) => m,
         Err(_) => {
@@ -638,38 +1115,149 @@ fn test_capture_method_consistency() {
         }
     };
 
-    // Capture with BGRA8
-    let res_bgra = manager.capture_frame();
-    if res_bgra.is_err() {
-        println!("BGRA8 capture failed, skipping consistency test.");
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping test");
         return;
-    }
-    let (pixels_bgra, (w_bgra, h_bgra)) = res_bgra.unwrap();
+    };
 
-    // Capture with components
-    // Need to re-acquire to get the same frame, if possible
-    manager.set_capture_source_index(manager.get_capture_source_index()); // Re-init
-    let res_comp = manager.capture_frame_components();
-    if res_comp.is_err() {
-        println!("Component capture failed, skipping consistency test.");
-        return;
+    // Re-selecting the primary display reuses the adapter's device, so this
+    // only re-runs the duplication each time
+    let start = std::time::Instant::now();
+    for _ in 0..10 {
+        if let Err(e) = manager.set_capture_source_index(0) {
+            println!("Re-selecting the primary display failed: {e:?}");
+        }
+        assert_eq!(manager.get_capture_source_index(), 0);
     }
-    let (pixels_comp, (w_comp, h_comp)) = res_comp.unwrap();
+    println!("10 source switches took {:?}", start.elapsed());
 
-    // Check dimensions
-    assert_eq!(w_bgra, w_comp, "Widths should match");
-    assert_eq!(h_bgra, h_comp, "Heights should match");
-    assert_eq!(
-        pixels_bgra.len() * 4,
-        pixels_comp.len(),
-        "Component buffer size should be 4x BGRA buffer"
-    );
+    // The fresh duplication still shows the same display
+    match manager.capture_frame() {
+        Ok((pixels, _)) => assert_mostly_equal(&pixels, &reference, "frame after switching"),
+        Err(CaptureError::Timeout) => println!("Capture timed out after switching"),
+        Err(e) => println!("Capture failed after switching: {e:?}"),
+    }
+}
 
-    // Check data consistency for a sample of pixels
-    let mut consistent = true;
-    for i in 0..pixels_bgra.len().min(100) {
-        let bgra = pixels_bgra[i];
-        let comp_b = pixels_comp[i * 4];
+#[test]
+fn test_changed_area() {
+    let mut manager = match DXGIManager::new(100) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+
+    let (width, height) = manager.geometry();
+    let total = width as u64 * height as u64;
+    for _ in 0..5 {
+        match manager.changed_area() {
+            Ok(changed) => assert!(changed <= total, "Changed area exceeds the frame"),
+            Err(CaptureError::Timeout) => println!("No frame - the screen is idle"),
+            Err(e) => println!("changed_area failed: {e:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_capture_latest_frame() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+
+    for max_drain in [0, 4] {
+        let Some((reference, _)) = reference_frame(&mut manager) else {
+            println!("No reference frame available - skipping test");
+            return;
+        };
+        match manager.capture_latest_frame(max_drain) {
+            Ok((pixels, _, skipped)) => {
+                assert_mostly_equal(&pixels, &reference, "latest frame");
+                assert!(skipped <= max_drain, "Skipped more frames than allowed");
+                println!("Skipped {skipped} of at most {max_drain} frames");
+            }
+            Err(CaptureError::Timeout) => println!("No frame - the screen is static"),
+            Err(e) => println!("Latest frame capture failed: {e:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_interleaved_acquisitions() {
+    let mut manager = match DXGIManager::new(100) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+
+    // On software adapters a captured frame may stay acquired for direct
+    // mapping; every other kind of acquisition must release it first
+    for _ in 0..3 {
+        let results = [
+            manager.capture_frame().map(|_| ()),
+            manager.peek_metadata().map(|_| ()),
+            manager.acquire_frame().map(|_| ()),
+            manager.capture_frame_components().map(|_| ()),
+            manager.changed_area().map(|_| ()),
+        ];
+        for result in results {
+            match result {
+                Ok(()) | Err(CaptureError::Timeout) => {}
+                Err(e) => println!("Acquisition failed: {e:?}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_capture_method_consistency() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping test");
+            return;
+        }
+    };
+
+    // Capture with BGRA8
+    let res_bgra = manager.capture_frame();
+    if res_bgra.is_err() {
+        println!("BGRA8 capture failed, skipping consistency test.");
+        return;
+    }
+    let (pixels_bgra, (w_bgra, h_bgra)) = res_bgra.unwrap();
+
+    // Capture with components
+    // Need to re-acquire to get the same frame, if possible
+    let _ = manager.set_capture_source_index(manager.get_capture_source_index()); // Re-init
+    let res_comp = manager.capture_frame_components();
+    if res_comp.is_err() {
+        println!("Component capture failed, skipping consistency test.");
+        return;
+    }
+    let (pixels_comp, (w_comp, h_comp)) = res_comp.unwrap();
+
+    // Check dimensions
+    assert_eq!(w_bgra, w_comp, "Widths should match");
+    assert_eq!(h_bgra, h_comp, "Heights should match");
+    assert_eq!(
+        pixels_bgra.len() * 4,
+        pixels_comp.len(),
+        "Component buffer size should be 4x BGRA buffer"
+    );
+
+    // Check data consistency for a sample of pixels
+    let mut consistent = true;
+    for i in 0..pixels_bgra.len().min(100) {
+        let bgra = pixels_bgra[i];
+        let comp_b = pixels_comp[i * 4];
         let comp_g = pixels_comp[i * 4 + 1];
         let comp_r = pixels_comp[i * 4 + 2];
         let comp_a = pixels_comp[i * 4 + 3];
@@ -798,23 +1386,18 @@ fn test_geometry_with_none_duplicated_output() {
     let (width, height) = manager.geometry();
     assert!(width > 0 && height > 0, "Initial geometry should be valid");
 
-    manager.set_capture_source_index(99);
-
-    let (width_after, height_after) = manager.geometry();
+    // The failed switch restores the previous capture source
+    assert!(manager.set_capture_source_index(99).is_err());
+    assert_eq!(
+        manager.geometry(),
+        (width, height),
+        "Geometry should be unchanged after selecting an invalid capture source"
+    );
 
-    if width_after == 0 && height_after == 0 {
-        println!(
-            "Geometry returned (0, 0) for invalid capture source - this is expected defensive behavior"
-        );
-    } else {
-        assert!(
-            width_after > 0 && height_after > 0,
-            "Geometry should be valid if not (0, 0)"
-        );
+    if let Err(e) = manager.set_capture_source_index(0) {
+        println!("Re-selecting the primary display failed: {e:?}");
     }
 
-    manager.set_capture_source_index(0);
-
     let (width_final, height_final) = manager.geometry();
     if width_final == 0 && height_final == 0 {
         println!("Warning: Could not re-acquire primary monitor after switching back");
@@ -901,7 +1484,13 @@ fn test_capture_frame_components_with_metadata() {
             assert!(width > 0 && height > 0);
 
             // Test rectangle validity
-            for &(left, top, right, bottom) in &metadata.dirty_rects {
+            for &Rect {
+                left,
+                top,
+                right,
+                bottom,
+            } in &metadata.dirty_rects
+            {
                 assert!(left >= 0 && top >= 0);
                 assert!(right > left && bottom > top);
                 assert!(right <= width as i32 && bottom <= height as i32);
@@ -909,7 +1498,7 @@ fn test_capture_frame_components_with_metadata() {
 
             for move_rect in &metadata.move_rects {
                 let (src_x, src_y) = move_rect.source_point;
-                let (dst_left, dst_top, dst_right, dst_bottom) = move_rect.destination_rect;
+                let (dst_left, dst_top, dst_right, dst_bottom) = move_rect.destination_rect.into();
 
                 assert!(src_x >= 0 && src_y >= 0);
                 assert!(dst_left >= 0 && dst_top >= 0);
@@ -943,9 +1532,13 @@ fn test_metadata_consistency() {
         println!("BGRA8 metadata capture failed, skipping consistency test");
         return;
     }
-    let (pixels_bgra, (w_bgra, h_bgra), _) = res_bgra.unwrap();
+    let (pixels_bgra, (w_bgra, h_bgra), metadata) = res_bgra.unwrap();
+
+    // Rectangles are only reported when DXGI provided a metadata buffer
+    assert!(!metadata.has_updates() || metadata.metadata_available);
+    assert_eq!(metadata.rects_coalesced, manager.rects_coalesced());
 
-    manager.set_capture_source_index(manager.get_capture_source_index());
+    let _ = manager.set_capture_source_index(manager.get_capture_source_index());
 
     let res_comp = manager.capture_frame_components_with_metadata();
     if res_comp.is_err() {
@@ -974,79 +1567,109 @@ fn test_metadata_helper_methods() {
     use dxgi_capture_rs::{FrameMetadata, MoveRect};
 
     // Test with no updates
-    let metadata_empty = FrameMetadata {
-        last_present_time: 12345,
-        last_mouse_update_time: 0,
-        accumulated_frames: 1,
-        rects_coalesced: false,
-        protected_content_masked_out: false,
-        pointer_position: None,
-        pointer_visible: false,
-        dirty_rects: Vec::new(),
-        move_rects: Vec::new(),
-    };
+    let mut metadata_empty = FrameMetadata::default();
+    metadata_empty.last_present_time = 12345;
+    metadata_empty.accumulated_frames = 1;
 
     assert!(!metadata_empty.has_updates());
     assert!(!metadata_empty.has_mouse_updates());
     assert_eq!(metadata_empty.total_change_count(), 0);
 
     // Test with dirty rects
-    let metadata_dirty = FrameMetadata {
-        last_present_time: 12345,
-        last_mouse_update_time: 6789,
-        accumulated_frames: 1,
-        rects_coalesced: false,
-        protected_content_masked_out: false,
-        pointer_position: Some((100, 200)),
-        pointer_visible: true,
-        dirty_rects: vec![(0, 0, 100, 100), (200, 200, 300, 300)],
-        move_rects: Vec::new(),
-    };
+    let mut metadata_dirty = FrameMetadata::default();
+    metadata_dirty.last_present_time = 12345;
+    metadata_dirty.last_mouse_update_time = 6789;
+    metadata_dirty.accumulated_frames = 1;
+    metadata_dirty.metadata_available = true;
+    metadata_dirty.pointer_position = Some((100, 200));
+    metadata_dirty.pointer_visible = true;
+    metadata_dirty.dirty_rects = vec![Rect::new(0, 0, 100, 100), Rect::new(200, 200, 300, 300)];
 
     assert!(metadata_dirty.has_updates());
     assert!(metadata_dirty.has_mouse_updates());
     assert_eq!(metadata_dirty.total_change_count(), 2);
 
     // Test with move rects
-    let metadata_move = FrameMetadata {
-        last_present_time: 12345,
-        last_mouse_update_time: 0,
-        accumulated_frames: 1,
-        rects_coalesced: false,
-        protected_content_masked_out: false,
-        pointer_position: None,
-        pointer_visible: false,
-        dirty_rects: Vec::new(),
-        move_rects: vec![MoveRect {
-            source_point: (50, 50),
-            destination_rect: (100, 100, 150, 150),
-        }],
-    };
+    let mut metadata_move = FrameMetadata::default();
+    metadata_move.last_present_time = 12345;
+    metadata_move.accumulated_frames = 1;
+    metadata_move.metadata_available = true;
+    metadata_move.move_rects = vec![MoveRect {
+        source_point: (50, 50),
+        destination_rect: Rect::new(100, 100, 150, 150),
+    }];
 
     assert!(metadata_move.has_updates());
     assert!(!metadata_move.has_mouse_updates());
     assert_eq!(metadata_move.total_change_count(), 1);
 
     // Test with both
-    let metadata_both = FrameMetadata {
-        last_present_time: 12345,
-        last_mouse_update_time: 6789,
-        accumulated_frames: 2,
-        rects_coalesced: true,
-        protected_content_masked_out: false,
-        pointer_position: Some((150, 250)),
-        pointer_visible: true,
-        dirty_rects: vec![(0, 0, 100, 100)],
-        move_rects: vec![MoveRect {
-            source_point: (50, 50),
-            destination_rect: (100, 100, 150, 150),
-        }],
-    };
+    let mut metadata_both = FrameMetadata::default();
+    metadata_both.last_present_time = 12345;
+    metadata_both.last_mouse_update_time = 6789;
+    metadata_both.accumulated_frames = 2;
+    metadata_both.rects_coalesced = true;
+    metadata_both.metadata_available = true;
+    metadata_both.pointer_position = Some((150, 250));
+    metadata_both.pointer_visible = true;
+    metadata_both.dirty_rects = vec![Rect::new(0, 0, 100, 100)];
+    metadata_both.move_rects = vec![MoveRect {
+        source_point: (50, 50),
+        destination_rect: Rect::new(100, 100, 150, 150),
+    }];
 
     assert!(metadata_both.has_updates());
     assert!(metadata_both.has_mouse_updates());
+    assert!(!metadata_both.is_mouse_only_update());
     assert_eq!(metadata_both.total_change_count(), 2);
 
+    // Test mouse-only update
+    let mut metadata_mouse_only = FrameMetadata::default();
+    metadata_mouse_only.last_mouse_update_time = 6789;
+    metadata_mouse_only.pointer_position = Some((10, 20));
+    metadata_mouse_only.pointer_visible = true;
+
+    assert!(!metadata_mouse_only.has_updates());
+    assert!(metadata_mouse_only.has_mouse_updates());
+    assert!(metadata_mouse_only.is_mouse_only_update());
+    assert!(!metadata_empty.is_mouse_only_update());
+
+    assert!(metadata_empty.is_desktop_update());
+    assert!(metadata_both.is_desktop_update());
+    assert!(!metadata_mouse_only.is_desktop_update());
+
+    let summary = metadata_both.summary();
+    assert_eq!(summary.present_time, 12345);
+    assert_eq!(summary.accumulated_frames, 2);
+    assert_eq!((summary.dirty_count, summary.move_count), (1, 1));
+    assert!(summary.pointer_visible);
+    assert!(summary.rects_coalesced);
+    assert_eq!(metadata_mouse_only.summary().present_time, 0);
+
+    // Coalesced or missing rectangles call for a full-frame update
+    assert!(metadata_both.requires_full_update());
+    assert!(metadata_empty.requires_full_update());
+    assert!(!metadata_dirty.requires_full_update());
+    assert!(!metadata_move.requires_full_update());
+    assert!(!metadata_mouse_only.requires_full_update());
+    let mut metadata_truncated = metadata_dirty.clone();
+    metadata_truncated.rects_truncated = true;
+    assert!(metadata_truncated.requires_full_update());
+
+    assert_eq!(metadata_empty.change_bounds(), None);
+    assert_eq!(
+        metadata_dirty.change_bounds(),
+        Some(Rect::new(0, 0, 300, 300))
+    );
+    assert_eq!(
+        metadata_move.change_bounds(),
+        Some(Rect::new(100, 100, 150, 150))
+    );
+    assert_eq!(
+        metadata_both.change_bounds(),
+        Some(Rect::new(0, 0, 150, 150))
+    );
+
     println!("Metadata helper methods test passed");
 }
 
@@ -1094,8 +1717,10 @@ fn test_metadata_error_handling() {
 
     manager.set_timeout_ms(1000);
 
-    manager.set_capture_source_index(1);
-    let result = manager.capture_frame_with_metadata();
+    let result = manager
+        .set_capture_source_index(1)
+        .map_err(CaptureError::RefreshFailure)
+        .and_then(|()| manager.capture_frame_with_metadata());
     match result {
         Ok((_, _, metadata)) => {
             let change_count = metadata.total_change_count();
@@ -1111,7 +1736,7 @@ fn test_metadata_error_handling() {
         }
     }
 
-    manager.set_capture_source_index(0);
+    let _ = manager.set_capture_source_index(0);
 
     println!("Metadata error handling test completed");
 }
@@ -1122,11 +1747,11 @@ fn test_move_rect_structure() {
 
     let move_rect = MoveRect {
         source_point: (100, 200),
-        destination_rect: (150, 250, 300, 400),
+        destination_rect: (150, 250, 300, 400).into(),
     };
 
     assert_eq!(move_rect.source_point, (100, 200));
-    assert_eq!(move_rect.destination_rect, (150, 250, 300, 400));
+    assert_eq!(move_rect.destination_rect, Rect::new(150, 250, 300, 400));
 
     // Test copy and debug
     let copied = move_rect;
@@ -1190,3 +1815,3054 @@ fn test_metadata_performance_impact() {
 
     println!("Metadata performance test completed");
 }
+
+#[test]
+fn test_capture_frame_scaled() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping scaled capture test");
+            return;
+        }
+    };
+
+    let (full_width, full_height) = manager.geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping scaled capture test");
+        return;
+    };
+
+    match manager.capture_frame_scaled(0.5) {
+        Ok((pixels, (width, height))) => {
+            assert_eq!(width, (full_width / 2).max(1));
+            assert_eq!(height, (full_height / 2).max(1));
+            // Each pixel of the half-size mip averages a 2x2 block
+            let expected: Vec<BGRA8> = (0..width * height)
+                .map(|i| {
+                    let (x, y) = (i % width * 2, i / width * 2);
+                    let block = [
+                        reference[y * full_width + x],
+                        reference[y * full_width + x + 1],
+                        reference[(y + 1) * full_width + x],
+                        reference[(y + 1) * full_width + x + 1],
+                    ];
+                    let mean = |c: fn(&BGRA8) -> u8| {
+                        (block.iter().map(|p| u32::from(c(p))).sum::<u32>() / 4) as u8
+                    };
+                    BGRA8 {
+                        b: mean(|p| p.b),
+                        g: mean(|p| p.g),
+                        r: mean(|p| p.r),
+                        a: mean(|p| p.a),
+                    }
+                })
+                .collect();
+            assert_mostly(
+                &pixels,
+                &expected,
+                |a, b| a.b.abs_diff(b.b) <= 2 && a.g.abs_diff(b.g) <= 2 && a.r.abs_diff(b.r) <= 2,
+                "scaled frame",
+            );
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Scaled capture timed out - acceptable in test environment");
+        }
+        Err(e) => {
+            println!("Scaled capture failed: {e:?}");
+        }
+    }
+
+    for scale in [0.0, -1.0, 1.5, f32::NAN] {
+        assert!(
+            matches!(
+                manager.capture_frame_scaled(scale),
+                Err(CaptureError::Fail(_))
+            ),
+            "Scale {scale} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn test_auto_recover() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping auto-recover test");
+            return;
+        }
+    };
+
+    assert!(
+        !manager.get_auto_recover(),
+        "Auto-recover should be off by default"
+    );
+
+    manager.set_auto_recover(true);
+    assert!(manager.get_auto_recover());
+
+    // Force a re-acquisition and make sure capture still succeeds
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping auto-recover test");
+        return;
+    };
+    match manager.capture_frame() {
+        Ok((pixels, _)) => assert_mostly_equal(&pixels, &reference, "auto-recover frame"),
+        Err(CaptureError::Timeout) => println!("Capture timed out - acceptable"),
+        Err(e) => panic!("Capture with auto-recover failed: {e:?}"),
+    }
+
+    manager.set_auto_recover(false);
+    assert!(!manager.get_auto_recover());
+}
+
+#[test]
+fn test_fullscreen_exclusive_heuristic() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping fullscreen exclusive test");
+            return;
+        }
+    };
+
+    assert!(!manager.is_output_in_fullscreen_exclusive());
+
+    // A capture that succeeds or times out proves the duplication works
+    match manager.capture_frame() {
+        Ok(_) | Err(CaptureError::Timeout) => {
+            assert!(!manager.is_output_in_fullscreen_exclusive())
+        }
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_current_mode() {
+    let manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping display mode test");
+            return;
+        }
+    };
+
+    let mode = manager
+        .current_mode()
+        .expect("Display mode should be available");
+    assert!(mode.width > 0 && mode.height > 0);
+
+    let refresh_rate = mode.refresh_rate_hz();
+    assert!(refresh_rate >= 0.0);
+    if mode.refresh_denominator != 0 {
+        assert!(refresh_rate > 0.0, "Known refresh rate should be positive");
+    }
+
+    assert!(
+        matches!(
+            mode.rotation,
+            Rotation::Identity | Rotation::Rotate90 | Rotation::Rotate180 | Rotation::Rotate270
+        ),
+        "Rotation should be resolved to a concrete value"
+    );
+    if mode.reported_rotation == Rotation::Unspecified {
+        println!(
+            "Driver reported no rotation, resolved to {:?}",
+            mode.rotation
+        );
+    } else {
+        assert_eq!(mode.rotation, mode.reported_rotation);
+    }
+
+    println!("Display mode: {mode:?} ({refresh_rate:.2} Hz)");
+}
+
+#[test]
+fn test_current_format() {
+    use windows::Win32::Graphics::Dxgi::Common::{
+        DXGI_FORMAT_R8G8B8A8_UNORM, DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+    };
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping format test");
+            return;
+        }
+    };
+
+    let format = manager
+        .current_format()
+        .expect("Format should be available");
+    println!("Desktop image format: {format:?}");
+
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping format test");
+        return;
+    };
+
+    // Copies are BGRA whatever the output's channel order
+    match manager.capture_frame_components() {
+        Ok((components, (width, height))) => {
+            let pixels = mapped_quads(&components, width * 4, width, height);
+            assert_mostly_equal(&pixels, &bgra_quads(&reference), "components");
+            if matches!(
+                format,
+                DXGI_FORMAT_R8G8B8A8_UNORM | DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+            ) {
+                println!("RGBA output - captures were converted to BGRA");
+            }
+        }
+        Err(CaptureError::Timeout) => println!("Capture timed out"),
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_display_mode_refresh_rate() {
+    use dxgi_capture_rs::{DisplayMode, Rotation};
+
+    let mut mode = DisplayMode::default();
+    mode.width = 1920;
+    mode.height = 1080;
+    mode.refresh_numerator = 60000;
+    mode.refresh_denominator = 1001;
+    mode.rotation = Rotation::Identity;
+    mode.reported_rotation = Rotation::Unspecified;
+    assert!((mode.refresh_rate_hz() - 59.94).abs() < 0.01);
+
+    let mut unknown = mode;
+    unknown.refresh_numerator = 0;
+    unknown.refresh_denominator = 0;
+    assert_eq!(unknown.refresh_rate_hz(), 0.0);
+}
+
+#[test]
+fn test_outputs_and_mirrors() {
+    let manager = match DXGIManager::builder().skip_mirrored_outputs(true).build() {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping outputs test");
+            return;
+        }
+    };
+    assert!(manager.get_skip_mirrored_outputs());
+
+    let outputs = manager.outputs().expect("Outputs should be enumerable");
+    assert_eq!(outputs.len(), manager.output_count());
+    for (index, output) in outputs.iter().enumerate() {
+        assert_eq!(output.index, index);
+        assert!(!output.device_name.is_empty());
+        if let Some(primary) = output.mirror_of {
+            assert!(primary < index, "A mirror must refer to an earlier output");
+            assert_eq!(outputs[primary].desktop_rect, output.desktop_rect);
+            assert!(!outputs[primary].is_mirror());
+        }
+    }
+
+    let capturers = match manager.split() {
+        Ok(capturers) => capturers,
+        Err(e) => {
+            println!("Split failed: {e:?}");
+            return;
+        }
+    };
+    let expected: Vec<usize> = outputs
+        .iter()
+        .filter(|output| !output.is_mirror())
+        .map(|output| output.index)
+        .collect();
+    let indices: Vec<usize> = capturers.iter().map(|capturer| capturer.index()).collect();
+    assert_eq!(indices, expected);
+}
+
+#[test]
+fn test_run_capture_loop() {
+    use std::ops::ControlFlow;
+
+    let manager = match DXGIManager::new(100) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping capture loop test");
+            return;
+        }
+    };
+
+    let geometry = manager.frame_geometry();
+    let capture = manager.run(move |pixels, (width, height), _| {
+        assert_eq!((width, height), geometry);
+        assert_eq!(pixels.len(), width * height);
+        ControlFlow::Break(())
+    });
+
+    // Stopping ends the loop even if no frame ever arrives
+    capture.stop();
+    let result = capture.join().expect("Capture thread should not panic");
+    assert!(result.is_ok(), "Capture loop failed: {result:?}");
+
+    // The manager was dropped with the thread, so the output is free again
+    assert!(
+        DXGIManager::new(100).is_ok(),
+        "Should be able to create a manager after the loop finished"
+    );
+}
+
+#[test]
+fn test_capture_session() {
+    use dxgi_capture_rs::CaptureSession;
+    use std::time::Duration;
+
+    let mut session = match CaptureSession::new(DXGIManager::builder().timeout_ms(100)) {
+        Ok(s) => s,
+        Err(_) => {
+            println!("DXGI not available - skipping capture session test");
+            return;
+        }
+    };
+
+    assert_eq!(session.get_max_attempts(), 10);
+    assert_eq!(
+        session.get_backoff(),
+        (Duration::from_millis(100), Duration::from_secs(5))
+    );
+    session.set_backoff(Duration::from_millis(10), Duration::from_millis(50));
+    session.set_max_attempts(3);
+    assert_eq!(
+        session.get_backoff(),
+        (Duration::from_millis(10), Duration::from_millis(50))
+    );
+    assert_eq!(session.get_max_attempts(), 3);
+    assert_eq!(session.manager().get_timeout_ms(), 100);
+    session.on_recover(|attempts, err| println!("Recovered from {err} after {attempts} attempts"));
+
+    match session.next_frame() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!((width, height), session.manager().frame_geometry());
+            assert_eq!(pixels.len(), width * height);
+        }
+        Err(CaptureError::Timeout) => println!("Session capture timed out (no screen changes)"),
+        Err(e) => println!("Session capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_rgba() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping RGBA capture test");
+            return;
+        }
+    };
+
+    let Some((bgra, (w_bgra, h_bgra))) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping RGBA test");
+        return;
+    };
+
+    let (rgba, (w_rgba, h_rgba)) = match manager.capture_frame_rgba() {
+        Ok(frame) => frame,
+        Err(e) => {
+            println!("RGBA capture failed: {e:?}");
+            return;
+        }
+    };
+
+    assert_eq!((w_bgra, h_bgra), (w_rgba, h_rgba));
+    assert_eq!(rgba.len(), w_rgba * h_rgba * 4);
+
+    let swapped: Vec<[u8; 4]> = bgra.iter().map(|p| [p.r, p.g, p.b, p.a]).collect();
+    let rgba: Vec<[u8; 4]> = rgba
+        .chunks_exact(4)
+        .map(|p| [p[0], p[1], p[2], p[3]])
+        .collect();
+    assert_mostly_equal(&rgba, &swapped, "RGBA frame");
+}
+
+#[test]
+fn test_capture_virtual_desktop() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping virtual desktop test");
+            return;
+        }
+    };
+
+    let (primary_width, primary_height) = manager.geometry();
+    let clear_color = BGRA8 {
+        b: 1,
+        g: 2,
+        r: 3,
+        a: 4,
+    };
+    manager.set_clear_color(clear_color);
+    assert_eq!(manager.get_clear_color(), clear_color);
+    let outputs = manager.outputs().expect("Outputs should be enumerable");
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping virtual desktop test");
+        return;
+    };
+
+    match manager.capture_virtual_desktop() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!(pixels.len(), width * height);
+            assert!(
+                width >= primary_width && height >= primary_height,
+                "Virtual desktop should cover the primary display"
+            );
+            // The primary display appears at its place in the virtual desktop
+            let left = outputs.iter().map(|o| o.desktop_rect.left).min().unwrap();
+            let top = outputs.iter().map(|o| o.desktop_rect.top).min().unwrap();
+            let primary = outputs[0].desktop_rect;
+            let expected = crop(
+                &pixels,
+                width,
+                (primary.left - left) as usize,
+                (primary.top - top) as usize,
+                primary_width,
+                primary_height,
+            );
+            assert_mostly_equal(&expected, &reference, "primary display in virtual desktop");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Virtual desktop capture timed out - acceptable in test environment");
+        }
+        Err(e) => {
+            println!("Virtual desktop capture failed: {e:?}");
+        }
+    }
+
+    // Switching back to single-source capture must still work
+    assert!(
+        manager.capture_frame().is_ok(),
+        "Capture should work after virtual desktop capture"
+    );
+    assert_eq!(manager.geometry(), (primary_width, primary_height));
+}
+
+#[test]
+fn test_peek_metadata() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping peek metadata test");
+            return;
+        }
+    };
+
+    match manager.peek_metadata() {
+        Ok(metadata) => {
+            assert_eq!(
+                metadata.total_change_count(),
+                metadata.dirty_rects.len() + metadata.move_rects.len()
+            );
+        }
+        Err(CaptureError::Timeout) => println!("Peek timed out - acceptable"),
+        Err(e) => panic!("Peek failed: {e:?}"),
+    }
+
+    // The peeked frame must have been released, otherwise the next acquire fails
+    for _ in 0..3 {
+        match manager.capture_frame() {
+            Ok(_) | Err(CaptureError::Timeout) => {}
+            Err(e) => panic!("Capture after peek failed: {e:?}"),
+        }
+        match manager.peek_metadata() {
+            Ok(_) | Err(CaptureError::Timeout) => {}
+            Err(e) => panic!("Repeated peek failed: {e:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_capture_frame_with() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping zero-copy capture test");
+            return;
+        }
+    };
+
+    let (geo_width, geo_height) = manager.geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping zero-copy capture test");
+        return;
+    };
+
+    match manager.capture_frame_with(|data, pitch, width, height| {
+        assert!(pitch >= width * 4, "Pitch must cover a full row of pixels");
+        assert_eq!(data.len(), pitch * height);
+        (mapped_quads(data, pitch, width, height), width, height)
+    }) {
+        Ok((pixels, width, height)) => {
+            assert_eq!(
+                width * height,
+                geo_width * geo_height,
+                "Mapped surface should cover the whole output"
+            );
+            if is_unrotated(&manager) {
+                assert_mostly_equal(&pixels, &bgra_quads(&reference), "mapped frame");
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Zero-copy capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Zero-copy capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_output_dpi() {
+    let manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping output DPI test");
+            return;
+        }
+    };
+
+    match manager.output_dpi() {
+        Ok((dpi_x, dpi_y)) => {
+            assert!(dpi_x > 0, "Horizontal DPI should be positive");
+            assert!(dpi_y > 0, "Vertical DPI should be positive");
+        }
+        Err(e) => println!("Output DPI query failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_color_space() {
+    use dxgi_capture_rs::ColorSpace;
+
+    assert!(!ColorSpace::Srgb.is_hdr());
+    assert!(ColorSpace::ScRgb.is_hdr());
+    assert!(ColorSpace::Hdr10.is_hdr());
+
+    let manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping color space test");
+            return;
+        }
+    };
+
+    match manager.color_space() {
+        Ok(color_space) => println!("Output color space: {color_space:?}"),
+        Err(e) => println!("Color space query failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_hdr_metadata() {
+    let manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping HDR metadata test");
+            return;
+        }
+    };
+
+    let hdr = manager.hdr_metadata();
+    if let Ok(color_space) = manager.color_space()
+        && !color_space.is_hdr()
+    {
+        assert!(hdr.is_none(), "SDR outputs should report no HDR metadata");
+    }
+    if let Some(hdr) = hdr {
+        assert!(hdr.min_luminance <= hdr.max_luminance);
+        println!("HDR metadata: {hdr:?}");
+    }
+}
+
+#[test]
+fn test_output_hmonitor() {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::Graphics::Gdi::{MONITOR_DEFAULTTONULL, MonitorFromPoint};
+
+    let manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping monitor handle test");
+            return;
+        }
+    };
+
+    let monitor = manager
+        .output_hmonitor()
+        .expect("The capture source should have a monitor");
+    assert!(!monitor.is_invalid());
+
+    // The primary display contains the origin of the virtual desktop
+    let primary = unsafe { MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTONULL) };
+    println!(
+        "Capture source monitor {monitor:?} is primary: {}",
+        monitor == primary
+    );
+}
+
+#[test]
+fn test_is_secure_desktop() {
+    use dxgi_capture_rs::is_secure_desktop;
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping secure desktop test");
+            return;
+        }
+    };
+
+    // A desktop switch is only reported while the secure desktop is active
+    println!("Secure desktop active: {:?}", is_secure_desktop());
+    match manager.capture_frame() {
+        Err(CaptureError::DesktopSwitch) => assert_eq!(is_secure_desktop(), Some(true)),
+        Ok(_) => {}
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_remote_session_error() {
+    use dxgi_capture_rs::{OutputDuplicationError, is_remote_session};
+
+    println!("Remote session: {}", is_remote_session());
+    match DXGIManager::new(1000) {
+        Ok(_) => {}
+        Err(OutputDuplicationError::UnsupportedSession) => assert!(is_remote_session()),
+        Err(e) => println!("DXGI not available - {e}"),
+    }
+}
+
+#[test]
+fn test_wait_for_frame() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping wait for frame test");
+            return;
+        }
+    };
+
+    match manager.wait_for_frame(std::time::Duration::from_millis(500)) {
+        Ok(FrameStatus::Changed(metadata)) => {
+            assert!(
+                metadata.has_updates(),
+                "Changed frames should carry dirty or move rects"
+            );
+        }
+        Ok(FrameStatus::Unchanged) => println!("Frame arrived without changes"),
+        Err(CaptureError::Timeout) => println!("No frame arrived within the timeout"),
+        Err(e) => println!("Wait for frame failed: {e:?}"),
+    }
+
+    assert_eq!(
+        manager.get_timeout_ms(),
+        1000,
+        "Waiting must not change the configured timeout"
+    );
+}
+
+#[test]
+fn test_capture_layers() {
+    use dxgi_capture_rs::PointerShapeType;
+
+    let mut manager = match DXGIManager::new(300) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping layers test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping layers test");
+        return;
+    };
+    // The desktop layer never contains the pointer
+    manager.set_composite_cursor(true);
+
+    match manager.capture_layers() {
+        Ok(layers) => {
+            if let Some((pixels, (width, height))) = &layers.desktop {
+                assert_eq!((*width, *height), manager.frame_geometry());
+                assert_mostly_equal(pixels, &reference, "desktop layer");
+            }
+            if let Some((shape, position)) = &layers.cursor {
+                let bits_per_row = match shape.shape_type {
+                    PointerShapeType::Monochrome => shape.width,
+                    PointerShapeType::Color | PointerShapeType::MaskedColor => shape.width * 32,
+                };
+                assert!(shape.pitch * 8 >= bits_per_row);
+                assert!(shape.data.len() >= (shape.pitch * shape.height) as usize);
+                println!("Pointer {:?} at {position:?}", shape.shape_type);
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Layers capture timed out (acceptable in tests)");
+        }
+        Err(e) => println!("Layers capture failed with error: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_on_change() {
+    use std::time::{Duration, Instant};
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping capture on change test");
+            return;
+        }
+    };
+
+    let start = Instant::now();
+    match manager.capture_on_change(std::time::Duration::from_millis(500)) {
+        Ok((pixels, (width, height), metadata)) => {
+            assert_eq!(pixels.len(), width * height);
+            assert!(metadata.has_updates(), "Only changed frames are returned");
+        }
+        Err(CaptureError::Timeout) => {
+            // The deadline covers the whole wait, not each skipped frame
+            assert!(start.elapsed() < Duration::from_secs(2));
+        }
+        Err(e) => println!("Capture on change failed: {e:?}"),
+    }
+
+    // No frame is left acquired, so a regular capture still works
+    match manager.capture_frame() {
+        Ok(_) | Err(CaptureError::Timeout) => {}
+        Err(e) => println!("Capture after capture on change failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_rect() {
+    let rect = Rect::new(10, 20, 30, 60);
+    assert_eq!(rect.width(), 20);
+    assert_eq!(rect.height(), 40);
+    assert!(!rect.is_empty());
+
+    // Right and bottom edges are exclusive
+    assert!(rect.contains(10, 20));
+    assert!(rect.contains(29, 59));
+    assert!(!rect.contains(30, 59));
+    assert!(!rect.contains(29, 60));
+    assert!(!rect.contains(9, 20));
+
+    assert_eq!(
+        rect.intersect(&Rect::new(0, 0, 15, 25)),
+        Some(Rect::new(10, 20, 15, 25))
+    );
+    assert_eq!(rect.intersect(&rect), Some(rect));
+    // Touching edges do not overlap
+    assert_eq!(rect.intersect(&Rect::new(30, 20, 40, 60)), None);
+    assert_eq!(rect.intersect(&Rect::new(100, 100, 200, 200)), None);
+
+    let inverted = Rect::new(30, 30, 20, 40);
+    assert_eq!(inverted.width(), 0);
+    assert!(inverted.is_empty());
+    assert_eq!(inverted.intersect(&Rect::new(0, 0, 100, 100)), None);
+
+    assert_eq!(
+        rect.union(&Rect::new(100, 0, 110, 25)),
+        Rect::new(10, 0, 110, 60)
+    );
+    assert_eq!(rect.union(&rect), rect);
+
+    let tuple: (i32, i32, i32, i32) = rect.into();
+    assert_eq!(tuple, (10, 20, 30, 60));
+    assert_eq!(Rect::from(tuple), rect);
+}
+
+#[test]
+fn test_compute_dirty_rects() {
+    let black = BGRA8 {
+        b: 0,
+        g: 0,
+        r: 0,
+        a: 255,
+    };
+    let white = BGRA8 {
+        b: 255,
+        g: 255,
+        r: 255,
+        a: 255,
+    };
+    let (width, height) = (10, 10);
+    let prev = vec![black; width * height];
+
+    // Identical frames have no changes
+    assert!(compute_dirty_rects(&prev, &prev, width, height, 4).is_empty());
+
+    // A single pixel marks its whole tile, clamped at the frame edge
+    let mut cur = prev.clone();
+    cur[9 * width + 9] = white;
+    assert_eq!(
+        compute_dirty_rects(&prev, &cur, width, height, 4),
+        vec![Rect::new(8, 8, 10, 10)]
+    );
+
+    // Adjacent tiles merge horizontally and vertically
+    let mut cur = prev.clone();
+    cur[0] = white;
+    cur[5] = white;
+    cur[5 * width] = white;
+    cur[5 * width + 5] = white;
+    assert_eq!(
+        compute_dirty_rects(&prev, &cur, width, height, 4),
+        vec![Rect::new(0, 0, 8, 8)]
+    );
+
+    // Tiles that are not adjacent stay separate
+    let mut cur = prev.clone();
+    cur[0] = white;
+    cur[9 * width + 9] = white;
+    assert_eq!(
+        compute_dirty_rects(&prev, &cur, width, height, 4),
+        vec![Rect::new(0, 0, 4, 4), Rect::new(8, 8, 10, 10)]
+    );
+
+    // A tile size of one reports exact pixels
+    let mut cur = prev.clone();
+    cur[3 * width + 2] = white;
+    assert_eq!(
+        compute_dirty_rects(&prev, &cur, width, height, 1),
+        vec![Rect::new(2, 3, 3, 4)]
+    );
+}
+
+#[test]
+fn test_manager_builder() {
+    let manager = match DXGIManagerBuilder::new()
+        .timeout_ms(250)
+        .auto_recover(true)
+        .build()
+    {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping builder test");
+            return;
+        }
+    };
+
+    assert_eq!(manager.get_timeout_ms(), 250);
+    assert!(manager.get_auto_recover());
+    assert_eq!(manager.get_capture_source_index(), 0);
+}
+
+#[test]
+fn test_logger() {
+    use dxgi_capture_rs::LogLevel;
+    use std::sync::{Arc, Mutex};
+
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let sink = messages.clone();
+    let mut manager = match DXGIManager::builder()
+        .logger(move |level, message| sink.lock().unwrap().push((level, message.to_owned())))
+        .build()
+    {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping logger test");
+            return;
+        }
+    };
+
+    // A successful duplication is reported at debug level
+    assert!(
+        messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(level, message)| *level == LogLevel::Debug && message.contains("Duplicated"))
+    );
+
+    manager.clear_logger();
+    messages.lock().unwrap().clear();
+    assert!(manager.acquire_output_duplication().is_ok());
+    assert!(messages.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_output_order() {
+    use dxgi_capture_rs::PixelOrder;
+
+    let mut manager = match DXGIManagerBuilder::new()
+        .output_order(PixelOrder::Rgba)
+        .build()
+    {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping output order test");
+            return;
+        }
+    };
+    assert_eq!(manager.get_output_order(), PixelOrder::Rgba);
+    // capture_frame always returns BGRA8, whatever the output order
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping output order test");
+        return;
+    };
+
+    match manager.capture_frame_components() {
+        Ok((components, (width, height))) => {
+            let pixels = mapped_quads(&components, width * 4, width, height);
+            let swapped: Vec<[u8; 4]> = reference.iter().map(|p| [p.r, p.g, p.b, p.a]).collect();
+            assert_mostly_equal(&pixels, &swapped, "RGBA components");
+        }
+        Err(CaptureError::Timeout) => println!("Capture timed out"),
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+
+    manager.set_output_order(PixelOrder::Bgra);
+    assert_eq!(manager.get_output_order(), PixelOrder::Bgra);
+    assert_eq!(PixelOrder::default(), PixelOrder::Bgra);
+}
+
+#[test]
+fn test_capture_frame_view() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping typed view capture test");
+            return;
+        }
+    };
+
+    let (geo_width, geo_height) = manager.geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping typed view capture test");
+        return;
+    };
+
+    match manager.capture_frame_view(|pixels, stride, width, height| {
+        assert!(stride >= width, "Stride must cover a full row of pixels");
+        assert_eq!(pixels.len(), stride * height);
+        (crop(pixels, stride, 0, 0, width, height), width, height)
+    }) {
+        Ok((pixels, width, height)) => {
+            assert_eq!(
+                width * height,
+                geo_width * geo_height,
+                "Mapped surface should cover the whole output"
+            );
+            if is_unrotated(&manager) {
+                assert_mostly_equal(&pixels, &reference, "typed view");
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Typed view capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Typed view capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_region_with() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping region map test");
+            return;
+        }
+    };
+
+    let (width, height) = manager.output_geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping region map test");
+        return;
+    };
+
+    // The region is clipped to the desktop image
+    let region = Rect::new(-10, 0, 64, height as i32 + 100);
+    match manager.capture_region_with(region, |data, pitch, width, height| {
+        assert!(pitch >= width * 4, "Pitch must cover a full row");
+        assert_eq!(data.len(), pitch * height);
+        (mapped_quads(data, pitch, width, height), (width, height))
+    }) {
+        Ok((pixels, dimensions)) => {
+            assert_eq!(dimensions, (64.min(width), height));
+            if is_unrotated(&manager) {
+                let expected = crop(&reference, width, 0, 0, dimensions.0, height);
+                assert_mostly_equal(&pixels, &bgra_quads(&expected), "mapped region");
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Region map timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Region map failed: {e:?}"),
+    }
+
+    // A region entirely outside the image is rejected without capturing
+    let outside = Rect::new(width as i32, 0, width as i32 + 10, 10);
+    assert!(matches!(
+        manager.capture_region_with(outside, |_, _, _, _| ()),
+        Err(CaptureError::Fail(_))
+    ));
+}
+
+#[test]
+fn test_capture_frame_with_mut() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping writable capture test");
+            return;
+        }
+    };
+
+    // Write access has to be requested up front
+    assert!(matches!(
+        manager.capture_frame_with_mut(|_, _, _, _| ()),
+        Err(CaptureError::Fail(_))
+    ));
+    drop(manager);
+
+    let mut manager = match DXGIManager::builder().cpu_write_access(true).build() {
+        Ok(m) => m,
+        Err(e) => {
+            println!("Failed to build manager with write access: {e:?}");
+            return;
+        }
+    };
+
+    match manager.capture_frame_with_mut(|data, pitch, width, _| {
+        data[..width * 4].fill(0x7f);
+        assert!(pitch >= width * 4);
+        data[..width * 4].iter().all(|&b| b == 0x7f)
+    }) {
+        Ok(written) => assert!(written, "Writes should be visible in the mapped frame"),
+        Err(CaptureError::Timeout) => {
+            println!("Writable capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Writable capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_luma() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping luma capture test");
+            return;
+        }
+    };
+
+    let (width, height) = manager.geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping luma capture test");
+        return;
+    };
+
+    match manager.capture_frame_luma() {
+        Ok((luma, (luma_width, luma_height))) => {
+            assert_eq!(luma.len(), luma_width * luma_height);
+            assert_eq!(
+                luma_width * luma_height,
+                width * height,
+                "Luma frame should cover the whole output"
+            );
+            // BT.601 weights in 8-bit fixed point
+            let expected: Vec<u8> = reference
+                .iter()
+                .map(|p| {
+                    let (r, g, b) = (u32::from(p.r), u32::from(p.g), u32::from(p.b));
+                    ((77 * r + 150 * g + 29 * b + 128) >> 8) as u8
+                })
+                .collect();
+            assert_mostly_equal(&luma, &expected, "luma frame");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Luma capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Luma capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_qpc_to_duration() {
+    use dxgi_capture_rs::FrameMetadata;
+    use std::time::Duration;
+
+    assert_eq!(qpc_to_duration(0), Duration::ZERO);
+    assert_eq!(qpc_to_duration(-1), Duration::ZERO);
+    assert!(qpc_to_duration(1) > Duration::ZERO);
+    assert!(qpc_to_duration(2_000_000) > qpc_to_duration(1_000_000));
+
+    let mut metadata = FrameMetadata::default();
+    metadata.last_present_time = 3_000_000;
+    metadata.accumulated_frames = 1;
+    assert_eq!(
+        metadata.present_time_since(1_000_000),
+        qpc_to_duration(2_000_000)
+    );
+    assert_eq!(metadata.present_time_since(4_000_000), Duration::ZERO);
+}
+
+#[test]
+fn test_capture_window_region() {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::GetDesktopWindow;
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping window region test");
+            return;
+        }
+    };
+
+    // A null handle is not on any display
+    assert!(matches!(
+        manager.capture_window_region(HWND::default()),
+        Err(CaptureError::WindowNotOnOutput)
+    ));
+
+    let (width, height) = manager.geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping window region test");
+        return;
+    };
+    match manager.capture_window_region(unsafe { GetDesktopWindow() }) {
+        Ok((pixels, (region_width, region_height))) => {
+            assert_eq!(pixels.len(), region_width * region_height);
+            assert!(region_width <= width && region_height <= height);
+            // The desktop window spans the primary display
+            if (region_width, region_height) == (width, height) {
+                assert_mostly_equal(&pixels, &reference, "desktop window region");
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Window region capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Window region capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_window_region_flipped() {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DestroyWindow, WINDOW_EX_STYLE, WS_POPUP, WS_VISIBLE,
+    };
+    use windows::core::w;
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping flipped window region test");
+            return;
+        }
+    };
+    let Some(output) = manager.outputs().ok().and_then(|o| o.into_iter().next()) else {
+        println!("No outputs available - skipping flipped window region test");
+        return;
+    };
+
+    // A borderless window whose client area covers a known part of the display
+    let desktop = output.desktop_rect;
+    let (x, y, w, h) = (64usize, 32usize, 128usize, 96usize);
+    let hwnd = match unsafe {
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("STATIC"),
+            None,
+            WS_POPUP | WS_VISIBLE,
+            desktop.left + x as i32,
+            desktop.top + y as i32,
+            w as i32,
+            h as i32,
+            None,
+            None,
+            None,
+            None,
+        )
+    } {
+        Ok(hwnd) => hwnd,
+        Err(e) => {
+            println!("Window creation failed - skipping flipped window region test: {e:?}");
+            return;
+        }
+    };
+
+    let Some((reference, (width, _))) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping flipped window region test");
+        unsafe { DestroyWindow(hwnd) }.ok();
+        return;
+    };
+    manager.set_flip_vertical(true);
+
+    match manager.capture_window_region(hwnd) {
+        Ok((pixels, dimensions)) => {
+            // A window clipped by a small display is not at a known place
+            if dimensions == (w, h) {
+                // The region holds the window's rows bottom-up
+                let expected: Vec<BGRA8> = crop(&reference, width, x, y, w, h)
+                    .rchunks_exact(w)
+                    .flatten()
+                    .copied()
+                    .collect();
+                assert_mostly_equal(&pixels, &expected, "flipped window region");
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Window region capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Flipped window region capture failed: {e:?}"),
+    }
+
+    unsafe { DestroyWindow(hwnd) }.ok();
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn test_capture_frame_async() {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    let mut manager = match DXGIManager::new(500) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping async capture test");
+            return;
+        }
+    };
+
+    let (width, height) = manager.geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping async capture test");
+        return;
+    };
+    match block_on(manager.capture_frame_async()) {
+        Ok((pixels, (frame_width, frame_height))) => {
+            assert_eq!(frame_width * frame_height, width * height);
+            assert_mostly_equal(&pixels, &reference, "async frame");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Async capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Async capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_is_protected() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping protected content test");
+            return;
+        }
+    };
+
+    assert!(
+        !manager.is_protected(),
+        "No frame has been acquired yet, so nothing is known to be protected"
+    );
+
+    match manager.capture_frame_with_metadata() {
+        Ok((_, _, metadata)) => {
+            assert_eq!(
+                manager.is_protected(),
+                metadata.protected_content_masked_out
+            );
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_fell_behind() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping fell behind test");
+            return;
+        }
+    };
+
+    assert!(!manager.fell_behind(), "No frame has been acquired yet");
+    assert_eq!(manager.last_accumulated_frames(), 0);
+
+    match manager.capture_frame_with_metadata() {
+        Ok((_, _, metadata)) => {
+            assert_eq!(
+                manager.last_accumulated_frames(),
+                metadata.accumulated_frames
+            );
+            assert_eq!(manager.fell_behind(), metadata.accumulated_frames > 1);
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+
+    // The fast path records the count as well
+    match manager.capture_frame_fast() {
+        Ok(_) => println!(
+            "Fast capture coalesced {} updates",
+            manager.last_accumulated_frames()
+        ),
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_lazy_release() {
+    let mut manager = match DXGIManager::builder()
+        .timeout_ms(1000)
+        .lazy_release(true)
+        .build()
+    {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping lazy release test");
+            return;
+        }
+    };
+
+    assert!(manager.get_lazy_release());
+
+    // Consecutive captures must release the held frame before acquiring again
+    for _ in 0..3 {
+        let Some((reference, _)) = reference_frame(&mut manager) else {
+            println!("No reference frame available - skipping lazy release test");
+            return;
+        };
+        match manager.capture_frame() {
+            Ok((pixels, _)) => assert_mostly_equal(&pixels, &reference, "lazily released frame"),
+            Err(CaptureError::Timeout) => {
+                println!("Capture timed out - this can happen if the screen is static");
+            }
+            Err(e) => println!("Capture failed: {e:?}"),
+        }
+    }
+
+    // Switching modes with a frame held must not break the next capture
+    manager.set_lazy_release(false);
+    assert!(!manager.get_lazy_release());
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping lazy release test");
+        return;
+    };
+    match manager.capture_frame_with_metadata() {
+        Ok((pixels, _, _)) => assert_mostly_equal(&pixels, &reference, "frame after lazy release"),
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+}
+
+#[test]
+#[cfg(feature = "nv12")]
+fn test_capture_frame_nv12() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping NV12 capture test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping NV12 capture test");
+        return;
+    };
+
+    match manager.capture_frame_nv12() {
+        Ok(frame) => {
+            assert_eq!(frame.y_stride % 16, 0, "Y stride should be 16-byte aligned");
+            assert_eq!(
+                frame.uv_stride % 16,
+                0,
+                "UV stride should be 16-byte aligned"
+            );
+            assert!(frame.y_stride >= frame.width);
+            assert!(frame.uv_stride >= frame.width.div_ceil(2) * 2);
+            assert_eq!(frame.y_plane.len(), frame.y_stride * frame.height);
+            assert_eq!(
+                frame.uv_plane.len(),
+                frame.uv_stride * frame.height.div_ceil(2)
+            );
+
+            let luma: Vec<u8> = (0..frame.height)
+                .flat_map(|row| &frame.y_plane[row * frame.y_stride..][..frame.width])
+                .copied()
+                .collect();
+            assert!(
+                luma.iter().all(|&y| (16..=235).contains(&y)),
+                "Luma should be limited range"
+            );
+            // Limited-range BT.709 luma in 8-bit fixed point
+            let expected: Vec<u8> = reference
+                .iter()
+                .map(|p| {
+                    let (r, g, b) = (u32::from(p.r), u32::from(p.g), u32::from(p.b));
+                    (((47 * r + 157 * g + 16 * b + 128) >> 8) + 16) as u8
+                })
+                .collect();
+            assert_mostly_equal(&luma, &expected, "NV12 luma plane");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("NV12 capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("NV12 capture failed: {e:?}"),
+    }
+}
+
+#[test]
+#[cfg(feature = "raw-dxgi")]
+fn test_capture_frame_raw_info() {
+    use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM;
+    use windows::Win32::Graphics::Dxgi::{DXGI_MAP_READ, DXGI_MAPPED_RECT};
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping raw frame info test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping raw frame info test");
+        return;
+    };
+
+    match manager.capture_frame_raw_info() {
+        Ok((surface, frame_info)) => {
+            let desc = unsafe { surface.GetDesc() }.expect("Surface should have a desc");
+            let (width, height) = manager.output_geometry();
+            assert_eq!((desc.Width as usize, desc.Height as usize), (width, height));
+            if is_unrotated(&manager) && desc.Format == DXGI_FORMAT_B8G8R8A8_UNORM {
+                let mut rect = DXGI_MAPPED_RECT::default();
+                unsafe { surface.Map(&mut rect, DXGI_MAP_READ) }.expect("Surface should map");
+                let pitch = rect.Pitch as usize;
+                let data = unsafe { std::slice::from_raw_parts(rect.pBits, pitch * height) };
+                let pixels = mapped_quads(data, pitch, width, height);
+                unsafe { surface.Unmap() }.expect("Surface should unmap");
+                assert_mostly_equal(&pixels, &bgra_quads(&reference), "raw surface");
+            }
+            println!(
+                "Accumulated frames: {}, metadata size: {}",
+                frame_info.AccumulatedFrames, frame_info.TotalMetadataBufferSize
+            );
+        }
+        Err(CaptureError::Timeout) => println!("Raw info capture timed out - acceptable"),
+        Err(e) => println!("Raw info capture failed: {e:?}"),
+    }
+}
+
+#[test]
+#[cfg(feature = "raw-dxgi")]
+fn test_capture_frame_shared_handle() {
+    use windows::Win32::Foundation::CloseHandle;
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping shared handle test");
+            return;
+        }
+    };
+
+    match manager.capture_frame_shared_handle() {
+        Ok(handle) => {
+            assert!(!handle.is_invalid());
+            unsafe { CloseHandle(handle) }.expect("Shared handle should close");
+        }
+        Err(CaptureError::Timeout) => println!("Shared handle capture timed out - acceptable"),
+        Err(e) => println!("Shared handle capture failed: {e:?}"),
+    }
+}
+
+#[test]
+#[cfg(feature = "raw-dxgi")]
+fn test_from_device() {
+    use dxgi_capture_rs::OutputDuplicationError;
+    use windows::Win32::Foundation::HMODULE;
+    use windows::Win32::Graphics::Direct3D::D3D_DRIVER_TYPE_HARDWARE;
+    use windows::Win32::Graphics::Direct3D11::{
+        D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_CREATE_DEVICE_FLAG, D3D11_SDK_VERSION,
+        D3D11CreateDevice, ID3D11Device,
+    };
+
+    let create_device = |flags: D3D11_CREATE_DEVICE_FLAG| {
+        let mut device: Option<ID3D11Device> = None;
+        unsafe {
+            D3D11CreateDevice(
+                None,
+                D3D_DRIVER_TYPE_HARDWARE,
+                HMODULE::default(),
+                flags,
+                None,
+                D3D11_SDK_VERSION,
+                Some(&mut device),
+                None,
+                None,
+            )
+        }
+        .ok()
+        .and(device)
+    };
+
+    let Some(device) = create_device(D3D11_CREATE_DEVICE_BGRA_SUPPORT) else {
+        println!("No hardware D3D11 device - skipping external device test");
+        return;
+    };
+    match DXGIManager::from_device(device, 0) {
+        Ok(mut manager) => match manager.capture_frame() {
+            Ok((pixels, (width, height))) => {
+                assert_eq!((width, height), manager.frame_geometry());
+                assert_eq!(pixels.len(), width * height);
+            }
+            Err(CaptureError::Timeout) => println!("External device capture timed out"),
+            Err(e) => println!("External device capture failed: {e:?}"),
+        },
+        Err(e) => println!("Duplication with external device failed: {e:?}"),
+    }
+
+    if let Some(device) = create_device(D3D11_CREATE_DEVICE_FLAG(0)) {
+        assert!(matches!(
+            DXGIManager::from_device(device, 0),
+            Err(OutputDuplicationError::DeviceError(_))
+        ));
+    }
+}
+
+#[test]
+#[cfg(feature = "record")]
+fn test_record_to() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping recording test");
+            return;
+        }
+    };
+
+    let path = std::env::temp_dir().join(format!("dxgi-record-{}.y4m", std::process::id()));
+    let result = manager.record_to(&path, 10, std::time::Duration::from_millis(500));
+    assert_eq!(manager.get_timeout_ms(), 1000, "Timeout should be restored");
+
+    match result {
+        Ok(stats) => {
+            assert_eq!(stats.frames_written, 5);
+            assert!(stats.frames_repeated + stats.frames_dropped < stats.frames_written);
+
+            let data = std::fs::read(&path).expect("Recording should exist");
+            assert!(data.starts_with(b"YUV4MPEG2 W"));
+            let header_end = data.iter().position(|&b| b == b'\n').unwrap();
+            let header = std::str::from_utf8(&data[..header_end]).unwrap();
+            assert!(header.contains(" F10:1 "), "Unexpected header: {header}");
+
+            let frames = data.windows(6).filter(|w| w == b"FRAME\n").count();
+            assert!(frames >= 5);
+        }
+        Err(e) => println!("Recording failed: {e}"),
+    }
+    let _ = std::fs::remove_file(&path);
+
+    let error = manager
+        .record_to(&path, 0, std::time::Duration::from_secs(1))
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_capture_frame_fast_rotated() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping rotated fast capture test");
+            return;
+        }
+    };
+
+    let Some((reference, (expected_width, expected_height))) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping rotated fast capture test");
+        return;
+    };
+
+    // The rotated fast path always matches the rotation-aware capture. On a display
+    // rotated by 90 or 270 degrees, capture_frame_fast instead returns the native
+    // orientation with width and height swapped.
+    match manager.capture_frame_fast_rotated() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!((width, height), (expected_width, expected_height));
+            let pixels = mapped_quads(&pixels, width * 4, width, height);
+            assert_mostly_equal(&pixels, &bgra_quads(&reference), "rotated fast frame");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Rotated fast capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Rotated fast capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_write() {
+    use std::io;
+
+    struct BrokenPipe;
+
+    impl io::Write for BrokenPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping frame write test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping frame write test");
+        return;
+    };
+
+    let mut out = Vec::new();
+    match manager.capture_frame_write(&mut out) {
+        Ok((width, height)) => {
+            assert_eq!((width, height), manager.geometry());
+            let pixels = mapped_quads(&out, width * 4, width, height);
+            assert_mostly_equal(&pixels, &bgra_quads(&reference), "written frame");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Frame write timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Frame write failed: {e:?}"),
+    }
+
+    match manager.capture_frame_write(&mut BrokenPipe) {
+        Ok(_) => panic!("Writing to a broken pipe should fail"),
+        Err(CaptureError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+        Err(CaptureError::Timeout) => {
+            println!("Frame write timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Frame write failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_vsync() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping vsync capture test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping vsync capture test");
+        return;
+    };
+
+    match manager.capture_frame_vsync() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!((width, height), manager.geometry());
+            assert_mostly_equal(&pixels, &reference, "vsync frame");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Vsync capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Vsync capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_fast_into() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping fast capture into buffer test");
+            return;
+        }
+    };
+
+    // The buffer is resized to the frame, dropping its previous contents
+    let mut buf = vec![0xAB; 64];
+    for _ in 0..2 {
+        let Some((reference, _)) = reference_frame(&mut manager) else {
+            println!("No reference frame available - skipping fast capture into buffer test");
+            return;
+        };
+        match manager.capture_frame_fast_into(&mut buf) {
+            Ok((width, height)) => {
+                assert_eq!((width, height), manager.output_geometry());
+                assert_eq!(buf.len(), width * height * 4);
+                if is_unrotated(&manager) {
+                    let pixels = mapped_quads(&buf, width * 4, width, height);
+                    assert_mostly_equal(&pixels, &bgra_quads(&reference), "fast frame");
+                }
+            }
+            Err(CaptureError::Timeout) => {
+                println!("Fast capture timed out - this can happen if the screen is static");
+            }
+            Err(e) => println!("Fast capture into buffer failed: {e:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_capture_frame_borrowed() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping borrowed capture test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping borrowed capture test");
+        return;
+    };
+    let unrotated = is_unrotated(&manager);
+
+    match manager.capture_frame_borrowed() {
+        Ok(frame) => {
+            let (width, height) = frame.dimensions();
+            assert_eq!(frame.len(), width * height * 4);
+            if unrotated {
+                let pixels = mapped_quads(&frame, width * 4, width, height);
+                assert_mostly_equal(&pixels, &bgra_quads(&reference), "borrowed frame");
+            }
+            println!(
+                "Borrowed capture {}: {}x{}",
+                if frame.is_borrowed() {
+                    "zero-copy"
+                } else {
+                    "copied"
+                },
+                width,
+                height
+            );
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Borrowed capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Borrowed capture failed: {e:?}"),
+    }
+
+    // Dropping the frame unmaps the surface so capturing can continue
+    match manager.capture_frame_fast() {
+        Ok(_) | Err(CaptureError::Timeout) => {}
+        Err(e) => panic!("Capture after a borrowed frame failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_rows() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping row iterator test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping row iterator test");
+        return;
+    };
+    let unrotated = is_unrotated(&manager);
+
+    match manager.capture_frame_rows() {
+        Ok((rows, (width, height))) => {
+            assert_eq!(rows.len(), height);
+            let mut pixels = Vec::with_capacity(width * height);
+            for row in rows {
+                assert_eq!(row.len(), width);
+                pixels.extend_from_slice(row);
+            }
+            assert_eq!(pixels.len(), width * height);
+            if unrotated {
+                assert_mostly_equal(&pixels, &reference, "frame rows");
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Row capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Row capture failed: {e:?}"),
+    }
+
+    // The next capture unmaps the surface the rows were read from
+    match manager.capture_frame_fast() {
+        Ok(_) | Err(CaptureError::Timeout) => {}
+        Err(e) => panic!("Capture after iterating rows failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_to_sink() {
+    use dxgi_capture_rs::FrameSink;
+
+    struct CollectingSink {
+        rows: usize,
+        row_len: Option<usize>,
+        data: Vec<u8>,
+    }
+
+    impl FrameSink for CollectingSink {
+        fn row(&mut self, y: usize, row: &[u8]) {
+            assert_eq!(y, self.rows);
+            assert_eq!(*self.row_len.get_or_insert(row.len()), row.len());
+            self.data.extend_from_slice(row);
+            self.rows += 1;
+        }
+    }
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping frame sink test");
+            return;
+        }
+    };
+
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping frame sink test");
+        return;
+    };
+    let mut sink = CollectingSink {
+        rows: 0,
+        row_len: None,
+        data: Vec::new(),
+    };
+    match manager.capture_frame_to_sink(&mut sink) {
+        Ok((width, height)) => {
+            // Rows are upright, so they match the rotation-aware capture
+            assert_eq!((width, height), manager.geometry());
+            assert_eq!(sink.rows, height);
+            assert_eq!(sink.row_len, Some(width * 4));
+            let pixels = mapped_quads(&sink.data, width * 4, width, height);
+            assert_mostly_equal(&pixels, &bgra_quads(&reference), "sink rows");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Sink capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Sink capture failed: {e:?}"),
+    }
+
+    let mut bytes = 0;
+    match manager.capture_frame_to_sink(&mut |_y: usize, row: &[u8]| bytes += row.len()) {
+        Ok((width, height)) => assert_eq!(bytes, width * height * 4),
+        Err(CaptureError::Timeout) => {
+            println!("Sink capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Sink capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_metadata_clamp_to() {
+    use dxgi_capture_rs::{FrameMetadata, MoveRect};
+
+    let mut metadata = FrameMetadata::default();
+    metadata.last_present_time = 12345;
+    metadata.accumulated_frames = 1;
+    metadata.metadata_available = true;
+    metadata.dirty_rects = vec![
+        Rect::new(10, 10, 20, 20),     // Inside
+        Rect::new(-5, -5, 10, 10),     // Partially outside top-left
+        Rect::new(90, 40, 120, 60),    // Partially outside right and bottom
+        Rect::new(200, 200, 300, 300), // Entirely outside
+        Rect::new(30, 30, 20, 40),     // Inverted
+        Rect::new(5, 5, 5, 10),        // Zero width
+    ];
+    metadata.move_rects = vec![
+        MoveRect {
+            source_point: (0, 0),
+            destination_rect: Rect::new(10, 10, 20, 20),
+        },
+        MoveRect {
+            source_point: (-10, 0),
+            destination_rect: Rect::new(0, 0, 30, 10),
+        },
+        MoveRect {
+            source_point: (0, 0),
+            destination_rect: Rect::new(95, 45, 110, 55),
+        },
+        MoveRect {
+            source_point: (0, 0),
+            destination_rect: Rect::new(20, 20, 10, 10),
+        },
+    ];
+
+    metadata.clamp_to(100, 50);
+
+    assert_eq!(
+        metadata.dirty_rects,
+        vec![
+            Rect::new(10, 10, 20, 20),
+            Rect::new(0, 0, 10, 10),
+            Rect::new(90, 40, 100, 50)
+        ]
+    );
+    assert_eq!(
+        metadata.move_rects,
+        vec![
+            MoveRect {
+                source_point: (0, 0),
+                destination_rect: Rect::new(10, 10, 20, 20),
+            },
+            // Clipped so the source starts inside the frame
+            MoveRect {
+                source_point: (0, 0),
+                destination_rect: Rect::new(10, 0, 30, 10),
+            },
+            MoveRect {
+                source_point: (0, 0),
+                destination_rect: Rect::new(95, 45, 100, 50),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_split_into_output_capturers() {
+    let manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping split test");
+            return;
+        }
+    };
+
+    let capturers = match manager.split() {
+        Ok(capturers) => capturers,
+        Err(e) => {
+            println!("Split failed: {e:?}");
+            return;
+        }
+    };
+    assert!(!capturers.is_empty());
+
+    let threads: Vec<_> = capturers
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut capturer)| {
+            assert_eq!(capturer.index(), i);
+            std::thread::spawn(move || match capturer.capture_frame() {
+                Ok((pixels, (width, height))) => {
+                    let geometry = capturer.geometry().expect("Output should have a desc");
+                    assert_eq!((width, height), geometry);
+                    assert_eq!(pixels.len(), width * height);
+                }
+                Err(CaptureError::Timeout) => {}
+                Err(e) => println!("Output {} capture failed: {e:?}", capturer.index()),
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().expect("Capture thread panicked");
+    }
+}
+
+#[test]
+fn test_capture_frame_hashed() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping hashed capture test");
+            return;
+        }
+    };
+
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping hashed capture test");
+        return;
+    };
+    let (first_pixels, first_dimensions, first_hash) = match manager.capture_frame_hashed() {
+        Ok(frame) => frame,
+        Err(e) => {
+            println!("Hashed capture failed: {e:?}");
+            return;
+        }
+    };
+    assert_mostly_equal(&first_pixels, &reference, "hashed frame");
+
+    // A timeout here means the screen is static, so only compare real frames
+    if let Ok((second_pixels, second_dimensions, second_hash)) = manager.capture_frame_hashed()
+        && second_pixels == first_pixels
+        && second_dimensions == first_dimensions
+    {
+        assert_eq!(
+            first_hash, second_hash,
+            "Identical frames must hash equally"
+        );
+    }
+}
+
+#[test]
+fn test_composite_cursor() {
+    let mut manager = match DXGIManager::builder().composite_cursor(true).build() {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping cursor compositing test");
+            return;
+        }
+    };
+
+    assert!(manager.get_composite_cursor());
+
+    manager.set_composite_cursor(false);
+    assert!(!manager.get_composite_cursor());
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping cursor compositing test");
+        return;
+    };
+    manager.set_composite_cursor(true);
+
+    match manager.capture_frame_with_metadata() {
+        Ok((pixels, (width, _), metadata)) => {
+            // The pointer is drawn over the plain capture, which is unchanged elsewhere
+            let pointer = metadata
+                .pointer_position
+                .filter(|_| metadata.pointer_visible && is_unrotated(&manager));
+            let Some((x, y)) = pointer else {
+                assert_mostly_equal(&pixels, &reference, "frame without pointer");
+                return;
+            };
+            let near_pointer = |i: usize| {
+                let (px, py) = ((i % width) as i32, (i / width) as i32);
+                (x - 8..x + 256).contains(&px) && (y - 8..y + 256).contains(&py)
+            };
+            let (outside, outside_reference): (Vec<BGRA8>, Vec<BGRA8>) = pixels
+                .iter()
+                .zip(&reference)
+                .enumerate()
+                .filter(|(i, _)| !near_pointer(*i))
+                .map(|(_, pair)| pair)
+                .unzip();
+            assert_mostly_equal(&outside, &outside_reference, "frame away from pointer");
+            assert!(
+                (0..pixels.len()).any(|i| near_pointer(i) && pixels[i] != reference[i]),
+                "The visible pointer should be drawn into the frame"
+            );
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture with cursor failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_timeout_duration() {
+    use std::time::Duration;
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping timeout duration test");
+            return;
+        }
+    };
+
+    assert_eq!(manager.get_timeout(), Duration::from_millis(1000));
+
+    manager.set_timeout(Duration::from_millis(250));
+    assert_eq!(manager.get_timeout_ms(), 250);
+    assert_eq!(manager.get_timeout(), Duration::from_millis(250));
+
+    // Sub-millisecond parts are truncated
+    manager.set_timeout(Duration::from_micros(1500));
+    assert_eq!(manager.get_timeout_ms(), 1);
+
+    // Overlong timeouts saturate
+    manager.set_timeout(Duration::from_secs(u64::MAX));
+    assert_eq!(manager.get_timeout_ms(), u32::MAX);
+}
+
+#[test]
+fn test_capture_frame_raw() {
+    use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM;
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping raw capture test");
+            return;
+        }
+    };
+
+    match manager.capture_frame_raw() {
+        Ok(frame) => {
+            assert!(
+                frame.pitch >= frame.width * 4,
+                "Pitch must cover a full row"
+            );
+            assert_eq!(frame.data.len(), frame.pitch * frame.height);
+            assert_eq!(frame.format, DXGI_FORMAT_B8G8R8A8_UNORM);
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Raw capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Raw capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_described() {
+    use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM;
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping described capture test");
+            return;
+        }
+    };
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping described capture test");
+        return;
+    };
+
+    match manager.capture_frame_described() {
+        Ok((data, desc)) => {
+            assert!(desc.pitch >= desc.width * 4, "Pitch must cover a full row");
+            assert_eq!(data.len(), desc.pitch * desc.height);
+            assert_eq!((desc.width, desc.height), manager.output_geometry());
+            assert_eq!(desc.format, manager.current_format().unwrap());
+            assert_eq!(desc.rotation, manager.current_mode().unwrap().rotation);
+            assert_eq!(desc.color_space, manager.color_space().unwrap());
+            if desc.rotation == Rotation::Identity && desc.format == DXGI_FORMAT_B8G8R8A8_UNORM {
+                let pixels = mapped_quads(&data, desc.pitch, desc.width, desc.height);
+                assert_mostly_equal(&pixels, &bgra_quads(&reference), "described frame");
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Described capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Described capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_metadata_rapid_updates() {
+    let mut manager = match DXGIManager::new(100) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping rapid metadata test");
+            return;
+        }
+    };
+
+    // Back-to-back captures while the screen changes exercise the retry when
+    // the metadata buffer grows between the size query and the fetch
+    let mut total_rects = 0;
+    for _ in 0..30 {
+        match manager.capture_frame_with_metadata() {
+            Ok((_, _, metadata)) => {
+                for rect in &metadata.dirty_rects {
+                    assert!(
+                        rect.left <= rect.right && rect.top <= rect.bottom,
+                        "Malformed dirty rect"
+                    );
+                }
+                for move_rect in &metadata.move_rects {
+                    let rect = move_rect.destination_rect;
+                    assert!(
+                        rect.left <= rect.right && rect.top <= rect.bottom,
+                        "Malformed move rect"
+                    );
+                }
+                total_rects += metadata.total_change_count();
+            }
+            Err(CaptureError::Timeout) => {}
+            Err(e) => {
+                println!("Metadata capture failed: {e:?}");
+                break;
+            }
+        }
+    }
+
+    println!("Rapid metadata test saw {total_rects} rects in total");
+}
+
+#[test]
+fn test_acquire_retries() {
+    use dxgi_capture_rs::OutputDuplicationError;
+    use std::time::{Duration, Instant};
+
+    // No system has this many outputs, so every attempt fails with NoOutput
+    let start = Instant::now();
+    let result = DXGIManagerBuilder::new()
+        .capture_source_index(99)
+        .acquire_retries(2)
+        .acquire_retry_delay(Duration::from_millis(50))
+        .build();
+
+    match result {
+        Ok(_) => println!("Unexpectedly found output 99"),
+        Err(OutputDuplicationError::NoOutput) => {
+            assert!(
+                start.elapsed() >= Duration::from_millis(100),
+                "Both retries should have waited"
+            );
+        }
+        Err(e) => println!("DXGI not available - skipping retry test: {e:?}"),
+    }
+}
+
+#[test]
+fn test_output_count() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping output count test");
+            return;
+        }
+    };
+
+    let geometry = manager.geometry();
+    let count = manager.output_count();
+    assert!(count >= 1, "The captured output must be counted");
+
+    // Counting must not disturb the active duplication
+    assert_eq!(manager.geometry(), geometry);
+    match manager.capture_frame() {
+        Ok(_) | Err(CaptureError::Timeout) => {}
+        Err(e) => println!("Capture after counting outputs failed: {e:?}"),
+    }
+
+    use dxgi_capture_rs::OutputDuplicationError;
+
+    // Capture source indices count outputs across all adapters, like outputs()
+    let outputs = manager.outputs().expect("Outputs should be enumerable");
+    for output in &outputs {
+        match manager.set_capture_source_index(output.index) {
+            Ok(()) => assert_eq!(
+                manager.geometry(),
+                (
+                    output.desktop_rect.width() as usize,
+                    output.desktop_rect.height() as usize
+                ),
+                "Index {} should select {}",
+                output.index,
+                output.device_name
+            ),
+            Err(e) => println!("Could not select output {}: {e:?}", output.index),
+        }
+    }
+    assert!(matches!(
+        manager.set_capture_source_index(count),
+        Err(OutputDuplicationError::NoOutput)
+    ));
+
+    println!("Found {count} output(s)");
+}
+
+#[test]
+fn test_refresh_outputs() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping refresh outputs test");
+            return;
+        }
+    };
+
+    // Nothing changed since the manager acquired its output
+    match manager.refresh_outputs() {
+        Ok(changed) => assert!(!changed, "Topology should be unchanged"),
+        Err(e) => println!("Refreshing outputs failed: {e:?}"),
+    }
+
+    match manager.capture_frame() {
+        Ok(_) | Err(CaptureError::Timeout) => {}
+        Err(e) => println!("Capture after refresh failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_components_into_slice() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping slice capture test");
+            return;
+        }
+    };
+
+    let mut too_small = [0u8; 4];
+    match manager.capture_frame_components_into_slice(&mut too_small) {
+        Err(CaptureError::Fail(_)) => {}
+        Err(CaptureError::Timeout) => {
+            println!("Slice capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Slice capture failed: {e:?}"),
+        Ok(dimensions) => panic!("A 4-byte buffer cannot hold a {dimensions:?} frame"),
+    }
+
+    let (width, height) = manager.geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping slice capture test");
+        return;
+    };
+    let mut slot = vec![0u8; width * height * 4 + 16];
+    match manager.capture_frame_components_into_slice(&mut slot) {
+        Ok((w, h)) => {
+            assert_eq!(w * h, width * height, "Frame must fit the output size");
+            assert!(
+                slot[w * h * 4..].iter().all(|&b| b == 0),
+                "Bytes past the frame must be untouched"
+            );
+            let pixels = mapped_quads(&slot, w * 4, w, h);
+            assert_mostly_equal(&pixels, &bgra_quads(&reference), "frame in slice");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Slice capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Slice capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_capture_frame_into_ptr() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping pointer capture test");
+            return;
+        }
+    };
+
+    let result = unsafe { manager.capture_frame_into_ptr(std::ptr::null_mut(), 1 << 30) };
+    assert!(
+        matches!(result, Err(CaptureError::Fail(_))),
+        "A null pointer must be rejected, got {result:?}"
+    );
+
+    let (width, height) = manager.geometry();
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping pointer capture test");
+        return;
+    };
+    let mut region = vec![0u8; width * height * 4 + 16];
+    let result = unsafe { manager.capture_frame_into_ptr(region.as_mut_ptr(), region.len()) };
+    match result {
+        Ok((w, h)) => {
+            assert_eq!(w * h, width * height, "Frame must fit the output size");
+            assert!(
+                region[w * h * 4..].iter().all(|&b| b == 0),
+                "Bytes past the frame must be untouched"
+            );
+            let pixels = mapped_quads(&region, w * 4, w, h);
+            assert_mostly_equal(&pixels, &bgra_quads(&reference), "frame at pointer");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Pointer capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Pointer capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_acquire_frame_guard() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping frame guard test");
+            return;
+        }
+    };
+
+    match manager.acquire_frame() {
+        Ok(mut frame) => {
+            let metadata = frame.metadata().expect("Metadata of a held frame");
+            assert_eq!(
+                metadata.total_change_count(),
+                metadata.dirty_rects.len() + metadata.move_rects.len()
+            );
+
+            let (pixels, (width, height)) = frame.map().expect("Mapping a held frame");
+            assert_eq!(pixels.len(), width * height);
+            // Both mappings read the same held frame
+            let (components, dimensions) = frame.map_components().expect("Mapping again");
+            assert_eq!(dimensions, (width, height));
+            let components: Vec<[u8; 4]> = components
+                .chunks_exact(4)
+                .map(|p| [p[0], p[1], p[2], p[3]])
+                .collect();
+            assert_eq!(components, bgra_quads(&pixels));
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Frame acquisition timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Frame acquisition failed: {e:?}"),
+    }
+
+    // The guard released its frame, so acquiring again must not fail with
+    // DXGI_ERROR_INVALID_CALL
+    match manager.capture_frame() {
+        Ok(_) | Err(CaptureError::Timeout) => {}
+        Err(CaptureError::Fail(e)) => panic!("Capture after dropping the guard failed: {e:?}"),
+        Err(e) => println!("Capture after dropping the guard failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_duplication_formats() {
+    use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM;
+
+    let mut manager = match DXGIManagerBuilder::new()
+        .duplication_formats(&[DXGI_FORMAT_B8G8R8A8_UNORM])
+        .build()
+    {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping duplication formats test");
+            return;
+        }
+    };
+
+    // Whether DuplicateOutput1 or the fallback was used, the only requested
+    // format is also the default one
+    match manager.capture_frame_raw() {
+        Ok(frame) => assert_eq!(frame.format, DXGI_FORMAT_B8G8R8A8_UNORM),
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture with duplication formats failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_metadata_flags() {
+    use dxgi_capture_rs::MetadataFlags;
+
+    assert!(MetadataFlags::ALL.contains(MetadataFlags::POINTER));
+    assert!(MetadataFlags::ALL.contains(MetadataFlags::RECTS));
+    assert_eq!(
+        MetadataFlags::DIRTY_RECTS | MetadataFlags::MOVE_RECTS,
+        MetadataFlags::RECTS
+    );
+    assert!(!MetadataFlags::POINTER.contains(MetadataFlags::DIRTY_RECTS));
+    assert!(MetadataFlags::POINTER.contains(MetadataFlags::NONE));
+    assert_eq!(MetadataFlags::default(), MetadataFlags::ALL);
+
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping metadata flags capture test");
+            return;
+        }
+    };
+
+    match manager.capture_frame_with_metadata_opts(MetadataFlags::POINTER) {
+        Ok((_, _, metadata)) => {
+            assert!(
+                metadata.dirty_rects.is_empty(),
+                "Dirty rects were not requested"
+            );
+            assert!(
+                metadata.move_rects.is_empty(),
+                "Move rects were not requested"
+            );
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture with metadata flags failed: {e:?}"),
+    }
+
+    match manager.capture_frame_with_metadata_opts(MetadataFlags::RECTS) {
+        Ok((_, _, metadata)) => {
+            assert!(!metadata.pointer_visible, "Pointer was not requested");
+            assert_eq!(metadata.pointer_position, None);
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture with metadata flags failed: {e:?}"),
+    }
+}
+
+/// Builds a `width` x `height` BGRA image with rows `pitch` bytes apart where
+/// each pixel holds its own coordinates, trimmed after the last pixel so that
+/// any read past the image panics.
+fn coordinate_image(width: usize, height: usize, pitch: usize) -> Vec<u8> {
+    let mut image = vec![0xEE; pitch * height];
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * pitch + x * 4;
+            image[index..index + 4].copy_from_slice(&[x as u8, y as u8, 0x42, 0xFF]);
+        }
+    }
+    image.truncate(pitch * (height - 1) + width * 4);
+    image
+}
+
+#[test]
+fn test_copy_rotated_odd_resolution() {
+    // An odd width with a padded pitch, like 1366 pixel wide modes
+    let (width, height, pitch) = (5, 3, 32);
+    let source = coordinate_image(width, height, pitch);
+
+    // Maps each output pixel back to the source pixel it must come from
+    type SourceOf = fn(usize, usize) -> (usize, usize);
+    let cases: [(Rotation, (usize, usize), SourceOf); 5] = [
+        (Rotation::Identity, (5, 3), |x, y| (x, y)),
+        (Rotation::Unspecified, (5, 3), |x, y| (x, y)),
+        (Rotation::Rotate90, (3, 5), |x, y| (y, 2 - x)),
+        (Rotation::Rotate180, (5, 3), |x, y| (4 - x, 2 - y)),
+        (Rotation::Rotate270, (3, 5), |x, y| (4 - y, x)),
+    ];
+
+    let mut out = Vec::new();
+    for (rotation, expected, source_of) in cases {
+        let (out_width, out_height) =
+            copy_rotated(&source, pitch, width, height, rotation, &mut out);
+        assert_eq!((out_width, out_height), expected, "{rotation:?}");
+        assert_eq!(out.len(), width * height * 4);
+
+        for y in 0..out_height {
+            for x in 0..out_width {
+                let (source_x, source_y) = source_of(x, y);
+                let index = (y * out_width + x) * 4;
+                assert_eq!(
+                    out[index..index + 4],
+                    [source_x as u8, source_y as u8, 0x42, 0xFF],
+                    "{rotation:?} at ({x}, {y})"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_copy_rotated_known_pattern() {
+    // A 4x3 image whose pixels are numbered 0 to 11 in row-major order:
+    //  0  1  2  3
+    //  4  5  6  7
+    //  8  9 10 11
+    let (width, height, pitch) = (4, 3, 16);
+    let source: Vec<u8> = (0..12u8).flat_map(|n| [n; 4]).collect();
+
+    let cases = [
+        (
+            Rotation::Identity,
+            (4, 3),
+            vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        ),
+        (
+            Rotation::Rotate90,
+            (3, 4),
+            vec![8, 4, 0, 9, 5, 1, 10, 6, 2, 11, 7, 3],
+        ),
+        (
+            Rotation::Rotate180,
+            (4, 3),
+            vec![11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+        ),
+        (
+            Rotation::Rotate270,
+            (3, 4),
+            vec![3, 7, 11, 2, 6, 10, 1, 5, 9, 0, 4, 8],
+        ),
+    ];
+
+    let mut out = Vec::new();
+    for (rotation, dimensions, expected) in cases {
+        assert_eq!(
+            copy_rotated(&source, pitch, width, height, rotation, &mut out),
+            dimensions,
+            "{rotation:?}"
+        );
+        let numbers: Vec<u8> = out.chunks_exact(4).map(|pixel| pixel[0]).collect();
+        assert_eq!(numbers, expected, "{rotation:?}");
+    }
+}
+
+#[test]
+fn test_copy_rotated_flipped() {
+    // The same numbered 4x3 image as in the known pattern test, padded
+    let (width, height, pitch) = (4, 3, 20);
+    let source: Vec<u8> = (0..height as u8)
+        .flat_map(|y| {
+            (0..width as u8)
+                .flat_map(move |x| [y * 4 + x; 4])
+                .chain([0xEE; 4])
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    let dimensions = copy_rotated_flipped(
+        &source,
+        pitch,
+        width,
+        height,
+        Rotation::Rotate90,
+        true,
+        &mut out,
+    );
+    assert_eq!(dimensions, (3, 4));
+    let pixels: Vec<u8> = out.chunks_exact(4).map(|pixel| pixel[0]).collect();
+    assert_eq!(pixels, [11, 7, 3, 10, 6, 2, 9, 5, 1, 8, 4, 0]);
+
+    // Flipping applies after rotation: the rows of the rotated image reversed
+    for rotation in [
+        Rotation::Identity,
+        Rotation::Rotate90,
+        Rotation::Rotate180,
+        Rotation::Rotate270,
+    ] {
+        let mut upright = Vec::new();
+        let (out_width, _) = copy_rotated(&source, pitch, width, height, rotation, &mut upright);
+        let mut flipped = Vec::new();
+        copy_rotated_flipped(&source, pitch, width, height, rotation, true, &mut flipped);
+        let expected: Vec<u8> = upright
+            .chunks(out_width * 4)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        assert_eq!(flipped, expected, "{rotation:?}");
+
+        let mut unflipped = Vec::new();
+        copy_rotated_flipped(
+            &source,
+            pitch,
+            width,
+            height,
+            rotation,
+            false,
+            &mut unflipped,
+        );
+        assert_eq!(unflipped, upright, "{rotation:?}");
+    }
+}
+
+#[test]
+fn test_flip_vertical_capture() {
+    let mut manager = match DXGIManager::builder()
+        .timeout_ms(1000)
+        .flip_vertical(true)
+        .build()
+    {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping flip test");
+            return;
+        }
+    };
+    assert!(manager.get_flip_vertical());
+
+    manager.set_flip_vertical(false);
+    assert!(!manager.get_flip_vertical());
+    let Some((reference, _)) = reference_frame(&mut manager) else {
+        println!("No reference frame available - skipping flip test");
+        return;
+    };
+    manager.set_flip_vertical(true);
+
+    match manager.capture_frame() {
+        Ok((pixels, (width, _))) => {
+            // Flipped rows are the rows of the upright capture in reverse order
+            let expected: Vec<BGRA8> = reference.rchunks_exact(width).flatten().copied().collect();
+            assert_mostly_equal(&pixels, &expected, "flipped frame");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Flipped capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Flipped capture failed: {e:?}"),
+    }
+
+    // The fast path is never flipped, even where it falls back to the
+    // rotation-aware copy
+    if manager.acquire_output_duplication().is_err() {
+        return;
+    }
+    match manager.capture_frame_fast_rotated() {
+        Ok((components, (width, height))) => {
+            let pixels = mapped_quads(&components, width * 4, width, height);
+            assert_mostly_equal(&pixels, &bgra_quads(&reference), "fast rotated frame");
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Fast capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Fast capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_copy_rotated_stays_in_bounds() {
+    // 1366 * 4 bytes is not a multiple of the 64-byte pitch alignment
+    let (width, height, pitch) = (1366, 768, 5504);
+    let source = vec![0x7F; pitch * (height - 1) + width * 4];
+    let mut out = Vec::new();
+
+    for rotation in [
+        Rotation::Identity,
+        Rotation::Rotate90,
+        Rotation::Rotate180,
+        Rotation::Rotate270,
+    ] {
+        copy_rotated(&source, pitch, width, height, rotation, &mut out);
+        assert_eq!(out.len(), width * height * 4);
+        assert!(out.iter().all(|&b| b == 0x7F), "{rotation:?} left gaps");
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_copy_rotated_short_source_panics() {
+    let (width, height, pitch) = (5, 3, 32);
+    let mut source = coordinate_image(width, height, pitch);
+    source.pop();
+    copy_rotated(
+        &source,
+        pitch,
+        width,
+        height,
+        Rotation::Rotate90,
+        &mut Vec::new(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "overflows")]
+fn test_copy_rotated_overflowing_size_panics() {
+    // The byte size overflows before the short source is even looked at
+    copy_rotated(
+        &[],
+        usize::MAX,
+        usize::MAX / 2,
+        3,
+        Rotation::Identity,
+        &mut Vec::new(),
+    );
+}
+
+#[test]
+fn test_max_frame_pixels() {
+    let mut manager = match DXGIManager::builder().max_frame_pixels(16).build() {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping max frame size test");
+            return;
+        }
+    };
+    assert_eq!(manager.get_max_frame_pixels(), 16);
+
+    match manager.capture_frame() {
+        Err(CaptureError::FrameTooLarge { width, height }) => {
+            assert_eq!((width, height), manager.output_geometry());
+        }
+        Err(CaptureError::Timeout) => println!("Capture timed out - acceptable"),
+        Ok((_, dimensions)) => panic!("A {dimensions:?} frame should be rejected"),
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+
+    manager.set_max_frame_pixels(usize::MAX);
+    match manager.capture_frame() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!((width, height), manager.frame_geometry());
+            assert_eq!(pixels.len(), width * height);
+        }
+        Err(CaptureError::Timeout) => println!("Capture timed out - acceptable"),
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_frame_size_matches_desktop_not_texture() {
+    let mut manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping texture size test");
+            return;
+        }
+    };
+
+    match manager.capture_frame_fast() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!((width, height), manager.output_geometry());
+            assert_eq!(pixels.len(), width * height * 4);
+
+            let (texture_width, texture_height) = manager
+                .texture_dimensions()
+                .expect("Texture size is known after a capture");
+            assert!(texture_width >= width && texture_height >= height);
+            if (texture_width, texture_height) != (width, height) {
+                println!("Desktop texture is padded to {texture_width}x{texture_height}");
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+
+    match manager.capture_frame() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!((width, height), manager.frame_geometry());
+            assert_eq!(pixels.len(), width * height);
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture failed: {e:?}"),
+    }
+}
+
+#[test]
+fn test_min_change_fraction() {
+    let mut manager = match DXGIManager::builder()
+        .timeout_ms(500)
+        .min_change_fraction(0.01)
+        .build()
+    {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping change threshold test");
+            return;
+        }
+    };
+    assert_eq!(manager.get_min_change_fraction(), 0.01);
+
+    match manager.capture_on_change(std::time::Duration::from_millis(500)) {
+        Ok((pixels, (width, height), metadata)) => {
+            assert_eq!(pixels.len(), width * height);
+            assert!(metadata.has_updates());
+        }
+        Err(CaptureError::Timeout) => {
+            println!("No frame above the threshold - this can happen if the screen is static");
+        }
+        Err(e) => println!("Thresholded capture failed: {e:?}"),
+    }
+
+    match manager.capture_frame_with_metadata() {
+        Ok((_, _, metadata)) => assert!(metadata.has_updates()),
+        Err(CaptureError::Timeout) => {
+            println!("No frame above the threshold - this can happen if the screen is static");
+        }
+        Err(e) => println!("Thresholded capture failed: {e:?}"),
+    }
+
+    manager.set_min_change_fraction(2.0);
+    assert_eq!(manager.get_min_change_fraction(), 1.0);
+    manager.set_min_change_fraction(-1.0);
+    assert_eq!(manager.get_min_change_fraction(), 0.0);
+    manager.set_min_change_fraction(0.5);
+    manager.set_min_change_fraction(f32::NAN);
+    assert_eq!(manager.get_min_change_fraction(), 0.0);
+    drop(manager);
+
+    if let Ok(manager) = DXGIManager::builder().min_change_fraction(f32::NAN).build() {
+        assert_eq!(manager.get_min_change_fraction(), 0.0);
+    }
+}
+
+#[test]
+fn test_capture_all_sources() {
+    let mut manager = match DXGIManager::new(200) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping round-robin test");
+            return;
+        }
+    };
+    let outputs = manager.outputs().unwrap_or_default();
+
+    for _ in 0..2 {
+        match manager.capture_all_sources() {
+            Ok(frames) => {
+                assert!(frames.len() <= outputs.len());
+                assert!(frames.windows(2).all(|pair| pair[0].0 < pair[1].0));
+                for (index, result) in frames {
+                    match result {
+                        Ok((pixels, (width, height))) => {
+                            // Each frame has the size of its own output
+                            let rect = outputs[index].desktop_rect;
+                            assert_eq!(
+                                (width, height),
+                                (rect.width() as usize, rect.height() as usize)
+                            );
+                            assert_eq!(pixels.len(), width * height);
+                        }
+                        Err(CaptureError::Timeout) => {
+                            println!("Output {index} timed out - acceptable in test environment");
+                        }
+                        Err(e) => println!("Output {index} failed: {e:?}"),
+                    }
+                }
+            }
+            Err(e) => println!("Round-robin capture failed: {e:?}"),
+        }
+    }
+
+    // Switching back to single-source capture re-acquires the duplication
+    match manager.capture_frame() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!((width, height), manager.frame_geometry());
+            assert_eq!(pixels.len(), width * height);
+        }
+        Err(e) => println!("Single-source capture after round-robin: {e:?}"),
+    }
+}
+
+#[test]
+fn test_premultiply_alpha() {
+    let pixel = |b, g, r, a| BGRA8 { b, g, r, a };
+    let mut pixels = [
+        pixel(10, 20, 30, 255),
+        pixel(10, 20, 30, 0),
+        pixel(255, 255, 255, 128),
+        pixel(200, 100, 50, 51),
+        pixel(1, 254, 127, 1),
+    ];
+    premultiply_alpha(&mut pixels);
+
+    assert_eq!(
+        pixels,
+        [
+            pixel(10, 20, 30, 255),
+            pixel(0, 0, 0, 0),
+            pixel(128, 128, 128, 128),
+            pixel(40, 20, 10, 51),
+            pixel(0, 0, 0, 1),
+        ]
+    );
+}
+
+#[test]
+fn test_premultiply_alpha_excludes_force_opaque() {
+    // The pointer is drawn before premultiplying, so it is premultiplied too
+    let builder = DXGIManagerBuilder::new()
+        .force_opaque_alpha(true)
+        .premultiply_alpha(true)
+        .composite_cursor(true);
+    let mut manager = match builder.build() {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping premultiplied alpha test");
+            return;
+        }
+    };
+    assert!(manager.get_premultiply_alpha());
+    assert!(!manager.get_force_opaque_alpha());
+
+    match manager.capture_frame() {
+        Ok((pixels, (width, height))) => {
+            assert_eq!(pixels.len(), width * height);
+            assert!(
+                pixels
+                    .iter()
+                    .all(|p| p.b <= p.a && p.g <= p.a && p.r <= p.a)
+            );
+        }
+        Err(CaptureError::Timeout) => {
+            println!("Premultiplied capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Premultiplied capture failed: {e:?}"),
+    }
+
+    match manager.capture_frame_u16() {
+        Ok((pixels, _)) => assert!(
+            pixels
+                .iter()
+                .all(|p| p[0] <= p[3] && p[1] <= p[3] && p[2] <= p[3])
+        ),
+        Err(CaptureError::Timeout) => {
+            println!("Premultiplied capture timed out - this can happen if the screen is static");
+        }
+        Err(e) => println!("Premultiplied 16-bit capture failed: {e:?}"),
+    }
+
+    manager.set_force_opaque_alpha(true);
+    assert!(manager.get_force_opaque_alpha());
+    assert!(!manager.get_premultiply_alpha());
+}
+
+#[test]
+fn test_to_global_to_local_round_trip() {
+    let manager = match DXGIManager::new(1000) {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping coordinate mapping test");
+            return;
+        }
+    };
+
+    let origin = manager.to_global(0, 0);
+    assert_eq!(manager.to_local(origin.0, origin.1), (0, 0));
+    for (x, y) in [(10, 20), (-5, 7), (1919, 1079)] {
+        let (global_x, global_y) = manager.to_global(x, y);
+        assert_eq!((global_x - origin.0, global_y - origin.1), (x, y));
+        assert_eq!(manager.to_local(global_x, global_y), (x, y));
+    }
+}
+
+#[test]
+fn test_max_rects() {
+    let mut manager = match DXGIManager::builder().timeout_ms(500).max_rects(1).build() {
+        Ok(m) => m,
+        Err(_) => {
+            println!("DXGI not available - skipping max rects test");
+            return;
+        }
+    };
+    assert_eq!(manager.get_max_rects(), 1);
+
+    match manager.capture_frame_with_metadata() {
+        Ok((_, (width, height), metadata)) => {
+            assert!(metadata.dirty_rects.len() <= 1);
+            assert!(metadata.move_rects.len() <= 1);
+            if metadata.rects_truncated {
+                assert!(metadata.requires_full_update());
+                assert!(metadata.move_rects.is_empty());
+                let rect = metadata.dirty_rects[0];
+                assert_eq!((rect.left, rect.top), (0, 0));
+                assert!(rect.width() as usize >= width.min(height));
+            }
+        }
+        Err(CaptureError::Timeout) => {
+            println!("No frame available - this can happen if the screen is static");
+        }
+        Err(e) => println!("Capture with max rects failed: {e:?}"),
+    }
+
+    manager.set_max_rects(usize::MAX);
+    assert_eq!(manager.get_max_rects(), usize::MAX);
+}